@@ -0,0 +1,24 @@
+#version 450
+
+// Adds the (blurred) bright parts of the image back on top of the original.
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D u_input;
+layout(set = 0, binding = 1) uniform sampler u_input_sampler;
+
+// u_params[0] -> (intensity, unused, unused, unused)
+layout(set = 1, binding = 0) uniform EffectParams {
+    vec4 u_params[4];
+};
+
+layout(set = 2, binding = 0) uniform texture2D u_bloom;
+layout(set = 2, binding = 1) uniform sampler u_bloom_sampler;
+
+void main() {
+    vec4 color = texture(sampler2D(u_input, u_input_sampler), v_tex_coords);
+    vec3 bloom = texture(sampler2D(u_bloom, u_bloom_sampler), v_tex_coords).rgb;
+
+    f_color = vec4(color.rgb + bloom * u_params[0].x, color.a);
+}
@@ -0,0 +1,23 @@
+#version 450
+
+// Keeps only the parts of the image brighter than the threshold.
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D u_input;
+layout(set = 0, binding = 1) uniform sampler u_input_sampler;
+
+// u_params[0] -> (threshold, unused, unused, unused)
+layout(set = 1, binding = 0) uniform EffectParams {
+    vec4 u_params[4];
+};
+
+void main() {
+    vec4 color = texture(sampler2D(u_input, u_input_sampler), v_tex_coords);
+
+    float brightness = max(color.r, max(color.g, color.b));
+    float contribution = max(brightness - u_params[0].x, 0.0) / max(brightness, 0.0001);
+
+    f_color = vec4(color.rgb * contribution, 1.0);
+}
@@ -0,0 +1,35 @@
+#version 450
+
+// Separable gaussian blur. Run once horizontally and once vertically.
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D u_input;
+layout(set = 0, binding = 1) uniform sampler u_input_sampler;
+
+// u_params[0] -> (direction.x, direction.y, radius in pixels, unused)
+layout(set = 1, binding = 0) uniform EffectParams {
+    vec4 u_params[4];
+};
+
+const int SAMPLES = 8;
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(u_input, u_input_sampler), 0));
+    vec2 dir = u_params[0].xy * texel;
+    float radius = max(u_params[0].z, 0.0);
+    float sigma = max(radius * 0.5, 0.0001);
+
+    vec4 sum = vec4(0.0);
+    float weight_sum = 0.0;
+
+    for (int i = -SAMPLES; i <= SAMPLES; i++) {
+        float x = float(i) / float(SAMPLES) * radius;
+        float w = exp(-(x * x) / (2.0 * sigma * sigma));
+        sum += texture(sampler2D(u_input, u_input_sampler), v_tex_coords + dir * x) * w;
+        weight_sum += w;
+    }
+
+    f_color = sum / weight_sum;
+}
@@ -0,0 +1,47 @@
+#version 450
+
+// Color grading through a lookup table (LUT).
+//
+// The LUT is a strip of `size` square slices, each `size` x `size` pixels, laid out horizontally
+// (so the texture is `size * size` pixels wide and `size` pixels tall). Red increases left to right
+// inside each slice, green increases top to bottom, and blue selects the slice.
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D u_input;
+layout(set = 0, binding = 1) uniform sampler u_input_sampler;
+
+// u_params[0] -> (LUT size, strength, unused, unused)
+layout(set = 1, binding = 0) uniform EffectParams {
+    vec4 u_params[4];
+};
+
+layout(set = 2, binding = 0) uniform texture2D u_lut;
+layout(set = 2, binding = 1) uniform sampler u_lut_sampler;
+
+vec2 lut_coords(vec3 c, float slice, float size) {
+    return vec2(
+        (slice * size + c.r * (size - 1.0) + 0.5) / (size * size),
+        (c.g * (size - 1.0) + 0.5) / size
+    );
+}
+
+void main() {
+    vec4 color = texture(sampler2D(u_input, u_input_sampler), v_tex_coords);
+
+    float size = u_params[0].x;
+    float strength = u_params[0].y;
+
+    vec3 c = clamp(color.rgb, 0.0, 1.0);
+
+    float blue = c.b * (size - 1.0);
+    float slice_0 = floor(blue);
+    float slice_1 = min(slice_0 + 1.0, size - 1.0);
+
+    vec3 graded_0 = texture(sampler2D(u_lut, u_lut_sampler), lut_coords(c, slice_0, size)).rgb;
+    vec3 graded_1 = texture(sampler2D(u_lut, u_lut_sampler), lut_coords(c, slice_1, size)).rgb;
+    vec3 graded = mix(graded_0, graded_1, blue - slice_0);
+
+    f_color = vec4(mix(color.rgb, graded, strength), color.a);
+}
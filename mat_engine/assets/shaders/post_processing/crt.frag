@@ -0,0 +1,43 @@
+#version 450
+
+// CRT monitor look: barrel distortion, scanlines and slight chromatic aberration.
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D u_input;
+layout(set = 0, binding = 1) uniform sampler u_input_sampler;
+
+// u_params[0] -> (curvature, scanline intensity, scanline count, chromatic aberration in pixels)
+layout(set = 1, binding = 0) uniform EffectParams {
+    vec4 u_params[4];
+};
+
+void main() {
+    float curvature = u_params[0].x;
+    float scanline_intensity = u_params[0].y;
+    float scanline_count = u_params[0].z;
+    float aberration = u_params[0].w;
+
+    // Barrel distortion, in [-1, 1] space.
+    vec2 centered = v_tex_coords * 2.0 - 1.0;
+    centered *= 1.0 + curvature * dot(centered, centered);
+    vec2 uv = (centered + 1.0) * 0.5;
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        f_color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(u_input, u_input_sampler), 0));
+    vec2 offset = vec2(aberration * texel.x, 0.0);
+
+    vec4 color = texture(sampler2D(u_input, u_input_sampler), uv);
+    color.r = texture(sampler2D(u_input, u_input_sampler), uv + offset).r;
+    color.b = texture(sampler2D(u_input, u_input_sampler), uv - offset).b;
+
+    float scanline = 0.5 + 0.5 * sin(uv.y * scanline_count * 6.28318531);
+    color.rgb *= 1.0 - scanline_intensity * scanline;
+
+    f_color = color;
+}
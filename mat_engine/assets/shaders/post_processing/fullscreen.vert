@@ -0,0 +1,17 @@
+#version 450
+
+// Draws a single triangle covering the whole screen, without any vertex buffers.
+// Vertices are (-1, -1), (3, -1) and (-1, 3), in counter clockwise order.
+
+layout(location=0) out vec2 v_tex_coords;
+
+void main() {
+    vec2 pos = vec2(
+        float((gl_VertexIndex & 1) << 2) - 1.0,
+        float((gl_VertexIndex & 2) << 1) - 1.0
+    );
+
+    // Texture coordinates have (0, 0) at the top left.
+    v_tex_coords = vec2((pos.x + 1.0) * 0.5, 1.0 - (pos.y + 1.0) * 0.5);
+    gl_Position = vec4(pos, 0.0, 1.0);
+}
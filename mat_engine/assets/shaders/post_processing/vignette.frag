@@ -0,0 +1,28 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D u_input;
+layout(set = 0, binding = 1) uniform sampler u_input_sampler;
+
+// u_params[0] -> (intensity, radius, softness, unused)
+// u_params[1] -> (color.r, color.g, color.b, unused)
+layout(set = 1, binding = 0) uniform EffectParams {
+    vec4 u_params[4];
+};
+
+void main() {
+    vec4 color = texture(sampler2D(u_input, u_input_sampler), v_tex_coords);
+
+    float intensity = u_params[0].x;
+    float radius = u_params[0].y;
+    float softness = u_params[0].z;
+
+    // 0 at the center of the screen, 1 at the corners.
+    float dist = length(v_tex_coords - vec2(0.5)) * 1.41421356;
+    float visible = 1.0 - smoothstep(radius, radius + softness, dist);
+
+    color.rgb = mix(u_params[1].rgb, color.rgb, mix(1.0, visible, intensity));
+    f_color = color;
+}
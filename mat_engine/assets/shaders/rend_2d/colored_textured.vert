@@ -0,0 +1,21 @@
+#version 450
+
+layout(location=0) in vec2 a_position;
+layout(location=1) in vec2 a_tex_coords;
+layout(location=2) in vec4 a_color;
+
+layout(location=3) in mat4 a_model_mat; // Occupies locations 3 through 6 inclusive.
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec4 v_color;
+
+layout(set=1, binding=0) 
+uniform Camera {
+    mat4 u_proj_mat;
+};
+
+void main() {
+    v_tex_coords = a_tex_coords;
+    v_color = a_color;
+    gl_Position = u_proj_mat * a_model_mat * vec4(a_position, 0.0, 1.0);
+}
@@ -0,0 +1,125 @@
+#version 450
+
+// Adds the contribution of a single light to the light accumulation buffer.
+
+// Must match `GpuLight` in rendering/rend_2d/lighting.rs.
+struct Light {
+    vec2 position;
+    float radius;
+    float height;
+    vec3 color;
+    float falloff;
+    vec2 spot_direction;
+    float spot_cos_inner;
+    float spot_cos_outer;
+    uint first_segment;
+    uint segment_count;
+    uint shadow_mode;
+    float source_radius;
+};
+
+#define SHADOWS_NONE 0
+#define SHADOWS_HARD 1
+#define SHADOWS_SOFT 2
+
+// Rays cast towards the light's disk, for soft shadows.
+#define SOFT_SHADOW_SAMPLES 8
+
+layout(location=0) in vec2 v_world_position;
+layout(location=1) in vec2 v_screen_tex_coords;
+layout(location=2) flat in uint v_light_index;
+
+layout(location=0) out vec4 f_color;
+
+// Normal buffer. (0.5, 0.5, 1.0) faces the camera.
+layout(set=0, binding=0) uniform texture2D u_normals;
+layout(set=0, binding=1) uniform sampler u_normals_sampler;
+
+layout(std430, set=2, binding=0) readonly buffer Lights {
+    Light lights[];
+};
+
+// Occluder edges, from (x, y) to (z, w).
+layout(std430, set=2, binding=1) readonly buffer Segments {
+    vec4 segments[];
+};
+
+// Whether the segments from p to q and from a to b cross.
+bool intersects(vec2 p, vec2 q, vec2 a, vec2 b) {
+    vec2 r = q - p;
+    vec2 s = b - a;
+    float denom = r.x * s.y - r.y * s.x;
+    if (abs(denom) < 1e-8) {
+        return false;
+    }
+
+    vec2 ap = a - p;
+    float t = (ap.x * s.y - ap.y * s.x) / denom;
+    float u = (ap.x * r.y - ap.y * r.x) / denom;
+    return t > 0.0 && t < 1.0 && u >= 0.0 && u <= 1.0;
+}
+
+bool is_occluded(vec2 p, vec2 q, Light light) {
+    for (uint i = light.first_segment; i < light.first_segment + light.segment_count; i++) {
+        if (intersects(p, q, segments[i].xy, segments[i].zw)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Fraction of the light that reaches p, from 0.0 (fully shadowed) to 1.0.
+float visibility(vec2 p, Light light) {
+    if (light.shadow_mode == SHADOWS_NONE || light.segment_count == 0) {
+        return 1.0;
+    }
+
+    if (light.shadow_mode == SHADOWS_HARD) {
+        return is_occluded(p, light.position, light) ? 0.0 : 1.0;
+    }
+
+    // Soft shadows: the light is a disk, sampled across its diameter, perpendicular to p.
+    vec2 to_light = light.position - p;
+    vec2 across = normalize(vec2(-to_light.y, to_light.x) + 1e-8) * light.source_radius;
+
+    float visible = 0.0;
+    for (int i = 0; i < SOFT_SHADOW_SAMPLES; i++) {
+        float offset = (float(i) + 0.5) / float(SOFT_SHADOW_SAMPLES) * 2.0 - 1.0;
+        if (!is_occluded(p, light.position + across * offset, light)) {
+            visible += 1.0;
+        }
+    }
+    return visible / float(SOFT_SHADOW_SAMPLES);
+}
+
+void main() {
+    Light light = lights[v_light_index];
+
+    vec2 to_light = light.position - v_world_position;
+    float dist = length(to_light);
+    if (dist >= light.radius) {
+        discard;
+    }
+
+    float attenuation = pow(1.0 - dist / light.radius, light.falloff);
+
+    // Point lights have cones wide enough for this to always be 1.0.
+    vec2 from_light = dist > 0.0 ? -to_light / dist : light.spot_direction;
+    float cone = smoothstep(
+        light.spot_cos_outer,
+        light.spot_cos_inner,
+        dot(from_light, light.spot_direction)
+    );
+
+    vec3 normal = texture(sampler2D(u_normals, u_normals_sampler), v_screen_tex_coords).rgb;
+    normal = normalize(normal * 2.0 - 1.0);
+    vec3 light_direction = normalize(vec3(to_light, light.height));
+    float diffuse = max(dot(normal, light_direction), 0.0);
+
+    float amount = attenuation * cone * diffuse;
+    if (amount <= 0.0) {
+        discard;
+    }
+
+    f_color = vec4(light.color * amount * visibility(v_world_position, light), 0.0);
+}
@@ -0,0 +1,52 @@
+#version 450
+
+// Draws each light (one instance per light) as a quad covering its radius, without any vertex
+// buffers.
+
+// Must match `GpuLight` in rendering/rend_2d/lighting.rs.
+struct Light {
+    vec2 position;
+    float radius;
+    float height;
+    vec3 color;
+    float falloff;
+    vec2 spot_direction;
+    float spot_cos_inner;
+    float spot_cos_outer;
+    uint first_segment;
+    uint segment_count;
+    uint shadow_mode;
+    float source_radius;
+};
+
+layout(location=0) out vec2 v_world_position;
+layout(location=1) out vec2 v_screen_tex_coords;
+layout(location=2) flat out uint v_light_index;
+
+layout(set=1, binding=0)
+uniform Camera {
+    mat4 u_proj_mat;
+};
+
+layout(std430, set=2, binding=0) readonly buffer Lights {
+    Light lights[];
+};
+
+// Two counter clockwise triangles.
+const vec2 CORNERS[6] = vec2[6](
+    vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+    vec2(-1.0, -1.0), vec2(1.0, 1.0), vec2(-1.0, 1.0)
+);
+
+void main() {
+    Light light = lights[gl_InstanceIndex];
+
+    vec2 world_position = light.position + CORNERS[gl_VertexIndex] * light.radius;
+    vec4 clip_position = u_proj_mat * vec4(world_position, 0.0, 1.0);
+
+    v_world_position = world_position;
+    // Texture coordinates have (0, 0) at the top left.
+    v_screen_tex_coords = vec2(clip_position.x + 1.0, 1.0 - clip_position.y) * 0.5;
+    v_light_index = gl_InstanceIndex;
+    gl_Position = clip_position;
+}
@@ -0,0 +1,18 @@
+#version 450
+
+// Multiplies the scene with the light accumulation buffer.
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D u_scene;
+layout(set = 0, binding = 1) uniform sampler u_scene_sampler;
+
+layout(set = 1, binding = 0) uniform texture2D u_light;
+layout(set = 1, binding = 1) uniform sampler u_light_sampler;
+
+void main() {
+    vec4 scene = texture(sampler2D(u_scene, u_scene_sampler), v_tex_coords);
+    vec3 light = texture(sampler2D(u_light, u_light_sampler), v_tex_coords).rgb;
+    f_color = vec4(scene.rgb * light, scene.a);
+}
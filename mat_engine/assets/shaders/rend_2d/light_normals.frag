@@ -0,0 +1,17 @@
+#version 450
+
+// Draws a normal map into the normal buffer. Vertex colors are ignored, except for their alpha:
+// transparent parts of the sprite keep the normals that were already there.
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=1) in vec4 v_color;
+
+layout(location=0) out vec4 f_color;
+
+layout(set = 0, binding = 0) uniform texture2D tex;
+layout(set = 0, binding = 1) uniform sampler samp;
+
+void main() {
+    vec4 normal = texture(sampler2D(tex, samp), v_tex_coords);
+    f_color = vec4(normal.rgb, normal.a * v_color.a);
+}
@@ -0,0 +1,31 @@
+#version 450
+
+// Unit quad, centered on the origin.
+layout(location=0) in vec2 a_position;
+layout(location=1) in vec2 a_tex_coords;
+
+// Per particle.
+layout(location=2) in vec2 i_position;
+layout(location=3) in float i_size;
+layout(location=4) in float i_rotation;
+layout(location=5) in vec4 i_color;
+layout(location=6) in vec4 i_uv_rect; // Top left (xy) and bottom right (zw) texture coordinates.
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec4 v_color;
+
+layout(set=1, binding=0) 
+uniform Camera {
+    mat4 u_proj_mat;
+};
+
+void main() {
+    float c = cos(i_rotation);
+    float s = sin(i_rotation);
+    vec2 p = a_position * i_size;
+    p = vec2(p.x * c - p.y * s, p.x * s + p.y * c) + i_position;
+
+    v_tex_coords = mix(i_uv_rect.xy, i_uv_rect.zw, a_tex_coords);
+    v_color = i_color;
+    gl_Position = u_proj_mat * vec4(p, 0.0, 1.0);
+}
@@ -12,7 +12,7 @@
 ///
 /// CONTROL FLOW*:
 ///
-/// INIT() -> LOOP { UPDATE() -> RENDER() } -> CLOSE()
+/// INIT() -> LOOP { FIXED_UPDATE() (zero or more times) -> UPDATE() -> RENDER() } -> CLOSE()
 ///
 /// *In general, exceptions/corner-cases exist.
 ///
@@ -31,6 +31,14 @@ pub trait Application: super::event::ApplicationEventReceiver {
     #[allow(unused_variables)]
     fn close(&mut self, ctx: &mut crate::context::EngineContext) {}
 
+    /// Called at a fixed rate (see `EngineConfig::timing`), for things that should be
+    /// deterministic, such as physics. Depending on how long frames take, this is called zero or
+    /// more times per frame, after handling events but before `Application::update()`.
+    ///
+    /// Use `chrono::fixed_delta_time()` instead of `chrono::delta_time()` here.
+    #[allow(unused_variables)]
+    fn fixed_update(&mut self, ctx: &mut crate::context::EngineContext) {}
+
     /// Called once per frame, after handling events* but before rendering.
     ///
     /// *TODO: Better investigate and document event handling order and corner-cases.
@@ -42,6 +50,9 @@ pub trait Application: super::event::ApplicationEventReceiver {
     /// Note: Sometimes, the OS / winit may request a redraw (for example, if the app window is resized),
     /// which could cause `render()` to be called without a preceding `update()` call. You should be careful
     /// to ensure your application functions (reasonably) correctly in this case.
+    ///
+    /// State updated in `Application::fixed_update()` may be interpolated using
+    /// `chrono::interpolation_alpha()`, for smooth movement regardless of the fixed update rate.
     #[allow(unused_variables)]
     fn render(&mut self, ctx: &mut crate::context::EngineContext) {}
 }
@@ -8,7 +8,7 @@
 /// *(Except, of course, for secondary arenas, if they have been implemented).
 ///
 /// This is a newtype around `::slotmap::DefaultKey`.
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct ArenaKey(::slotmap::DefaultKey);
 
 /// Generational Arena
@@ -73,4 +73,21 @@ impl<T> Arena<T> {
     pub fn has_key(&self, key: ArenaKey) -> bool {
         self.slotmap.contains_key(key.0)
     }
+
+    /// Iterates over every key and value, in no particular order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ArenaKey, &mut T)> {
+        self.slotmap
+            .iter_mut()
+            .map(|(key, val)| (ArenaKey(key), val))
+    }
+
+    /// Removes every value.
+    pub fn clear(&mut self) {
+        self.slotmap.clear();
+    }
+
+    /// Iterates over every value, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slotmap.values()
+    }
 }
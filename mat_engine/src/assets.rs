@@ -1,5 +1,5 @@
 use crate::typedefs::BoxErr;
-use std::{fs::File, io::BufReader, io::Read, path::PathBuf};
+use std::{fs::File, io::BufReader, io::Read, path::PathBuf, sync::RwLock};
 
 lazy_static::lazy_static! {
 
@@ -11,9 +11,21 @@ lazy_static::lazy_static! {
         calculate_top_path()
     };
 
-    static ref ENGINE_ASSETS_PATH: PathBuf = {
-        calculate_engine_assets_path()
-    };
+    /// Overrides `TOP_PATH`, see `set_asset_root()`.
+    static ref ASSET_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Sets the directory containing the asset folders, instead of finding it relative to the
+/// executable. See `EngineConfig::asset_root`.
+pub(crate) fn set_asset_root(asset_root: PathBuf) {
+    *ASSET_ROOT.write().expect("Asset root lock poisoned") = Some(asset_root);
+}
+
+fn top_path() -> PathBuf {
+    match &*ASSET_ROOT.read().expect("Asset root lock poisoned") {
+        Some(asset_root) => asset_root.clone(),
+        None => TOP_PATH.clone(),
+    }
 }
 
 fn calculate_top_path() -> PathBuf {
@@ -43,8 +55,8 @@ fn calculate_top_path() -> PathBuf {
     }
 }
 
-fn calculate_engine_assets_path() -> PathBuf {
-    let mut p: PathBuf = TOP_PATH.clone();
+pub(crate) fn get_engine_assets_path() -> PathBuf {
+    let mut p: PathBuf = top_path();
 
     p.push("mat_engine");
     p.push("assets");
@@ -52,12 +64,8 @@ fn calculate_engine_assets_path() -> PathBuf {
     p
 }
 
-pub(crate) fn get_engine_assets_path() -> PathBuf {
-    ENGINE_ASSETS_PATH.clone()
-}
-
 pub fn get_folder_assets_path(folder: &str) -> PathBuf {
-    let mut p = TOP_PATH.clone();
+    let mut p = top_path();
     p.push(folder);
     p.push("assets");
     p
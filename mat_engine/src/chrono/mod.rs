@@ -0,0 +1,387 @@
+//! Framerate and time based stuff
+//!
+//! Time is measured in two ways:
+//! - Unscaled time is real time.
+//! - Scaled time is game time: it passes `time_scale()` times faster than real time (slower if
+//!   the scale is below 1.0, for slow-motion), and stops while paused.
+//!
+//! Gameplay should use scaled time (`delta_time()`, `time()`), and things that should keep going
+//! regardless, such as menus, unscaled time.
+//!
+//! Built on top of it, `Timer`s count down time, `Tween`s animate values over time, and a
+//! `Scheduler` runs closures later.
+
+pub mod scheduler;
+pub mod timer;
+pub mod tween;
+
+pub use scheduler::{Scheduler, Sequence};
+use std::time::{Duration, Instant};
+pub use timer::{Timer, TimerMode};
+pub use tween::{Easing, Lerp, Tween};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{unwrap_mut, unwrap_ref};
+
+/// Returns 0.0 for the first frame, and the scaled duration of the previous frame in seconds for
+/// all other frames. 0.0 while paused.
+///
+/// Durations are clamped (see `TimingConfig::max_delta_time`), so that pausing in a debugger, for
+/// example, doesn't make the game jump ahead.
+pub fn delta_time(ctx: &crate::EngineContext) -> f64 {
+    unwrap_ref(&ctx.chrono_system).scaled_delta
+}
+
+/// Like `delta_time()`, but neither scaled nor paused. Still clamped.
+pub fn unscaled_delta_time(ctx: &crate::EngineContext) -> f64 {
+    unwrap_ref(&ctx.chrono_system).unscaled_delta
+}
+
+/// Scaled time elapsed since the first frame, in seconds. The sum of the `delta_time()` of every
+/// frame.
+pub fn time(ctx: &crate::EngineContext) -> f64 {
+    unwrap_ref(&ctx.chrono_system).scaled_time
+}
+
+/// Real time elapsed from the start of the first frame to the start of the current one, in
+/// seconds.
+pub fn unscaled_time(ctx: &crate::EngineContext) -> f64 {
+    unwrap_ref(&ctx.chrono_system).unscaled_time()
+}
+
+/// Number of the current frame, the first one being frame 0.
+pub fn frame_number(ctx: &crate::EngineContext) -> u64 {
+    unwrap_ref(&ctx.chrono_system).frame_number
+}
+
+/// 1.0 by default.
+pub fn time_scale(ctx: &crate::EngineContext) -> f64 {
+    unwrap_ref(&ctx.chrono_system).time_scale
+}
+
+/// Sets how fast scaled time passes compared to real time, e.g. 0.5 for half speed. Takes effect
+/// from the next frame on.
+///
+/// This is a wrapper method.
+pub fn set_time_scale(ctx: &mut crate::EngineContext, time_scale: f64) {
+    unwrap_mut(&mut ctx.chrono_system).set_time_scale(time_scale)
+}
+
+pub fn is_paused(ctx: &crate::EngineContext) -> bool {
+    unwrap_ref(&ctx.chrono_system).paused
+}
+
+/// Pausing stops scaled time, from the next frame on, and with it `Application::fixed_update()`
+/// calls. Unscaled time keeps going.
+pub fn set_paused(ctx: &mut crate::EngineContext, paused: bool) {
+    unwrap_mut(&mut ctx.chrono_system).paused = paused;
+}
+
+/// See `TimingConfig::max_delta_time`.
+///
+/// This is a wrapper method.
+pub fn set_max_delta_time(ctx: &mut crate::EngineContext, max_delta_time: f64) {
+    unwrap_mut(&mut ctx.chrono_system).set_max_delta_time(max_delta_time)
+}
+
+/// Time between two `Application::fixed_update()` calls, in seconds.
+pub fn fixed_delta_time(ctx: &crate::EngineContext) -> f64 {
+    unwrap_ref(&ctx.chrono_system).fixed_timestep
+}
+
+/// How far between the last and the next `Application::fixed_update()` the current frame is, from
+/// 0.0 (right at the last one) to 1.0 (right at the next one).
+///
+/// When rendering state updated at a fixed rate, interpolate between its previous and current
+/// values with it: `previous + (current - previous) * alpha`.
+pub fn interpolation_alpha(ctx: &crate::EngineContext) -> f64 {
+    let chrono_system = unwrap_ref(&ctx.chrono_system);
+    chrono_system.fixed_accumulator / chrono_system.fixed_timestep
+}
+
+/// See `TimingConfig::fixed_update_rate`.
+///
+/// This is a wrapper method.
+pub fn set_fixed_update_rate(ctx: &mut crate::EngineContext, updates_per_second: f64) {
+    unwrap_mut(&mut ctx.chrono_system).set_fixed_update_rate(updates_per_second)
+}
+
+/// See `LoopMode`.
+///
+/// This is a wrapper method.
+pub fn set_loop_mode(ctx: &mut crate::EngineContext, loop_mode: LoopMode) {
+    unwrap_mut(&mut ctx.chrono_system).set_loop_mode(loop_mode)
+}
+
+pub fn loop_mode(ctx: &crate::EngineContext) -> LoopMode {
+    unwrap_ref(&ctx.chrono_system).loop_mode
+}
+
+/// In `LoopMode::Reactive`, runs another frame as soon as possible, even if there is no input.
+/// Call it every frame for as long as something is animating. Has no effect in other modes.
+pub fn request_frame(ctx: &mut crate::EngineContext) {
+    unwrap_mut(&mut ctx.chrono_system).frame_requested = true;
+}
+
+/// How the engine schedules frames. See `TimingConfig::loop_mode`.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LoopMode {
+    /// Runs frames back to back, as fast as possible (or as vsync allows). Uses a full CPU core.
+    Continuous,
+    /// Runs at most `max_fps` frames per second. `max_fps` must be at least `MIN_CAPPED_FPS`.
+    Capped {
+        max_fps: f64,
+        /// If true, the event loop waits until shortly before the next frame, then spins until it
+        /// is due, which is precise, but uses some CPU. Otherwise, the event loop waits until the
+        /// next frame, which uses no CPU, but frames may come a bit late, as timers of some OSes
+        /// are coarse (about 1 ms, up to 15 ms on Windows).
+        precise: bool,
+    },
+    /// Only runs frames when a window receives events (input, resizes...), or when requested
+    /// with `request_frame()`. Otherwise, the engine sleeps. For tools and menus.
+    ///
+    /// Note that after a while without frames, `unscaled_time()` jumps ahead, while
+    /// `delta_time()` is clamped.
+    Reactive,
+}
+
+/// Lowest `max_fps` of `LoopMode::Capped`: a frame every 100 seconds. Lower rates would make frame
+/// deadlines overflow. Use `LoopMode::Reactive` for applications that rarely need frames.
+pub const MIN_CAPPED_FPS: f64 = 0.01;
+
+/// OS timers may overshoot by a millisecond or more, so precise waits wake up this long before
+/// the deadline, and spin for the rest.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+pub struct ChronoSystem {
+    /// First frame is frame 0.
+    frame_number: u64,
+    first_frame_start: Option<Instant>,
+    /// Time when `start_new_frame()` was last called. None before the first frame.
+    curr_frame_start: Option<Instant>,
+    /// Duration of the previous frame in seconds, clamped to `max_delta_time`. 0.0 for the first
+    /// frame.
+    unscaled_delta: f64,
+    /// `unscaled_delta`, scaled by `time_scale`, or 0.0 if paused.
+    scaled_delta: f64,
+    /// Sum of the `scaled_delta` of every frame.
+    scaled_time: f64,
+    time_scale: f64,
+    paused: bool,
+    /// In seconds.
+    max_delta_time: f64,
+
+    /// In seconds, see `fixed_delta_time()`.
+    fixed_timestep: f64,
+    max_fixed_updates_per_frame: u32,
+    /// Time not yet consumed by fixed updates, in seconds. Always less than `fixed_timestep`
+    /// outside of `take_fixed_updates()`.
+    fixed_accumulator: f64,
+
+    loop_mode: LoopMode,
+    /// When the next frame is due, if the frame rate is capped.
+    next_frame_deadline: Option<Instant>,
+    /// Whether a frame should run in `LoopMode::Reactive`: a window received events, or one was
+    /// requested.
+    frame_requested: bool,
+}
+
+impl ChronoSystem {
+    pub(crate) fn new(config: &crate::config::TimingConfig) -> Self {
+        let defaults = crate::config::TimingConfig::default();
+
+        let mut chrono_system = Self {
+            frame_number: 0,
+            first_frame_start: None,
+            curr_frame_start: None,
+            unscaled_delta: 0.0,
+            scaled_delta: 0.0,
+            scaled_time: 0.0,
+            time_scale: 1.0,
+            paused: false,
+            max_delta_time: defaults.max_delta_time,
+            fixed_timestep: 1.0 / defaults.fixed_update_rate,
+            max_fixed_updates_per_frame: config.max_fixed_updates_per_frame.max(1),
+            fixed_accumulator: 0.0,
+            loop_mode: LoopMode::Continuous,
+            next_frame_deadline: None,
+            // The first frame always runs.
+            frame_requested: true,
+        };
+        chrono_system.set_fixed_update_rate(config.fixed_update_rate);
+        chrono_system.set_max_delta_time(config.max_delta_time);
+        chrono_system.set_loop_mode(config.loop_mode);
+
+        chrono_system
+    }
+
+    fn unscaled_time(&self) -> f64 {
+        match (self.first_frame_start, self.curr_frame_start) {
+            (Some(first), Some(curr)) => curr.duration_since(first).as_secs_f64(),
+            _ => 0.0,
+        }
+    }
+
+    /// See wrapper method.
+    fn set_time_scale(&mut self, time_scale: f64) {
+        if time_scale.is_nan() || time_scale < 0.0 {
+            log::warn!(
+                "Ignoring invalid time scale {}, it must not be negative",
+                time_scale
+            );
+            return;
+        }
+
+        self.time_scale = time_scale;
+    }
+
+    /// See wrapper method.
+    fn set_max_delta_time(&mut self, max_delta_time: f64) {
+        if max_delta_time.is_nan() || max_delta_time <= 0.0 {
+            log::warn!(
+                "Ignoring invalid max delta time {}, it must be positive",
+                max_delta_time
+            );
+            return;
+        }
+
+        self.max_delta_time = max_delta_time;
+    }
+
+    /// See wrapper method.
+    fn set_fixed_update_rate(&mut self, updates_per_second: f64) {
+        if updates_per_second.is_nan() || updates_per_second <= 0.0 {
+            log::warn!(
+                "Ignoring invalid fixed update rate {}, it must be positive",
+                updates_per_second
+            );
+            return;
+        }
+
+        self.fixed_timestep = 1.0 / updates_per_second;
+        self.fixed_accumulator = self.fixed_accumulator.min(self.fixed_timestep);
+    }
+
+    /// See wrapper method.
+    fn set_loop_mode(&mut self, loop_mode: LoopMode) {
+        if let LoopMode::Capped { max_fps, .. } = loop_mode {
+            if max_fps.is_nan() || max_fps < MIN_CAPPED_FPS {
+                log::warn!(
+                    "Ignoring invalid loop mode, max fps {} is below the minimum of {}",
+                    max_fps,
+                    MIN_CAPPED_FPS
+                );
+                return;
+            }
+        }
+
+        self.loop_mode = loop_mode;
+    }
+
+    /// Adds the previous frame's scaled duration to the time to be consumed by fixed updates, and
+    /// returns how many `Application::fixed_update()` calls to make this frame.
+    pub(crate) fn take_fixed_updates(&mut self) -> u32 {
+        self.fixed_accumulator += self.scaled_delta;
+
+        let updates = (self.fixed_accumulator / self.fixed_timestep) as u32;
+
+        if updates > self.max_fixed_updates_per_frame {
+            log::debug!(
+                "Dropping {} fixed updates, frame took too long",
+                updates - self.max_fixed_updates_per_frame
+            );
+            self.fixed_accumulator %= self.fixed_timestep;
+            self.max_fixed_updates_per_frame
+        } else {
+            self.fixed_accumulator -= updates as f64 * self.fixed_timestep;
+            updates
+        }
+    }
+
+    /// Starts a new frame and ends the previous*, generating the relevant delta time data for the previous frame.
+    ///
+    /// *except if this is the first frame.
+    pub(crate) fn start_new_frame(&mut self) {
+        let now = Instant::now();
+
+        // If this isn't the first frame, end the previous one
+        if let Some(prev_frame_start) = self.curr_frame_start {
+            self.end_prev_frame(prev_frame_start, now);
+            self.frame_number += 1;
+        } else {
+            self.first_frame_start = Some(now);
+        }
+
+        self.curr_frame_start = Some(now);
+
+        self.frame_requested = false;
+        self.next_frame_deadline = match self.loop_mode {
+            LoopMode::Capped { max_fps, .. } => {
+                Some(Instant::now() + Duration::from_secs_f64(1.0 / max_fps))
+            }
+            _ => None,
+        };
+    }
+
+    /// Called for events that should wake the application up in `LoopMode::Reactive`.
+    pub(crate) fn receive_activity(&mut self) {
+        self.frame_requested = true;
+    }
+
+    /// Whether a frame should run in the current iteration of the event loop. In other
+    /// iterations, events are still handled, but neither `Application::update()` nor
+    /// `Application::render()` are called.
+    pub(crate) fn is_frame_due(&self) -> bool {
+        match self.loop_mode {
+            LoopMode::Continuous => true,
+            LoopMode::Capped { .. } => match self.next_frame_deadline {
+                Some(deadline) => Instant::now() >= deadline,
+                None => true,
+            },
+            LoopMode::Reactive => self.frame_requested,
+        }
+    }
+
+    /// Called at the end of every iteration of the event loop. Returns how the event loop should
+    /// wait for the next one. In precise `LoopMode::Capped`, the event loop waits until shortly
+    /// before the next frame (handling events in the meantime), and the rest is spun here.
+    pub(crate) fn wait_for_next_frame(&mut self) -> winit::event_loop::ControlFlow {
+        match (self.loop_mode, self.next_frame_deadline) {
+            (LoopMode::Capped { precise: true, .. }, Some(deadline)) => {
+                if deadline > Instant::now() + SPIN_MARGIN {
+                    return winit::event_loop::ControlFlow::WaitUntil(deadline - SPIN_MARGIN);
+                }
+                while Instant::now() < deadline {
+                    std::hint::spin_loop();
+                }
+
+                winit::event_loop::ControlFlow::Poll
+            }
+            (LoopMode::Capped { precise: false, .. }, Some(deadline)) => {
+                winit::event_loop::ControlFlow::WaitUntil(deadline)
+            }
+            (LoopMode::Reactive, _) if !self.frame_requested => {
+                winit::event_loop::ControlFlow::Wait
+            }
+            _ => winit::event_loop::ControlFlow::Poll,
+        }
+    }
+
+    /// Note: We currently call `end_prev_frame()` inside `start_new_frame()`, instead of calling end frame
+    /// from the winit main loop's RedrawEventsCleared, which should only make a difference
+    /// if a significant amount of time passes between the end of a frame and the beginning of the next.
+    ///
+    /// Still, we should think about this and figure out which approach is better.
+    fn end_prev_frame(&mut self, prev_frame_start: Instant, end: Instant) {
+        let seconds = end.duration_since(prev_frame_start).as_secs_f64();
+
+        self.unscaled_delta = seconds.min(self.max_delta_time);
+        self.scaled_delta = if self.paused {
+            0.0
+        } else {
+            self.unscaled_delta * self.time_scale
+        };
+        self.scaled_time += self.scaled_delta;
+    }
+}
@@ -0,0 +1,287 @@
+//! Running closures later, see `Scheduler`.
+
+use super::{delta_time, frame_number, is_paused, unscaled_delta_time};
+use crate::arena::{Arena, ArenaKey};
+
+type Task = Box<dyn FnMut(&mut crate::EngineContext)>;
+
+enum Step {
+    WaitSeconds(f64),
+    WaitFrames(u64),
+    Run(Task),
+}
+
+/// Steps run one after another by a `Scheduler`, like a coroutine: waits, and closures run once
+/// the previous waits are over.
+///
+/// ```ignore
+/// let intro = Sequence::new()
+///     .then(|ctx| show_title(ctx))
+///     .wait_seconds(2.0)
+///     .then(|ctx| hide_title(ctx))
+///     .wait_frames(1)
+///     .then(|ctx| start_level(ctx));
+/// self.scheduler.start(ctx, intro);
+/// ```
+pub struct Sequence {
+    steps: Vec<Step>,
+    repeating: bool,
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            repeating: false,
+        }
+    }
+
+    pub fn wait_seconds(mut self, seconds: f64) -> Self {
+        self.steps.push(Step::WaitSeconds(seconds));
+        self
+    }
+
+    pub fn wait_frames(mut self, frames: u64) -> Self {
+        self.steps.push(Step::WaitFrames(frames));
+        self
+    }
+
+    pub fn then(mut self, task: impl FnMut(&mut crate::EngineContext) + 'static) -> Self {
+        self.steps.push(Step::Run(Box::new(task)));
+        self
+    }
+
+    /// Starts over from the first step after the last one, until cancelled. A repeating sequence
+    /// without any wait runs once per update.
+    pub fn repeating(mut self) -> Self {
+        self.repeating = true;
+        self
+    }
+
+    fn has_wait(&self) -> bool {
+        self.steps.iter().any(|step| match step {
+            Step::WaitSeconds(seconds) => *seconds > 0.0,
+            Step::WaitFrames(frames) => *frames > 0,
+            Step::Run(_) => false,
+        })
+    }
+}
+
+struct ScheduledSequence {
+    sequence: Sequence,
+    current_step: usize,
+    /// Time and frames waited so far in the current step.
+    waited_seconds: f64,
+    waited_frames: u64,
+    /// Frame the sequence was started during. It isn't advanced during that frame, as
+    /// `chrono::delta_time()` then is the duration of the previous frame, from before it started.
+    start_frame: u64,
+}
+
+impl ScheduledSequence {
+    /// Runs the steps that are due after `seconds` and `frames` more were waited. Returns whether
+    /// the sequence is over.
+    fn advance(
+        &mut self,
+        ctx: &mut crate::EngineContext,
+        mut seconds: f64,
+        mut frames: u64,
+    ) -> bool {
+        let mut wrapped = false;
+
+        loop {
+            if self.current_step == self.sequence.steps.len() {
+                if !self.sequence.repeating {
+                    return true;
+                }
+                // Without a wait, the sequence would start over forever.
+                if wrapped && !self.sequence.has_wait() {
+                    return false;
+                }
+                self.current_step = 0;
+                wrapped = true;
+            }
+
+            match &mut self.sequence.steps[self.current_step] {
+                Step::WaitSeconds(duration) => {
+                    let needed = *duration - self.waited_seconds;
+                    if seconds < needed {
+                        self.waited_seconds += seconds;
+                        return false;
+                    }
+                    seconds -= needed.max(0.0);
+                    self.waited_seconds = 0.0;
+                }
+                Step::WaitFrames(duration) => {
+                    let needed = duration.saturating_sub(self.waited_frames);
+                    if frames < needed {
+                        self.waited_frames += frames;
+                        return false;
+                    }
+                    frames -= needed;
+                    self.waited_frames = 0;
+                }
+                Step::Run(task) => task(ctx),
+            }
+            self.current_step += 1;
+        }
+    }
+}
+
+/// Runs closures after some seconds or frames, or repeatedly, instead of keeping countdowns
+/// around for them:
+///
+/// ```ignore
+/// self.scheduler.after_seconds(ctx, 3.0, |ctx| windowing::set_title(ctx, "Three seconds later"));
+/// self.scheduler.update(ctx);
+/// ```
+///
+/// Scheduled closures get a handle, which can be used to cancel them.
+///
+/// By default, schedulers count scaled time and don't count frames while paused, so gameplay
+/// events follow `chrono::time_scale()` and pauses. See `unscaled()`.
+pub struct Scheduler {
+    sequences: Arena<ScheduledSequence>,
+    unscaled: bool,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            sequences: Arena::new(),
+            unscaled: false,
+        }
+    }
+
+    /// Makes the scheduler count unscaled time, and frames even while paused.
+    pub fn unscaled(mut self) -> Self {
+        self.unscaled = true;
+        self
+    }
+
+    /// Runs `task` once, after `seconds`.
+    pub fn after_seconds(
+        &mut self,
+        ctx: &crate::EngineContext,
+        seconds: f64,
+        task: impl FnOnce(&mut crate::EngineContext) + 'static,
+    ) -> ArenaKey {
+        self.start(ctx, Sequence::new().wait_seconds(seconds).then(once(task)))
+    }
+
+    /// Runs `task` once, after `frames` updates.
+    pub fn after_frames(
+        &mut self,
+        ctx: &crate::EngineContext,
+        frames: u64,
+        task: impl FnOnce(&mut crate::EngineContext) + 'static,
+    ) -> ArenaKey {
+        self.start(ctx, Sequence::new().wait_frames(frames).then(once(task)))
+    }
+
+    /// Runs `task` every `seconds`, until cancelled. If frames are longer than that, it runs
+    /// several times per update, to keep up.
+    pub fn every_seconds(
+        &mut self,
+        ctx: &crate::EngineContext,
+        seconds: f64,
+        task: impl FnMut(&mut crate::EngineContext) + 'static,
+    ) -> ArenaKey {
+        self.start(
+            ctx,
+            Sequence::new().wait_seconds(seconds).then(task).repeating(),
+        )
+    }
+
+    /// Runs `task` every `frames` updates, until cancelled.
+    pub fn every_frames(
+        &mut self,
+        ctx: &crate::EngineContext,
+        frames: u64,
+        task: impl FnMut(&mut crate::EngineContext) + 'static,
+    ) -> ArenaKey {
+        self.start(
+            ctx,
+            Sequence::new().wait_frames(frames).then(task).repeating(),
+        )
+    }
+
+    /// Starts running `sequence`, from the next frame's update.
+    pub fn start(&mut self, ctx: &crate::EngineContext, sequence: Sequence) -> ArenaKey {
+        self.sequences.insert(ScheduledSequence {
+            sequence,
+            current_step: 0,
+            waited_seconds: 0.0,
+            waited_frames: 0,
+            start_frame: frame_number(ctx),
+        })
+    }
+
+    /// Stops a scheduled closure or sequence before it runs (again). Returns whether it was still
+    /// scheduled.
+    pub fn cancel(&mut self, key: ArenaKey) -> bool {
+        self.sequences.remove(key).is_some()
+    }
+
+    /// Whether a closure or sequence is still scheduled, i.e. hasn't finished or been cancelled.
+    pub fn is_scheduled(&self, key: ArenaKey) -> bool {
+        self.sequences.has_key(key)
+    }
+
+    /// Cancels everything.
+    pub fn clear(&mut self) {
+        self.sequences.clear();
+    }
+
+    /// Runs what is due. Should be called once per frame, as frames are counted by calls to it.
+    ///
+    /// Sequences that are due during the same update run in no particular order.
+    pub fn update(&mut self, ctx: &mut crate::EngineContext) {
+        let (seconds, frames) = if self.unscaled {
+            (unscaled_delta_time(ctx), 1)
+        } else if is_paused(ctx) {
+            (0.0, 0)
+        } else {
+            (delta_time(ctx), 1)
+        };
+        let current_frame = frame_number(ctx);
+
+        let mut finished = Vec::new();
+        for (key, scheduled) in self.sequences.iter_mut() {
+            if scheduled.start_frame == current_frame {
+                continue;
+            }
+            if scheduled.advance(ctx, seconds, frames) {
+                finished.push(key);
+            }
+        }
+
+        for key in finished {
+            self.sequences.remove(key);
+        }
+    }
+}
+
+/// Wraps a closure that may only run once, for `Sequence` steps, which may run again.
+fn once(
+    task: impl FnOnce(&mut crate::EngineContext) + 'static,
+) -> impl FnMut(&mut crate::EngineContext) + 'static {
+    let mut task = Some(task);
+    move |ctx| {
+        if let Some(task) = task.take() {
+            task(ctx);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Countdown timers, see `Timer`.
+
+use super::{delta_time, unscaled_delta_time};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimerMode {
+    /// Finishes once, then stays finished until reset.
+    Once,
+    /// Starts over every time it finishes.
+    Repeating,
+}
+
+/// Counts time up to a duration, in place of countdown fields in `Application::update()`.
+///
+/// Call `update()` once per frame (or `advance()` with the time to count), then check
+/// `just_finished()`:
+///
+/// ```ignore
+/// self.spawn_timer.update(ctx);
+/// for _ in 0..self.spawn_timer.times_finished() {
+///     self.spawn_enemy();
+/// }
+/// ```
+///
+/// By default, timers count scaled time, so they slow down with `chrono::time_scale()` and stop
+/// while paused. See `unscaled()`.
+#[derive(Clone, Debug)]
+pub struct Timer {
+    duration: f64,
+    elapsed: f64,
+    mode: TimerMode,
+    paused: bool,
+    unscaled: bool,
+    /// Whether a `Once` timer has finished.
+    finished: bool,
+    /// How many times the timer finished during the last advance.
+    times_finished: u32,
+}
+
+impl Timer {
+    /// `seconds` is the duration of the timer.
+    pub fn new(seconds: f64, mode: TimerMode) -> Self {
+        Self {
+            duration: seconds,
+            elapsed: 0.0,
+            mode,
+            paused: false,
+            unscaled: false,
+            finished: false,
+            times_finished: 0,
+        }
+    }
+
+    pub fn once(seconds: f64) -> Self {
+        Self::new(seconds, TimerMode::Once)
+    }
+
+    pub fn repeating(seconds: f64) -> Self {
+        Self::new(seconds, TimerMode::Repeating)
+    }
+
+    /// Makes `update()` count unscaled time, e.g. for menus that should keep working while the
+    /// game is paused.
+    pub fn unscaled(mut self) -> Self {
+        self.unscaled = true;
+        self
+    }
+
+    /// Advances the timer by the duration of the last frame. Should be called once per frame.
+    pub fn update(&mut self, ctx: &crate::EngineContext) {
+        let seconds = if self.unscaled {
+            unscaled_delta_time(ctx)
+        } else {
+            delta_time(ctx)
+        };
+        self.advance(seconds);
+    }
+
+    /// Advances the timer by `seconds`, unless it is paused.
+    pub fn advance(&mut self, seconds: f64) {
+        self.times_finished = 0;
+        if self.paused {
+            return;
+        }
+
+        match self.mode {
+            TimerMode::Once => {
+                if self.finished {
+                    return;
+                }
+                self.elapsed += seconds;
+                if self.elapsed >= self.duration {
+                    self.elapsed = self.duration.max(0.0);
+                    self.finished = true;
+                    self.times_finished = 1;
+                }
+            }
+            TimerMode::Repeating => {
+                // Finishing more than once per advance would never end.
+                if self.duration <= 0.0 {
+                    self.times_finished = 1;
+                    return;
+                }
+                self.elapsed += seconds;
+                while self.elapsed >= self.duration {
+                    self.elapsed -= self.duration;
+                    self.times_finished += 1;
+                }
+            }
+        }
+    }
+
+    /// Whether the timer finished during the last advance.
+    pub fn just_finished(&self) -> bool {
+        self.times_finished > 0
+    }
+
+    /// How many times the timer finished during the last advance. Can be more than one for
+    /// repeating timers shorter than a frame.
+    pub fn times_finished(&self) -> u32 {
+        self.times_finished
+    }
+
+    /// Whether a `Once` timer has finished. Repeating timers never are.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Seconds counted since the timer was started, or last started over.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Seconds left until the timer finishes.
+    pub fn remaining(&self) -> f64 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+
+    /// Progress towards the end, from 0.0 to 1.0.
+    pub fn fraction(&self) -> f64 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.duration).min(1.0)
+        }
+    }
+
+    /// Starts the timer over. Doesn't unpause it.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+        self.times_finished = 0;
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// Keeps the elapsed time, so a timer shortened below it finishes on its next advance.
+    pub fn set_duration(&mut self, seconds: f64) {
+        self.duration = seconds;
+    }
+
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+}
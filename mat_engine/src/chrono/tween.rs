@@ -0,0 +1,187 @@
+//! Values animated over time, see `Tween`.
+
+use std::f32::consts::PI;
+
+use nalgebra_glm as glm;
+
+use super::timer::Timer;
+
+/// Values that can be linearly interpolated, e.g. by `Tween`s and particle `Curve`s.
+pub trait Lerp: Copy {
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for [f32; 4] {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut out = *self;
+        for (o, b) in out.iter_mut().zip(other) {
+            *o = o.lerp(b, t);
+        }
+        out
+    }
+}
+
+impl Lerp for glm::Vec2 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        glm::lerp(self, other, t)
+    }
+}
+
+/// How a tween's progress maps to the interpolation between its start and end values. "In"
+/// curves start slow, "Out" ones end slow, and "InOut" ones do both.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    /// Overshoots the end value slightly, then settles back.
+    BackOut,
+    /// Bounces against the end value a few times before settling.
+    BounceOut,
+}
+
+impl Easing {
+    /// Maps `t`, from 0.0 to 1.0, to an interpolation factor. Every curve maps 0.0 to 0.0 and 1.0
+    /// to 1.0, but some go outside of that range in between.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+            Easing::BackOut => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+            Easing::BounceOut => {
+                const N1: f32 = 7.5625;
+                const D1: f32 = 2.75;
+                if t < 1.0 / D1 {
+                    N1 * t * t
+                } else if t < 2.0 / D1 {
+                    let t = t - 1.5 / D1;
+                    N1 * t * t + 0.75
+                } else if t < 2.5 / D1 {
+                    let t = t - 2.25 / D1;
+                    N1 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D1;
+                    N1 * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+/// Animates a value from `from` to `to` over a duration, for example to fade something out or
+/// slide a menu in:
+///
+/// ```ignore
+/// let alpha = self.fade.update(ctx);
+/// ```
+///
+/// Like `Timer`s, tweens count scaled time by default, see `unscaled()`.
+#[derive(Clone, Debug)]
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    timer: Timer,
+    easing: Easing,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, seconds: f64, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            timer: Timer::once(seconds),
+            easing,
+        }
+    }
+
+    /// Makes `update()` count unscaled time, see `Timer::unscaled()`.
+    pub fn unscaled(mut self) -> Self {
+        self.timer = self.timer.unscaled();
+        self
+    }
+
+    /// Advances the tween by the duration of the last frame, and returns the new value. Should be
+    /// called once per frame.
+    pub fn update(&mut self, ctx: &crate::EngineContext) -> T {
+        self.timer.update(ctx);
+        self.value()
+    }
+
+    /// Advances the tween by `seconds`, and returns the new value.
+    pub fn advance(&mut self, seconds: f64) -> T {
+        self.timer.advance(seconds);
+        self.value()
+    }
+
+    pub fn value(&self) -> T {
+        let t = self.easing.apply(self.timer.fraction() as f32);
+        self.from.lerp(&self.to, t)
+    }
+
+    /// Whether the end value was reached.
+    pub fn is_finished(&self) -> bool {
+        self.timer.is_finished()
+    }
+
+    /// Whether the end value was reached during the last advance.
+    pub fn just_finished(&self) -> bool {
+        self.timer.just_finished()
+    }
+
+    /// Starts the tween over, from the start value.
+    pub fn reset(&mut self) {
+        self.timer.reset();
+    }
+
+    /// Starts over towards a new end value, from the current one. Useful to redirect a tween
+    /// that is still running without a jump.
+    pub fn retarget(&mut self, to: T) {
+        self.from = self.value();
+        self.to = to;
+        self.timer.reset();
+    }
+
+    /// The timer driving the tween, e.g. to pause it.
+    pub fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
@@ -0,0 +1,236 @@
+//! Engine configuration, given to `mat_engine::run()`.
+//!
+//! An `EngineConfig` may be built in code:
+//!
+//! ```ignore
+//! mat_engine::run::<MyApp>(EngineConfig::new().title("My game").size(1280, 720).msaa_samples(4));
+//! ```
+//!
+//! Or loaded from a TOML or RON file, see `EngineConfig::from_file()`. Every field is optional
+//! in files, missing ones take their default values. For example, in TOML:
+//!
+//! ```toml
+//! [window]
+//! title = "My game"
+//! size = [1280, 720]
+//!
+//! [rendering]
+//! msaa_samples = 4
+//! present_mode = "Mailbox"
+//!
+//! [log]
+//! filter = "mat_engine=info,my_game=trace,warn"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use anyhow::Result as AResult;
+use serde::{Deserialize, Serialize};
+
+use crate::chrono::LoopMode;
+use crate::rendering::{PresentMode, RenderingConfig};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub window: WindowConfig,
+    pub rendering: RenderingConfig,
+    /// Directory containing the asset folders, see the `assets` module. By default, it is found
+    /// relative to the executable.
+    pub asset_root: Option<PathBuf>,
+    pub log: LogConfig,
+    pub systems: SystemsConfig,
+    pub timing: TimingConfig,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Inner size, in logical pixels. By default, the platform decides.
+    pub size: Option<(u32, u32)>,
+    pub resizable: bool,
+    /// Borderless fullscreen, on the current monitor.
+    pub fullscreen: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: String::from("Sample Application"),
+            size: None,
+            resizable: true,
+            fullscreen: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// If set, the engine starts a logger with this filter (e.g. `"mat_engine=info,warn"`).
+    /// Otherwise, setting up logging is left to the application.
+    pub filter: Option<String>,
+}
+
+/// Optional systems to initialize, besides the ones that always are. See `EngineContext`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SystemsConfig {
+    /// See `EngineContext::imgui_init()`.
+    pub imgui: bool,
+}
+
+/// See the `chrono` module.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingConfig {
+    /// How many times per second `Application::fixed_update()` is called. May be changed later,
+    /// see `chrono::set_fixed_update_rate()`.
+    pub fixed_update_rate: f64,
+    /// Maximum number of `Application::fixed_update()` calls per frame. When frames take too
+    /// long, the fixed updates that don't fit are dropped, and the game slows down, instead of
+    /// taking even longer to catch up ("spiral of death").
+    pub max_fixed_updates_per_frame: u32,
+    /// May be changed later, see `chrono::set_loop_mode()`. Invalid modes (see
+    /// `LoopMode::Capped`) are ignored, in favour of `Continuous`.
+    pub loop_mode: LoopMode,
+    /// Longest frame duration `chrono::delta_time()` reports, in seconds. Longer frames (e.g. after
+    /// a debugger breakpoint, or while the window is being dragged on some platforms) are
+    /// treated as if they lasted this long. May be changed later, see
+    /// `chrono::set_max_delta_time()`.
+    pub max_delta_time: f64,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        Self {
+            fixed_update_rate: 60.0,
+            max_fixed_updates_per_frame: 8,
+            loop_mode: LoopMode::Continuous,
+            max_delta_time: 0.25,
+        }
+    }
+}
+
+impl EngineConfig {
+    /// The default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the configuration from a TOML (`.toml`) or RON (`.ron`) file.
+    pub fn from_file(path: impl AsRef<Path>) -> AResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Ok(toml::from_str(&contents)?),
+            Some("ron") => Ok(ron::from_str(&contents)?),
+            _ => Err(anyhow!(
+                "Unknown config file format for {:?}, expected a .toml or .ron file",
+                path
+            )),
+        }
+    }
+
+    /// Loads the configuration from the file called `file_name` next to the executable (see
+    /// `from_file()`), or returns the default one if there is no such file.
+    pub fn from_executable_dir(file_name: &str) -> AResult<Self> {
+        let mut path = std::env::current_exe()?;
+        path.pop();
+        path.push(file_name);
+
+        if path.exists() {
+            Self::from_file(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.window.title = title.into();
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.window.size = Some((width, height));
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.window.resizable = resizable;
+        self
+    }
+
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.window.fullscreen = fullscreen;
+        self
+    }
+
+    /// Shorthand for setting `rendering.present_mode` to `Vsync` or `Immediate`.
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.rendering.present_mode = if vsync {
+            PresentMode::Vsync
+        } else {
+            PresentMode::Immediate
+        };
+        self
+    }
+
+    pub fn msaa_samples(mut self, samples: u32) -> Self {
+        self.rendering.msaa_samples = samples;
+        self
+    }
+
+    pub fn rendering(mut self, rendering: RenderingConfig) -> Self {
+        self.rendering = rendering;
+        self
+    }
+
+    pub fn asset_root(mut self, asset_root: impl Into<PathBuf>) -> Self {
+        self.asset_root = Some(asset_root.into());
+        self
+    }
+
+    pub fn log_filter(mut self, filter: impl Into<String>) -> Self {
+        self.log.filter = Some(filter.into());
+        self
+    }
+
+    pub fn imgui(mut self, enabled: bool) -> Self {
+        self.systems.imgui = enabled;
+        self
+    }
+
+    pub fn fixed_update_rate(mut self, updates_per_second: f64) -> Self {
+        self.timing.fixed_update_rate = updates_per_second;
+        self
+    }
+
+    pub fn loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.timing.loop_mode = loop_mode;
+        self
+    }
+
+    /// Shorthand for a precise `LoopMode::Capped`.
+    pub fn max_fps(mut self, max_fps: f64) -> Self {
+        self.timing.loop_mode = LoopMode::Capped {
+            max_fps,
+            precise: true,
+        };
+        self
+    }
+
+    /// Starts the logger, if configured to. Fails if the filter is invalid, or if a logger was
+    /// already set.
+    pub(crate) fn start_logger(&self) -> AResult<()> {
+        if let Some(filter) = &self.log.filter {
+            flexi_logger::Logger::with_str(filter)
+                .format(flexi_logger::colored_opt_format)
+                .start()?;
+        }
+        Ok(())
+    }
+}
@@ -12,7 +12,8 @@ use crate::{
 ///
 /// A - Automatically initialized;
 ///
-/// M - Manual, Need to call `EngineContext::<system_name>_init()`.
+/// M - Manual, Need to call `EngineContext::<system_name>_init()`, or to be enabled in
+/// `EngineConfig::systems`.
 ///
 /// | System    | A/M |
 /// |-----------|-----|
@@ -50,13 +51,18 @@ impl EngineContext {
     }
 
     /// Automatically called, therefore isn't exported to users of crate.
-    pub(crate) fn chrono_init(&mut self) {
-        self.chrono_system = Some(ChronoSystem::new());
+    pub(crate) fn chrono_init(&mut self, config: &crate::config::TimingConfig) {
+        self.chrono_system = Some(ChronoSystem::new(config));
     }
 
     /// Automatically called, therefore isn't exported to users of crate.
     pub(crate) fn input_init(&mut self) {
-        self.input_system = Some(InputSystem::new());
+        self.input_system = Some(InputSystem::new(
+            self.windowing_system
+                .as_ref()
+                .expect("Need windowing system to make input system")
+                .main_window(),
+        ));
     }
 
     /// Automatically called, therefore isn't exported to users of crate.
@@ -72,13 +78,14 @@ impl EngineContext {
     }
 
     /// Automatically called, therefore isn't exported to users of crate.
-    pub(crate) fn rendering_init(&mut self) {
+    pub(crate) fn rendering_init(&mut self, config: &crate::rendering::RenderingConfig) {
         self.rendering_system = Some(
             crate::rendering::RenderingSystem::new(
                 &self
                     .windowing_system
                     .as_mut()
                     .expect("Need windowing system to make rendering system"),
+                config,
             )
             .unwrap(),
         );
@@ -1,6 +1,10 @@
 use winit::dpi::PhysicalSize;
 
+use crate::arena::ArenaKey;
+
 #[derive(Debug, Copy, Clone)]
 pub struct WindowResizeEvent {
+    /// See `windowing::open_window()`.
+    pub window: ArenaKey,
     pub new_size: PhysicalSize<u32>,
 }
@@ -16,7 +16,8 @@ pub enum Event {
     PreRenderEvent,
     /// Called just after `Application::render()`
     PostRenderEvent,
-    /// Called right at the beginning of a frame (really, a winit loop), before any other events.
+    /// Called right at the beginning of a frame, before `Application::update()`. Winit loops in which
+    /// no frame runs (see `chrono::LoopMode`) don't have one.
     Start,
 }
 
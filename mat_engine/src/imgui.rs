@@ -72,18 +72,35 @@ where
     unwrap_mut(&mut ctx.imgui_system).add_render_fn(func);
 }
 
+/// Adds a window showing the rendering statistics of the last frame (see
+/// `rendering::last_frame_stats()`), including GPU pass timings when enabled (see
+/// `rendering::set_gpu_timing_enabled()`).
+///
+/// Like any other render function, this must be called every frame for the window to stay.
+///
+/// This is a wrapper method.
+pub fn add_render_stats_panel(ctx: &mut crate::EngineContext) {
+    let stats = crate::rendering::last_frame_stats(ctx).clone();
+    add_render_fn(ctx, move |ui| {
+        crate::rendering::stats::imgui_panel(ui, &stats)
+    });
+}
+
 /// Actually render the closures in the queue. See `add_render_fn()` for how to add these closures.
 /// It also clears the queue once it is done.
 ///
+/// Imgui is drawn on top of whatever `target` already contains. When using a `RenderGraph`,
+/// this is usually called from the last pass writing to the swapchain.
+///
 /// Note: It additionally operates on a global, thread shared queue, iff `USE_GLOBAL_DEBUG_RENDER_FNS`
 /// is true. In this case, see `global_debug_add_render_fn()` for info.
 ///
 /// This is a wrapper method.
-pub fn render(ctx: &mut crate::EngineContext, frt: &mut crate::rendering::FrameRenderTarget) {
+pub fn render(ctx: &mut crate::EngineContext, target: &mut crate::rendering::RenderTarget) {
     unwrap_mut(&mut ctx.imgui_system).render(
         unwrap_ref(&ctx.windowing_system),
         unwrap_mut(&mut ctx.rendering_system),
-        frt,
+        target,
     );
 }
 
@@ -167,7 +184,7 @@ impl ImguiSystem {
         &mut self,
         windowing_system: &crate::windowing::WindowingSystem,
         rendering_system: &mut crate::rendering::RenderingSystem,
-        frt: &mut crate::rendering::FrameRenderTarget,
+        target: &mut crate::rendering::RenderTarget,
     ) {
         let mut ui = self.imgui_ctx.frame();
 
@@ -197,7 +214,7 @@ impl ImguiSystem {
         let draw_data = ui.render();
 
         self.rendering_subsystem
-            .perform_render(draw_data, rendering_system, frt);
+            .perform_render(draw_data, rendering_system, target);
     }
 
     /// See wrapper method.
@@ -1,6 +1,20 @@
-use crate::utils::unwrap_ref;
+//! Cursor position and appearance.
+//!
+//! Cursor appearance functions act on the main window, or any window for the ones ending in `_of`,
+//! and forward to the windowing system.
+//!
+//! # Custom cursor images
+//!
+//! The windowing library the engine uses cannot set custom cursor images. Instead, hide the cursor
+//! with `set_cursor_visible(false)` and have the renderer draw the image, see
+//! `rendering::rend_2d::software_cursor::SoftwareCursor`.
 
-/// Returns whether the cursor is inside the screen.
+use anyhow::Result as AResult;
+
+use crate::arena::ArenaKey;
+use crate::utils::{unwrap_mut, unwrap_ref};
+
+/// Returns whether the cursor is inside the screen (the main window).
 /// If state is unknown, returns false
 pub fn is_cursor_inside_screen(ctx: &crate::context::EngineContext) -> bool {
     let (state, _) = get_cursor_info(ctx);
@@ -18,18 +32,130 @@ pub fn get_cursor_position(ctx: &crate::context::EngineContext) -> Option<Cursor
     pos
 }
 
+/// Cursor state and position relative to the main window.
 pub fn get_cursor_info(
     ctx: &crate::context::EngineContext,
 ) -> (CursorState, Option<CursorPosition>) {
-    let cs = unwrap_ref(&ctx.input_system).cursor_state;
+    get_window_cursor_info(ctx, crate::windowing::main_window(ctx))
+}
 
-    if let CursorState::InsideScreen = cs {
-        (cs, Some(unwrap_ref(&ctx.input_system).cursor_pos))
-    } else {
-        (cs, None)
+/// Like `get_cursor_info()`, but relative to any window, see `windowing::open_window()`.
+pub fn get_window_cursor_info(
+    ctx: &crate::context::EngineContext,
+    window: ArenaKey,
+) -> (CursorState, Option<CursorPosition>) {
+    match unwrap_ref(&ctx.input_system).cursors.get(&window) {
+        Some(cursor) => {
+            if let CursorState::InsideScreen = cursor.state {
+                (cursor.state, Some(cursor.pos))
+            } else {
+                (cursor.state, None)
+            }
+        }
+        None => (CursorState::Unknown, None),
     }
 }
 
+/// The window the cursor is inside of, if any.
+pub fn hovered_window(ctx: &crate::context::EngineContext) -> Option<ArenaKey> {
+    unwrap_ref(&ctx.input_system)
+        .cursors
+        .iter()
+        .find(|(_, cursor)| matches!(cursor.state, CursorState::InsideScreen))
+        .map(|(&window, _)| window)
+}
+
+/// Sum of the raw mouse movements received this frame, in unspecified units (usually mouse
+/// "counts", unaffected by the OS pointer speed and acceleration). Positive y is downwards.
+///
+/// Unlike cursor positions, the movements are received even if the cursor is grabbed and stuck
+/// against the edge of the window. They are ignored while the window is unfocused.
+/// Use with `set_relative_mouse_mode()` for FPS-style aiming.
+pub fn get_mouse_motion(ctx: &crate::context::EngineContext) -> (f64, f64) {
+    unwrap_ref(&ctx.input_system).mouse_motion
+}
+
+pub fn set_cursor_visible(ctx: &mut crate::EngineContext, visible: bool) {
+    set_cursor_visible_of(ctx, crate::windowing::main_window(ctx), visible)
+}
+
+/// This is a wrapper method.
+pub fn set_cursor_visible_of(ctx: &mut crate::EngineContext, window: ArenaKey, visible: bool) {
+    unwrap_mut(&mut ctx.windowing_system).set_cursor_visible_of(window, visible)
+}
+
+/// Grabs the cursor, preventing it from leaving the window. Depending on the platform, the cursor
+/// is either confined to the window (Windows, X11), or locked in place (macOS, Wayland). Fails if
+/// the platform doesn't support either.
+///
+/// The grab is released by the OS while the window is unfocused, and restored once it is focused
+/// again.
+pub fn set_cursor_grabbed(ctx: &mut crate::EngineContext, grabbed: bool) -> AResult<()> {
+    set_cursor_grabbed_of(ctx, crate::windowing::main_window(ctx), grabbed)
+}
+
+/// This is a wrapper method.
+pub fn set_cursor_grabbed_of(
+    ctx: &mut crate::EngineContext,
+    window: ArenaKey,
+    grabbed: bool,
+) -> AResult<()> {
+    unwrap_mut(&mut ctx.windowing_system).set_cursor_grabbed_of(window, grabbed)
+}
+
+/// Relative mouse mode hides and grabs the cursor, so that the mouse can be used to aim or move a
+/// camera, using `get_mouse_motion()`. Cursor positions are meaningless in this mode.
+///
+/// Disabling it restores the visibility and grab set with `set_cursor_visible()` and
+/// `set_cursor_grabbed()`.
+pub fn set_relative_mouse_mode(ctx: &mut crate::EngineContext, enabled: bool) -> AResult<()> {
+    set_relative_mouse_mode_of(ctx, crate::windowing::main_window(ctx), enabled)
+}
+
+/// This is a wrapper method.
+pub fn set_relative_mouse_mode_of(
+    ctx: &mut crate::EngineContext,
+    window: ArenaKey,
+    enabled: bool,
+) -> AResult<()> {
+    unwrap_mut(&mut ctx.windowing_system).set_relative_mouse_mode_of(window, enabled)
+}
+
+pub fn is_relative_mouse_mode(ctx: &crate::EngineContext) -> bool {
+    is_relative_mouse_mode_of(ctx, crate::windowing::main_window(ctx))
+}
+
+/// This is a wrapper method.
+pub fn is_relative_mouse_mode_of(ctx: &crate::EngineContext, window: ArenaKey) -> bool {
+    unwrap_ref(&ctx.windowing_system).is_relative_mouse_mode_of(window)
+}
+
+/// Moves the cursor to a position in the window, in physical pixels. Not supported on all
+/// platforms (e.g. Wayland).
+pub fn set_cursor_position(ctx: &mut crate::EngineContext, x: f64, y: f64) -> AResult<()> {
+    set_cursor_position_of(ctx, crate::windowing::main_window(ctx), x, y)
+}
+
+/// This is a wrapper method.
+pub fn set_cursor_position_of(
+    ctx: &mut crate::EngineContext,
+    window: ArenaKey,
+    x: f64,
+    y: f64,
+) -> AResult<()> {
+    unwrap_mut(&mut ctx.windowing_system).set_cursor_position_of(window, x, y)
+}
+
+/// Sets the system cursor shown while the cursor is over the window.
+pub fn set_cursor_icon(ctx: &mut crate::EngineContext, icon: CursorIcon) {
+    set_cursor_icon_of(ctx, crate::windowing::main_window(ctx), icon)
+}
+
+/// This is a wrapper method.
+pub fn set_cursor_icon_of(ctx: &mut crate::EngineContext, window: ArenaKey, icon: CursorIcon) {
+    unwrap_mut(&mut ctx.windowing_system).set_cursor_icon_of(window, icon)
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum CursorState {
     Unknown,
@@ -42,3 +168,57 @@ pub struct CursorPosition {
     pub x: f64,
     pub y: f64,
 }
+
+/// Standard system cursors. Their look depends on the platform.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CursorIcon {
+    /// Usually an arrow.
+    Default,
+    /// Usually a hand, for clickable things such as links.
+    Pointer,
+    /// Text selection (I-beam).
+    Text,
+    Crosshair,
+    /// Something is to be moved.
+    Move,
+    /// The application is busy, and cannot be interacted with.
+    Wait,
+    /// The application is busy, but can still be interacted with.
+    Progress,
+    Help,
+    NotAllowed,
+    /// Something can be grabbed.
+    Grab,
+    /// Something is being grabbed.
+    Grabbing,
+    /// Left-right resize.
+    ResizeHorizontal,
+    /// Up-down resize.
+    ResizeVertical,
+    /// Top right to bottom left resize.
+    ResizeNeSw,
+    /// Top left to bottom right resize.
+    ResizeNwSe,
+}
+
+impl CursorIcon {
+    pub(crate) fn to_winit(self) -> winit::window::CursorIcon {
+        match self {
+            CursorIcon::Default => winit::window::CursorIcon::Default,
+            CursorIcon::Pointer => winit::window::CursorIcon::Hand,
+            CursorIcon::Text => winit::window::CursorIcon::Text,
+            CursorIcon::Crosshair => winit::window::CursorIcon::Crosshair,
+            CursorIcon::Move => winit::window::CursorIcon::Move,
+            CursorIcon::Wait => winit::window::CursorIcon::Wait,
+            CursorIcon::Progress => winit::window::CursorIcon::Progress,
+            CursorIcon::Help => winit::window::CursorIcon::Help,
+            CursorIcon::NotAllowed => winit::window::CursorIcon::NotAllowed,
+            CursorIcon::Grab => winit::window::CursorIcon::Grab,
+            CursorIcon::Grabbing => winit::window::CursorIcon::Grabbing,
+            CursorIcon::ResizeHorizontal => winit::window::CursorIcon::EwResize,
+            CursorIcon::ResizeVertical => winit::window::CursorIcon::NsResize,
+            CursorIcon::ResizeNeSw => winit::window::CursorIcon::NeswResize,
+            CursorIcon::ResizeNwSe => winit::window::CursorIcon::NwseResize,
+        }
+    }
+}
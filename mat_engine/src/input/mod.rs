@@ -1,6 +1,7 @@
 pub mod button;
 pub mod cursor;
-use crate::utils::unwrap_mut;
+use crate::arena::ArenaKey;
+use crate::utils::{unwrap_mut, unwrap_ref};
 use std::collections::HashMap;
 
 /// Returns true iff the button is down (i.e was pressed this frame or is being held).
@@ -31,6 +32,30 @@ pub fn is_button_pressed(ctx: &mut crate::EngineContext, button_id: &button::But
     }
 }
 
+/// Like `is_button_down()`, but only true if `window` has focus, i.e. is the window receiving
+/// keyboard input. See `windowing::open_window()`.
+pub fn is_button_down_in(
+    ctx: &mut crate::EngineContext,
+    window: ArenaKey,
+    button_id: &button::ButtonId,
+) -> bool {
+    focused_window(ctx) == Some(window) && is_button_down(ctx, button_id)
+}
+
+/// Like `is_button_pressed()`, but only true if `window` has focus. See `is_button_down_in()`.
+pub fn is_button_pressed_in(
+    ctx: &mut crate::EngineContext,
+    window: ArenaKey,
+    button_id: &button::ButtonId,
+) -> bool {
+    focused_window(ctx) == Some(window) && is_button_pressed(ctx, button_id)
+}
+
+/// The window that has focus, if any of the application's windows does.
+pub fn focused_window(ctx: &crate::EngineContext) -> Option<ArenaKey> {
+    unwrap_ref(&ctx.input_system).focused_window
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct ModifierKeyState {
     shift: bool,
@@ -44,9 +69,12 @@ pub(crate) struct InputSystem {
     // I'm wondering whether the second might be better (sth like UP -> DOWN_FIRST -> DOWN_HELD -> UP)
     frame_count: u64,
 
-    cursor_state: cursor::CursorState,
-    /// May give incorrect info if cursor_state isn't InsideScreen.
-    cursor_pos: cursor::CursorPosition,
+    /// Cursor state and position relative to each window, see `cursor::get_window_cursor_info()`.
+    /// Windows the cursor never moved over have no entry.
+    cursors: HashMap<ArenaKey, WindowCursor>,
+    /// Raw mouse movement received this frame, see `cursor::get_mouse_motion()`.
+    mouse_motion: (f64, f64),
+    focused_window: Option<ArenaKey>,
 
     modifier_state: ModifierKeyState,
 
@@ -55,14 +83,13 @@ pub(crate) struct InputSystem {
 }
 
 impl InputSystem {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(main_window: ArenaKey) -> Self {
         Self {
             frame_count: 0,
-            cursor_state: cursor::CursorState::Unknown,
-            cursor_pos: cursor::CursorPosition {
-                x: std::f64::NAN,
-                y: std::f64::NAN,
-            },
+            cursors: HashMap::new(),
+            mouse_motion: (0.0, 0.0),
+            // winit doesn't always send an initial Focused event, assume the new window is focused.
+            focused_window: Some(main_window),
             modifier_state: ModifierKeyState {
                 shift: false,
                 alt: false,
@@ -83,15 +110,16 @@ impl InputSystem {
             .unwrap_or(&button::ButtonState::Up { change_frame: 0 })
     }
 
-    /// Since engine event Start (winit event NewEvents) is always the first event emitted in a "frame",
-    /// followed by inputs, then only after all inputs is Update (winit event MainEventsCleared) called,
-    /// this means it should be safe to update the frame counter at Start and depend on it being the same
-    /// for all relevant inputs, and also the same at the time the inputs are processed (assuming the user
+    /// Inputs are handled before `Application::update()` (engine event PreUpdate, winit event
+    /// MainEventsCleared), possibly over several winit loops (see `chrono::LoopMode`), so the frame
+    /// counter is updated once the inputs have been read, after `Application::update()`, and all
+    /// inputs received until the next update are counted in the next frame (assuming the user
     /// calls this module's input reading functions inside `Application::update()`).
-    /// This function should therefore be called by the event handler for engine event Start
+    /// This function should therefore be called by the event handler for engine event PostUpdate
     /// (see `EventReceiver` impl for `InputSystem`).
     fn start_new_frame(&mut self) {
         self.frame_count += 1;
+        self.mouse_motion = (0.0, 0.0);
         //log::trace!("start_new_frame {:?}", self.frame_count);
     }
 
@@ -138,7 +166,31 @@ impl InputSystem {
         };
     }
 
-    pub(crate) fn receive_winit_windowing_event(&mut self, evt: &winit::event::WindowEvent) {
+    /// Drops what is known about a closed window.
+    pub(crate) fn forget_window(&mut self, window: ArenaKey) {
+        self.cursors.remove(&window);
+
+        if self.focused_window == Some(window) {
+            self.focused_window = None;
+        }
+    }
+
+    fn cursor_mut(&mut self, window: ArenaKey) -> &mut WindowCursor {
+        self.cursors.entry(window).or_insert(WindowCursor {
+            state: cursor::CursorState::Unknown,
+            pos: cursor::CursorPosition {
+                x: std::f64::NAN,
+                y: std::f64::NAN,
+            },
+        })
+    }
+
+    /// `window` is the window that received the event.
+    pub(crate) fn receive_winit_windowing_event(
+        &mut self,
+        window: ArenaKey,
+        evt: &winit::event::WindowEvent,
+    ) {
         match evt {
             winit::event::WindowEvent::Resized(_)
             | winit::event::WindowEvent::CloseRequested
@@ -162,9 +214,13 @@ impl InputSystem {
             winit::event::WindowEvent::Touch(_) => {
                 // log::warn!("Input System: Currently unsupported event Touch");
             }
-            // TODO: Should this be handled by input or windowing?
-            winit::event::WindowEvent::Focused(_) => {
-                // log::warn!("Input System: Currently unsupported event Focused");
+            // Also handled by the windowing system, which restores cursor grabs.
+            winit::event::WindowEvent::Focused(focused) => {
+                if *focused {
+                    self.focused_window = Some(window);
+                } else if self.focused_window == Some(window) {
+                    self.focused_window = None;
+                }
             }
             winit::event::WindowEvent::TouchpadPressure { .. } => {
                 // log::warn!("Input System: Currently unsupported event TouchpadPressure");
@@ -177,14 +233,15 @@ impl InputSystem {
             }
             // The actual interesting code should be here...
             winit::event::WindowEvent::CursorMoved { position, .. } => {
-                self.cursor_pos.x = position.x;
-                self.cursor_pos.y = position.y;
+                let cursor = self.cursor_mut(window);
+                cursor.pos.x = position.x;
+                cursor.pos.y = position.y;
             }
             winit::event::WindowEvent::CursorEntered { .. } => {
-                self.cursor_state = cursor::CursorState::InsideScreen;
+                self.cursor_mut(window).state = cursor::CursorState::InsideScreen;
             }
             winit::event::WindowEvent::CursorLeft { .. } => {
-                self.cursor_state = cursor::CursorState::OutsideScreen;
+                self.cursor_mut(window).state = cursor::CursorState::OutsideScreen;
             }
             winit::event::WindowEvent::ModifiersChanged(modifiers) => {
                 self.modifier_state.shift = modifiers.shift();
@@ -241,24 +298,41 @@ impl InputSystem {
         }
     }
 
-    pub(crate) fn receive_winit_device_event(&mut self, _evt: &winit::event::DeviceEvent) {
-        //log::trace!(
-        //    "Input system received device event but didn't process it: {:?}",
-        //    evt
-        //);
+    pub(crate) fn receive_winit_device_event(&mut self, evt: &winit::event::DeviceEvent) {
+        match evt {
+            // Device events are received regardless of which window has focus, if any.
+            winit::event::DeviceEvent::MouseMotion { delta } if self.focused_window.is_some() => {
+                self.mouse_motion.0 += delta.0;
+                self.mouse_motion.1 += delta.1;
+            }
+            _ => {
+                //log::trace!(
+                //    "Input system received device event but didn't process it: {:?}",
+                //    evt
+                //);
+            }
+        }
     }
 }
 
+struct WindowCursor {
+    state: cursor::CursorState,
+    /// May give incorrect info if state isn't InsideScreen.
+    pos: cursor::CursorPosition,
+}
+
 impl crate::event::EventReceiver for InputSystem {
     fn receives_event_type(evt_type: crate::event::types::EventType) -> bool {
         match evt_type {
-            crate::event::types::EventType::StartEvent => true,
+            crate::event::types::EventType::PostUpdateEvent => true,
             _ => false,
         }
     }
     fn receive_event(ctx: &mut crate::EngineContext, evt: crate::event::Event) {
         match evt {
-            crate::event::Event::Start => unwrap_mut(&mut ctx.input_system).start_new_frame(),
+            crate::event::Event::PostUpdateEvent => {
+                unwrap_mut(&mut ctx.input_system).start_new_frame()
+            }
             _ => unreachable!(),
         }
     }
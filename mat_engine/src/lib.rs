@@ -31,6 +31,7 @@ pub mod application;
 pub mod arena;
 pub mod assets;
 pub mod chrono;
+pub mod config;
 pub mod context;
 pub mod event;
 pub mod imgui;
@@ -38,6 +39,7 @@ pub mod input;
 pub mod rendering;
 pub mod windowing;
 
+pub use config::EngineConfig;
 pub use context::EngineContext;
 
 const DEBUG_TRACE_ENGINE_START_AND_END: bool = false;
@@ -46,8 +48,19 @@ const DEBUG_TRACE_EVENT_LOOP_STEPS: bool = false;
 /// Execute a given Application. Doesn't return, use the `Application::close()` method to
 /// gracefully handle shutdown. See module `windowing` for more info.
 ///
+/// The engine is set up according to `config`, see `EngineConfig`.
+///
 /// Generic over Application type.
-pub fn run<T: application::Application + 'static>() -> ! {
+pub fn run<T: application::Application + 'static>(config: EngineConfig) -> ! {
+    // Not fatal: the application may have set a logger already, in which case this reaches it.
+    if let Err(e) = config.start_logger() {
+        log::warn!("Could not start the logger: {}", e);
+    }
+
+    if let Some(asset_root) = &config.asset_root {
+        assets::set_asset_root(asset_root.clone());
+    }
+
     if DEBUG_TRACE_ENGINE_START_AND_END {
         log::trace!("Starting mat_engine");
     }
@@ -56,25 +69,25 @@ pub fn run<T: application::Application + 'static>() -> ! {
 
     let winit_ev_loop = windowing::make_winit_event_loop();
 
-    ctx.chrono_init();
-
-    ctx.input_init();
+    ctx.chrono_init(&config.timing);
 
     ctx.windowing_init(
-        windowing::make_default_winit_window(&winit_ev_loop),
+        windowing::make_winit_window(&winit_ev_loop, &config.window)
+            .expect("Could not obtain winit window"),
         windowing::make_winit_event_loop_proxy(&winit_ev_loop),
     );
 
-    ctx.rendering_init();
+    ctx.input_init();
 
-    let mut app = Box::new(T::new(&mut ctx));
+    ctx.rendering_init(&config.rendering);
+
+    if config.systems.imgui {
+        ctx.imgui_init();
+    }
 
-    winit_ev_loop.run(move |event, _, control_flow| {
-        // Immediately start the next loop once current is done, instead of waiting
-        // for user input.
-        // TODO: This may be useful for frame-rate limiting, I'm unsure. Need to examine.
-        *control_flow = winit::event_loop::ControlFlow::Poll;
+    let mut app = Box::new(T::new(&mut ctx));
 
+    winit_ev_loop.run(move |event, window_target, control_flow| {
         // If the application is to be closed, we may skip everything below, and just quit.
         if let winit::event::Event::LoopDestroyed = event {
             app.close(&mut ctx);
@@ -93,10 +106,15 @@ pub fn run<T: application::Application + 'static>() -> ! {
             //
             // Cycle:
             //
-            // START (NewEvents) -> Main events... -> UPDATE (MainEventsCleared) -> RENDER (RedrawRequested)
-            // -> Post render step (RedrawEventsCleared) -> Loop again;
+            // NewEvents -> Main events... -> START, UPDATE (MainEventsCleared) -> RENDER (RedrawRequested)
+            // -> Wait for next frame (RedrawEventsCleared) -> Loop again;
+            //
+            // Depending on the `LoopMode`, START, UPDATE and RENDER may be skipped in some loops, see
+            // `ChronoSystem::is_frame_due()`. Events are handled nonetheless.
             match &event {
                 winit::event::Event::UserEvent(request) => {
+                    ctx.chrono_system.as_mut().unwrap().receive_activity();
+
                     match request {
                         windowing::Request::Quit => {
                             // goes to winit::event::Event::LoopDestroyed, after processing
@@ -105,45 +123,73 @@ pub fn run<T: application::Application + 'static>() -> ! {
                         }
                     };
                 }
-                // Currently we ignore the window id, is this a problem???
-                winit::event::Event::WindowEvent { event, .. } => {
-                    match event {
-                        winit::event::WindowEvent::CloseRequested => {
-                            // goes to winit::event::Event::LoopDestroyed, after processing
-                            // queued/outstanding events
-                            *control_flow = winit::event_loop::ControlFlow::Exit;
+                winit::event::Event::WindowEvent { window_id, event } => {
+                    // Events of windows that were just closed may still come through.
+                    let window = ctx.windowing_system.as_ref().unwrap().window_key(*window_id);
+
+                    if window.is_some() {
+                        ctx.chrono_system.as_mut().unwrap().receive_activity();
+                    }
+
+                    match (window, event) {
+                        (None, ignored) => {
+                            log::trace!("Ignored event of closed window {:?}", ignored);
                         }
-                        winit::event::WindowEvent::Resized(new_size) => {
+                        (Some(window), winit::event::WindowEvent::CloseRequested) => {
+                            if window == windowing::main_window(&ctx) {
+                                // goes to winit::event::Event::LoopDestroyed, after processing
+                                // queued/outstanding events
+                                *control_flow = winit::event_loop::ControlFlow::Exit;
+                            } else {
+                                windowing::close_window(&mut ctx, window);
+                            }
+                        }
+                        (Some(window), winit::event::WindowEvent::Resized(new_size)) => {
                             // Note that if we don't call process_engine_events, that means this event will
                             // stay in the queue until that happens.
                             ctx.event_queue.push_event(event::Event::WindowResizeEvent(
                                 event::events::WindowResizeEvent {
+                                    window,
                                     new_size: *new_size
                                 },
                             ));
                         }
-                        winit::event::WindowEvent::ScaleFactorChanged {
-                            new_inner_size, ..
-                        } => {
+                        (
+                            Some(window),
+                            winit::event::WindowEvent::ScaleFactorChanged {
+                                new_inner_size, ..
+                            },
+                        ) => {
                             // Note that if we don't call process_engine_events, that means this event will
                             // stay in the queue until that happens.
                             ctx.event_queue.push_event(event::Event::WindowResizeEvent(
                                 event::events::WindowResizeEvent {
+                                    window,
                                     new_size: **new_inner_size
                                 },
                             ));
                         }
-                        ignored @ winit::event::WindowEvent::ThemeChanged(_)
-                        | ignored @ winit::event::WindowEvent::Moved(_)
-                        | ignored @ winit::event::WindowEvent::Destroyed => {
+                        (Some(window), winit::event::WindowEvent::Focused(focused)) => {
+                            ctx.windowing_system
+                                .as_mut()
+                                .unwrap()
+                                .receive_focus_change(window, *focused);
+                            ctx.input_system
+                                .as_mut()
+                                .unwrap()
+                                .receive_winit_windowing_event(window, event);
+                        }
+                        (Some(_), ignored @ winit::event::WindowEvent::ThemeChanged(_))
+                        | (Some(_), ignored @ winit::event::WindowEvent::Moved(_))
+                        | (Some(_), ignored @ winit::event::WindowEvent::Destroyed) => {
                             log::trace!("Ignored windowing event {:?}", ignored);
                         }
                         // Input events
-                        input_evt => {
+                        (Some(window), input_evt) => {
                             ctx.input_system
                                 .as_mut()
                                 .unwrap()
-                                .receive_winit_windowing_event(input_evt);
+                                .receive_winit_windowing_event(window, input_evt);
                         }
                     };
                 }
@@ -154,36 +200,50 @@ pub fn run<T: application::Application + 'static>() -> ! {
                         .receive_winit_device_event(event);
                 }
 
-                // +-------+
-                // | START |
-                // +-------+
                 winit::event::Event::NewEvents(_) => {
                     if DEBUG_TRACE_EVENT_LOOP_STEPS {
-                        log::trace!("*** Start of Frame - after this, will run main events - winit NewEvents");
+                        log::trace!("*** Start of loop - after this, will run main events - winit NewEvents");
                     }
 
-                    ctx.chrono_system.as_mut().unwrap().start_new_frame();
+                    windowing::open_pending_windows(&mut ctx, window_target);
 
                     // If the queue isn't empty here, we made a serious mistake somewhere.
                     //
-                    // Ensures events don't persist across frames.
+                    // Ensures events don't persist across loops.
                     assert!(ctx.event_queue.is_empty());
-                    ctx.event_queue.push_event(event::Event::Start);
-                    process_engine_events(&mut ctx, app.as_mut());
                 }
 
-                // +--------+
-                // | UPDATE |
-                // +--------+
+                // +---------------+
+                // | START, UPDATE |
+                // +---------------+
+                winit::event::Event::MainEventsCleared
+                    if !ctx.chrono_system.as_ref().unwrap().is_frame_due() =>
+                {
+                    if DEBUG_TRACE_EVENT_LOOP_STEPS {
+                        log::trace!("*** Finished main events, frame isn't due - winit MainEventsCleared");
+                    }
+
+                    // Events queued while handling winit events, e.g. resizes.
+                    process_engine_events(&mut ctx, app.as_mut());
+                }
                 winit::event::Event::MainEventsCleared => {
                     if DEBUG_TRACE_EVENT_LOOP_STEPS {
-                        log::trace!("*** Finished main events, now running UPDATE - winit MainEventsCleared");
+                        log::trace!("*** Finished main events, now running START and UPDATE - winit MainEventsCleared");
                     }
 
+                    ctx.chrono_system.as_mut().unwrap().start_new_frame();
+
+                    ctx.event_queue.push_event(event::Event::Start);
+
                     // Since the Queue is FIFO, process outstanding events, with the last being PreUpdateEvent
                     ctx.event_queue.push_event(event::Event::PreUpdateEvent);
                     process_engine_events(&mut ctx, app.as_mut());
 
+                    let fixed_updates = ctx.chrono_system.as_mut().unwrap().take_fixed_updates();
+                    for _ in 0..fixed_updates {
+                        app.fixed_update(&mut ctx);
+                    }
+
                     app.update(&mut ctx);
 
                     ctx.event_queue.push_event(event::Event::PostUpdateEvent);
@@ -192,16 +252,20 @@ pub fn run<T: application::Application + 'static>() -> ! {
                     // After `update()`, we want to run `render()`
                     {
                         ctx.windowing_system
-                            .as_mut()
+                            .as_ref()
                             .unwrap()
-                            .winit_window
+                            .get_window_ref()
                             .request_redraw();
                     }
                 }
                 // +--------+
                 // | RENDER |
                 // +--------+
-                winit::event::Event::RedrawRequested(_) => {
+                // `render()` renders every window, once per frame. Redraws requested by the OS for
+                // other windows are ignored, they are drawn anyway.
+                winit::event::Event::RedrawRequested(window_id)
+                    if *window_id == ctx.windowing_system.as_ref().unwrap().get_window_ref().id() =>
+                {
                     if DEBUG_TRACE_EVENT_LOOP_STEPS {
                         log::trace!("*** Finished UPDATE, now running RENDER - winit RedrawRequested");
                     }
@@ -219,7 +283,9 @@ pub fn run<T: application::Application + 'static>() -> ! {
                     if DEBUG_TRACE_EVENT_LOOP_STEPS {
                         log::trace!("*** Finished RENDER - winit RedrawEventsCleared");
                     }
-                    // No need for any behavior here.
+
+                    // Has no effect once quitting, as winit ignores changes after Exit.
+                    *control_flow = ctx.chrono_system.as_mut().unwrap().wait_for_next_frame();
                 }
                 winit::event::Event::LoopDestroyed => {
                     unreachable!("Should be handled by if let");
@@ -0,0 +1,90 @@
+//! Information about the adapter (GPU, or software renderer) the rendering system runs on, see
+//! `rendering::adapter_info()`.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdapterInfo {
+    pub name: String,
+    /// PCI vendor id, when applicable.
+    pub vendor: usize,
+    /// PCI device id, when applicable.
+    pub device: usize,
+    pub device_type: DeviceType,
+    pub backend: Backend,
+    pub limits: AdapterLimits,
+}
+
+impl AdapterInfo {
+    pub(super) fn new(adapter: &wgpu::Adapter) -> Self {
+        let info = adapter.get_info();
+        let limits = adapter.limits();
+
+        Self {
+            name: info.name,
+            vendor: info.vendor,
+            device: info.device,
+            device_type: match info.device_type {
+                wgpu::DeviceType::IntegratedGpu => DeviceType::IntegratedGpu,
+                wgpu::DeviceType::DiscreteGpu => DeviceType::DiscreteGpu,
+                wgpu::DeviceType::VirtualGpu => DeviceType::VirtualGpu,
+                wgpu::DeviceType::Cpu => DeviceType::Cpu,
+                _ => DeviceType::Other,
+            },
+            backend: match info.backend {
+                wgpu::Backend::Vulkan => Backend::Vulkan,
+                wgpu::Backend::Metal => Backend::Metal,
+                wgpu::Backend::Dx12 => Backend::Dx12,
+                wgpu::Backend::Dx11 => Backend::Dx11,
+                wgpu::Backend::Gl => Backend::Gl,
+                _ => Backend::Other,
+            },
+            limits: AdapterLimits {
+                max_texture_dimension_2d: limits.max_texture_dimension_2d,
+                max_bind_groups: limits.max_bind_groups,
+                max_uniform_buffer_binding_size: limits.max_uniform_buffer_binding_size,
+                max_storage_buffer_binding_size: limits.max_storage_buffer_binding_size,
+                max_vertex_buffers: limits.max_vertex_buffers,
+                max_vertex_attributes: limits.max_vertex_attributes,
+                max_push_constant_size: limits.max_push_constant_size,
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeviceType {
+    IntegratedGpu,
+    DiscreteGpu,
+    VirtualGpu,
+    /// Software renderer.
+    Cpu,
+    Other,
+}
+
+/// Graphics API the adapter is used through.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Vulkan,
+    Metal,
+    Dx12,
+    Dx11,
+    Gl,
+    Other,
+}
+
+/// The most relevant of the limits the adapter supports.
+///
+/// Note that the engine only relies on the default limits, which every adapter supports.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AdapterLimits {
+    /// Maximum width and height of a texture, in pixels.
+    pub max_texture_dimension_2d: u32,
+    pub max_bind_groups: u32,
+    /// In bytes.
+    pub max_uniform_buffer_binding_size: u32,
+    /// In bytes.
+    pub max_storage_buffer_binding_size: u32,
+    pub max_vertex_buffers: u32,
+    pub max_vertex_attributes: u32,
+    /// In bytes.
+    pub max_push_constant_size: u32,
+}
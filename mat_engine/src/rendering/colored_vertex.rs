@@ -3,17 +3,18 @@ use crate::rendering::vertex_trait::Vertex;
 use bytemuck::{Pod, Zeroable};
 use std::ops::Range;
 
+/// Untextured vertex. Color is rgba, so that it may be blended.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 pub(crate) struct ColoredVertex {
     pub(crate) position: [f32; 3],
-    pub(crate) color: [f32; 3],
+    pub(crate) color: [f32; 4],
 }
 
 // Asserts that there is no padding in ColoredVertex
 static_assertions::const_assert_eq!(
     std::mem::size_of::<ColoredVertex>(),
-    std::mem::size_of::<f32>() * 6
+    std::mem::size_of::<f32>() * 7
 );
 
 impl VertexBufferable for ColoredVertex {
@@ -35,7 +36,7 @@ impl VertexBufferable for ColoredVertex {
                 wgpu::VertexAttribute {
                     // Color
                     offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
-                    format: wgpu::VertexFormat::Float32x3,
+                    format: wgpu::VertexFormat::Float32x4,
                     shader_location: start_shader_location + 1,
                 },
             ],
@@ -0,0 +1,147 @@
+//! Settings the rendering system is initialized with, see `RenderingConfig`.
+
+use serde::{Deserialize, Serialize};
+
+/// Settings the rendering system is initialized with, see `EngineConfig::rendering`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderingConfig {
+    /// Number of samples per pixel used for multisample anti-aliasing (MSAA). 1 disables it.
+    ///
+    /// Must be a power of two, no greater than 16. Only 1 and 4 are supported everywhere.
+    pub msaa_samples: u32,
+    /// May be changed later, see `rendering::set_present_mode()`.
+    pub present_mode: PresentMode,
+    /// Graphics APIs that may be used. The adapter is picked among those available through them.
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    /// Use a software (CPU) adapter, even if a GPU is available. Fails to initialize if there is
+    /// none. Mostly useful for testing, for example on CI machines.
+    pub force_fallback_adapter: bool,
+}
+
+impl Default for RenderingConfig {
+    fn default() -> Self {
+        Self {
+            msaa_samples: 1,
+            present_mode: PresentMode::Vsync,
+            backends: Backends::ALL,
+            power_preference: PowerPreference::Default,
+            force_fallback_adapter: false,
+        }
+    }
+}
+
+/// How frames are presented to the screen. Modes the surface doesn't support fall back to `Vsync`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentMode {
+    /// Frames are presented in sync with the display's refresh rate, waiting for it if
+    /// necessary. No tearing, and supported everywhere.
+    Vsync,
+    /// Frames are presented as soon as they are ready. Lowest latency, but may tear.
+    Immediate,
+    /// Like `Vsync`, but rendering doesn't wait: the latest complete frame is presented at each
+    /// refresh, and older ones are dropped. No tearing, and lower latency than `Vsync`.
+    Mailbox,
+}
+
+impl PresentMode {
+    pub(super) fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Vsync => wgpu::PresentMode::Fifo,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerPreference {
+    /// Let the platform decide.
+    Default,
+    /// Prefer integrated GPUs, to save battery.
+    LowPower,
+    /// Prefer discrete GPUs.
+    HighPerformance,
+}
+
+impl PowerPreference {
+    pub(super) fn to_wgpu(self) -> wgpu::PowerPreference {
+        match self {
+            PowerPreference::Default => wgpu::PowerPreference::default(),
+            PowerPreference::LowPower => wgpu::PowerPreference::LowPower,
+            PowerPreference::HighPerformance => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// Set of graphics APIs. APIs missing from config files are excluded. Defaults to `ALL`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backends {
+    #[serde(default)]
+    pub vulkan: bool,
+    #[serde(default)]
+    pub metal: bool,
+    #[serde(default)]
+    pub dx12: bool,
+    #[serde(default)]
+    pub dx11: bool,
+    #[serde(default)]
+    pub gl: bool,
+}
+
+impl Default for Backends {
+    fn default() -> Self {
+        Backends::ALL
+    }
+}
+
+impl Backends {
+    pub const ALL: Backends = Backends {
+        vulkan: true,
+        metal: true,
+        dx12: true,
+        dx11: true,
+        gl: true,
+    };
+
+    /// The modern APIs, which are the best supported: Vulkan, Metal and DX12.
+    pub const PRIMARY: Backends = Backends {
+        vulkan: true,
+        metal: true,
+        dx12: true,
+        dx11: false,
+        gl: false,
+    };
+
+    /// The older APIs, with limited support: DX11 and OpenGL.
+    pub const SECONDARY: Backends = Backends {
+        vulkan: false,
+        metal: false,
+        dx12: false,
+        dx11: true,
+        gl: true,
+    };
+
+    pub(super) fn to_wgpu(self) -> wgpu::Backends {
+        let mut backends = wgpu::Backends::empty();
+
+        if self.vulkan {
+            backends |= wgpu::Backends::VULKAN;
+        }
+        if self.metal {
+            backends |= wgpu::Backends::METAL;
+        }
+        if self.dx12 {
+            backends |= wgpu::Backends::DX12;
+        }
+        if self.dx11 {
+            backends |= wgpu::Backends::DX11;
+        }
+        if self.gl {
+            backends |= wgpu::Backends::GL;
+        }
+
+        backends
+    }
+}
@@ -1,3 +1,5 @@
+use crate::arena::ArenaKey;
+
 /// Represents the resources necessary to render a frame to screen, which are created by
 /// `rendering::start_render()`, used as needed, including possibly passing to other systems
 /// (the imgui system, for example, needs to mutably borrow a `FrameRenderTarget` to be
@@ -7,7 +9,76 @@ pub struct FrameRenderTarget {
     // Note: Due to issue https://github.com/gfx-rs/wgpu/issues/1797, view must be dropped before frame.
     // So the order of declaration here is important.
     // If you declare frame first, then view second, this will cause a runtime crash.
+    // `frame` is never read: it is only kept so that it is dropped (presented) after `view`.
     pub(super) view: wgpu::TextureView,
+    /// Lent by `WgpuState` for the duration of the frame when MSAA is enabled, see
+    /// `RenderTarget::msaa_view`.
+    pub(super) msaa_view: Option<wgpu::TextureView>,
+    #[allow(dead_code)]
     pub(super) frame: wgpu::SurfaceFrame,
     pub(super) encoder: wgpu::CommandEncoder,
+    /// Window the frame is presented to.
+    pub(super) window: ArenaKey,
+    pub(super) width: u32,
+    pub(super) height: u32,
+}
+
+impl FrameRenderTarget {
+    /// Window the frame is rendered to, see `rendering::start_window_render()`.
+    pub fn window(&self) -> ArenaKey {
+        self.window
+    }
+
+    /// Returns a `RenderTarget` that draws directly to the screen (the swapchain).
+    ///
+    /// If you are using a `RenderGraph`, you won't need this: passes are handed the
+    /// appropriate `RenderTarget` by the graph.
+    pub fn target(&mut self) -> RenderTarget<'_> {
+        RenderTarget {
+            encoder: &mut self.encoder,
+            view: &self.view,
+            msaa_view: self.msaa_view.as_ref(),
+            inputs: vec![],
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+/// Something that may be drawn to during a frame: either the screen itself, or an
+/// intermediate texture managed by a `RenderGraph`.
+///
+/// Every render pass started against a `RenderTarget` records into the frame's single command
+/// encoder, so everything is submitted together in `rendering::complete_render()`.
+pub struct RenderTarget<'a> {
+    pub(crate) encoder: &'a mut wgpu::CommandEncoder,
+    pub(crate) view: &'a wgpu::TextureView,
+    /// When MSAA is enabled, passes draw to this multisampled view, which is then resolved
+    /// into `view`.
+    pub(crate) msaa_view: Option<&'a wgpu::TextureView>,
+    /// Bind groups (texture + sampler, see `WgpuTexture`'s layout) for the targets that the pass
+    /// declared as inputs, in declaration order.
+    pub(crate) inputs: Vec<&'a wgpu::BindGroup>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+impl<'a> RenderTarget<'a> {
+    /// Size of the target in pixels, as `(width, height)`.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Number of inputs that were declared for the pass drawing to this target.
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Bind group of the `i`th input declared for the pass drawing to this target (see
+    /// `PassBuilder::read()`): a texture and sampler, laid out like a `WgpuTexture`'s.
+    ///
+    /// Panics if `i` is not less than `input_count()`.
+    pub fn input(&self, i: usize) -> &'a wgpu::BindGroup {
+        self.inputs[i]
+    }
 }
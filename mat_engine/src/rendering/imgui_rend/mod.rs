@@ -7,8 +7,9 @@ impl ImguiRenderingSubsystem {
         rendering_system: &mut crate::rendering::RenderingSystem,
         imgui_ctx: &mut imgui::Context,
     ) -> Self {
-        let renderer = rendering_system
-            .make_imgui_wgpu_renderer(imgui_ctx, rendering_system.state.surface_cfg.format);
+        // Imgui is only drawn to the main window.
+        let texture_format = rendering_system.state.main_surface().cfg.format;
+        let renderer = rendering_system.make_imgui_wgpu_renderer(imgui_ctx, texture_format);
 
         Self { renderer }
     }
@@ -17,13 +18,13 @@ impl ImguiRenderingSubsystem {
         &mut self,
         draw_data: &imgui::DrawData,
         rendering_system: &mut crate::rendering::RenderingSystem,
-        frt: &mut crate::rendering::FrameRenderTarget,
+        target: &mut crate::rendering::RenderTarget,
     ) {
         let device = &rendering_system.state.device;
         let queue = &rendering_system.state.queue;
 
         // TODO: Do we have to do anything else w/ this render_pass?
-        let render_pass = &mut rendering_system.state.make_render_pass(frt);
+        let render_pass = &mut rendering_system.state.make_render_pass(target);
 
         self.renderer
             .render(draw_data, queue, device, &mut render_pass.wgpu_render_pass)
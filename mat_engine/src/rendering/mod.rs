@@ -1,13 +1,19 @@
 // See https://sotrh.github.io/learn-wgpu/
 
+pub mod adapter;
+pub mod config;
 pub mod frame;
+pub mod post_processing;
 pub mod rend_2d;
+pub mod render_graph;
 pub mod shaders;
+pub mod stats;
 
 pub(crate) mod bind_group;
 pub(crate) mod colored_vertex;
 pub(crate) mod generic_uniform;
 pub(crate) mod imgui_rend;
+pub(crate) mod surface;
 pub(crate) mod textured_vertex;
 pub(crate) mod vertex_buffer;
 pub(crate) mod vertex_trait;
@@ -15,18 +21,42 @@ pub(crate) mod wgpu_pipeline;
 pub(crate) mod wgpu_state;
 pub(crate) mod wgpu_texture;
 
-use crate::utils::unwrap_mut;
-pub use frame::FrameRenderTarget;
+use crate::arena::ArenaKey;
+use crate::utils::{unwrap_mut, unwrap_ref};
+use adapter::AdapterInfo;
+pub use config::{Backends, PowerPreference, PresentMode, RenderingConfig};
+pub use frame::{FrameRenderTarget, RenderTarget};
+use render_graph::{RenderGraph, TransientTargetPool};
+use stats::RenderStats;
 use wgpu_state::WgpuState;
 
 use anyhow::Result as AResult;
 
-/// Starts rendering a new frame. Returns a `FrameRenderTarget` that may be used to draw
-/// arbitrary things to screen.
+/// Starts rendering a new frame to the main window. Returns a `FrameRenderTarget` that may be
+/// used to draw arbitrary things to screen.
+///
+/// Returns `None` when the frame must be skipped, for example while the window is minimized,
+/// in which case nothing should be rendered (and `complete_render()` not called). A lost or
+/// outdated surface is recovered from automatically. The only error is running out of memory,
+/// which is fatal.
 ///
 /// Wrapper around the `RenderingSystem::start_render()` method.
-pub fn start_render(ctx: &mut crate::EngineContext) -> AResult<FrameRenderTarget> {
-    unwrap_mut(&mut ctx.rendering_system).start_render()
+pub fn start_render(ctx: &mut crate::EngineContext) -> AResult<Option<FrameRenderTarget>> {
+    let main_window = crate::windowing::main_window(ctx);
+    unwrap_mut(&mut ctx.rendering_system).start_render(main_window)
+}
+
+/// Like `start_render()`, but for any window, see `windowing::open_window()`. Also returns `None`
+/// if the window isn't open, which is the case during the frame in which it is opened.
+///
+/// Every window's frame is completed separately, with `complete_render()`.
+///
+/// Wrapper around the `RenderingSystem::start_render()` method.
+pub fn start_window_render(
+    ctx: &mut crate::EngineContext,
+    window: ArenaKey,
+) -> AResult<Option<FrameRenderTarget>> {
+    unwrap_mut(&mut ctx.rendering_system).start_render(window)
 }
 
 /// Completes the rendering of a frame. You need to give back ownership of the
@@ -37,31 +67,98 @@ pub fn complete_render(ctx: &mut crate::EngineContext, frt: FrameRenderTarget) {
     unwrap_mut(&mut ctx.rendering_system).complete_render(frt);
 }
 
+/// Executes every pass of `graph`, recording them into `frt`. See the `render_graph` module.
+///
+/// Fails if the graph is malformed (for example, if its passes form a cycle), in which case no
+/// pass is executed.
+pub fn execute_render_graph(
+    ctx: &mut crate::EngineContext,
+    frt: &mut FrameRenderTarget,
+    graph: RenderGraph,
+) -> AResult<()> {
+    render_graph::execute(ctx, frt, graph)
+}
+
+/// Statistics of the last frame completed by `complete_render()`. See the `stats` module.
+pub fn last_frame_stats(ctx: &crate::EngineContext) -> &RenderStats {
+    &unwrap_ref(&ctx.rendering_system).state.last_stats
+}
+
+/// Whether the GPU supports timing render graph passes.
+pub fn gpu_timing_supported(ctx: &crate::EngineContext) -> bool {
+    unwrap_ref(&ctx.rendering_system)
+        .state
+        .gpu_timing_supported()
+}
+
+/// Enables or disables timing render graph passes on the GPU. Timings show up in the
+/// `pass_timings` of `last_frame_stats()`. Disabled by default, and ignored if
+/// `gpu_timing_supported()` is false.
+///
+/// Note: Reading back the timings makes `complete_render()` wait for the GPU to finish the frame,
+/// so this is meant for profiling only.
+pub fn set_gpu_timing_enabled(ctx: &mut crate::EngineContext, enabled: bool) {
+    unwrap_mut(&mut ctx.rendering_system)
+        .state
+        .set_gpu_timing_enabled(enabled);
+}
+
+/// Sample count used for anti-aliasing, see `RenderingConfig::msaa_samples`.
+pub fn msaa_samples(ctx: &crate::EngineContext) -> u32 {
+    unwrap_ref(&ctx.rendering_system).state.msaa_samples
+}
+
+/// Information about the adapter the rendering system picked, see `RenderingConfig` for how to
+/// influence the choice.
+pub fn adapter_info(ctx: &crate::EngineContext) -> AdapterInfo {
+    AdapterInfo::new(&unwrap_ref(&ctx.rendering_system).state.adapter)
+}
+
+/// Changes how frames are presented to every window, e.g. to toggle vsync. See `RenderingConfig::present_mode`
+/// for the initial one.
+pub fn set_present_mode(ctx: &mut crate::EngineContext, present_mode: PresentMode) {
+    unwrap_mut(&mut ctx.rendering_system)
+        .state
+        .set_present_mode(present_mode);
+}
+
 /// System that stores state and provides functions related to rendering.
 ///
 /// Currently implemented
 pub struct RenderingSystem {
     pub(crate) state: WgpuState,
+    pub(crate) transient_targets: TransientTargetPool,
 }
 
 impl RenderingSystem {
     /// Creates a new Rendering System.
-    pub(crate) fn new(windowing_system: &crate::windowing::WindowingSystem) -> AResult<Self> {
+    pub(crate) fn new(
+        windowing_system: &crate::windowing::WindowingSystem,
+        config: &RenderingConfig,
+    ) -> AResult<Self> {
         Ok(Self {
-            state: WgpuState::new(windowing_system.get_window_ref())?,
+            state: WgpuState::new(
+                windowing_system.get_window_ref(),
+                windowing_system.main_window(),
+                config,
+            )?,
+            transient_targets: TransientTargetPool::default(),
         })
     }
 
-    /// See the `start_render()` procedure.
-    fn start_render(&mut self) -> AResult<FrameRenderTarget> {
-        let mut frt = self.state.start_frame_render()?;
+    /// See the `start_render()` and `start_window_render()` procedures.
+    fn start_render(&mut self, window: ArenaKey) -> AResult<Option<FrameRenderTarget>> {
+        let mut frt = match self.state.start_frame_render(window)? {
+            Some(frt) => frt,
+            None => return Ok(None),
+        };
 
         // Clear screen?
         // This may be an issue, with the way we do this.
         // TODO: Investigate.
-        self.state.make_render_pass(&mut frt);
+        self.state.make_render_pass(&mut frt.target());
 
-        Ok(frt)
+        Ok(Some(frt))
     }
 
     /// See the `complete_render()` procedure.
@@ -69,6 +166,20 @@ impl RenderingSystem {
         self.state.complete_frame_render(frt);
     }
 
+    /// Creates the surface of a window opened with `windowing::open_window()`.
+    pub(crate) fn add_window_surface(
+        &mut self,
+        window: &winit::window::Window,
+        window_key: ArenaKey,
+    ) -> AResult<()> {
+        self.state.add_surface(window, window_key)
+    }
+
+    /// Must be called before the window is dropped.
+    pub(crate) fn remove_window_surface(&mut self, window_key: ArenaKey) {
+        self.state.remove_surface(window_key);
+    }
+
     #[cfg(not(feature = "glsl-to-spirv"))]
     pub(crate) fn make_imgui_wgpu_renderer(
         &mut self,
@@ -82,6 +193,7 @@ impl RenderingSystem {
             imgui_wgpu::RendererConfig {
                 texture_format,
                 depth_format: None,
+                sample_count: self.state.msaa_samples,
                 ..Default::default()
             },
         )
@@ -101,6 +213,7 @@ impl RenderingSystem {
             imgui_wgpu::RendererConfig {
                 texture_format,
                 depth_format: None,
+                sample_count: self.state.msaa_samples,
                 ..Default::default()
             }, // TODO: Are these defaults correct?
         )
@@ -119,7 +232,7 @@ impl super::event::EventReceiver for RenderingSystem {
             crate::event::Event::WindowResizeEvent(resize) => match &mut ctx.rendering_system {
                 None => {}
                 Some(rc) => {
-                    rc.state.resize(resize.new_size);
+                    rc.state.resize(resize.window, resize.new_size);
                 }
             },
             _ => unreachable!("See receives_event_type"),
@@ -0,0 +1,102 @@
+use std::num::NonZeroU64;
+
+use crate::rendering::{bind_group::BindGroupable, generic_uniform::Uniform};
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+/// Parameters of a single post processing pass. Every pass gets the same uniform block, and
+/// interprets the 16 floats however it wishes (see the comments in each shader).
+pub(super) struct EffectUniformComponent {
+    pub(super) content: EffectUniformContent,
+    buffer: wgpu::Buffer,
+}
+
+impl EffectUniformComponent {
+    pub(super) fn new(device: &mut wgpu::Device, params: [[f32; 4]; 4]) -> Self {
+        let content = EffectUniformContent { params };
+        let buffer = Self::create_new_buffer(
+            content,
+            device,
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        );
+        Self { content, buffer }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Zeroable, Pod)]
+pub(super) struct EffectUniformContent {
+    pub(super) params: [[f32; 4]; 4],
+}
+
+// Asserts that there is no padding in EffectUniformContent
+static_assertions::const_assert_eq!(
+    std::mem::size_of::<EffectUniformContent>(),
+    std::mem::size_of::<f32>() * 4 * 4
+);
+
+impl BindGroupable for EffectUniformComponent {
+    fn get_wgpu_bind_group_layout_descriptor() -> wgpu::BindGroupLayoutDescriptor<'static> {
+        wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false, // This is NOT a dynamically sized array, it is statically sized.
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("effect_uniform_bind_group_layout"),
+        }
+    }
+
+    fn make_wgpu_bind_group(
+        &self,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        device: &mut wgpu::Device,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &self.buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(std::mem::size_of_val(&self.content) as u64),
+                }),
+            }],
+            label: Some("effect_uniform_bind_group"),
+        })
+    }
+}
+
+impl Uniform for EffectUniformComponent {
+    type Content = EffectUniformContent;
+
+    fn create_new_buffer(
+        content: Self::Content,
+        device: &mut wgpu::Device,
+        usage: wgpu::BufferUsages,
+    ) -> wgpu::Buffer {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[content]),
+            usage,
+        })
+    }
+
+    fn update_buffer(&self, encoder: &mut wgpu::CommandEncoder, device: &mut wgpu::Device) {
+        let staging_buffer =
+            Self::create_new_buffer(self.content, device, wgpu::BufferUsages::COPY_SRC);
+
+        encoder.copy_buffer_to_buffer(
+            &staging_buffer,
+            0,
+            &self.buffer,
+            0,
+            std::mem::size_of_val(&self.content) as wgpu::BufferAddress,
+        );
+    }
+}
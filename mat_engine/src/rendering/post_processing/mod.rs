@@ -0,0 +1,654 @@
+//! Post processing component: a configurable chain of full screen effects, applied to a rendered
+//! scene before it is presented.
+//!
+//! The stack doesn't draw anything by itself. Instead, it adds its passes to a `RenderGraph`:
+//! you render your scene to a transient target, and then let the stack read from that target and
+//! write to the swapchain. Anything drawn to the swapchain afterwards (imgui, for example) is drawn
+//! on top of the processed image.
+//!
+//! ```ignore
+//! let scene = graph.create_transient(TransientTargetDesc::screen_sized());
+//! graph.add_pass("scene", scene).clear([0.0, 0.0, 0.0, 1.0]).execute(|ctx, target| { ... });
+//! post_stack.add_passes(&mut graph, scene, TargetHandle::SWAPCHAIN);
+//! graph.add_pass("imgui", TargetHandle::SWAPCHAIN).execute(|ctx, target| imgui::render(ctx, target));
+//! ```
+//!
+//! # Custom effects
+//!
+//! Custom effects are GLSL fragment shaders, registered with
+//! `PostProcessStack::register_custom_effect()`. They are drawn using a full screen triangle, and must
+//! follow this interface:
+//!
+//! ```glsl
+//! layout(location=0) in vec2 v_tex_coords; // (0, 0) is the top left of the screen.
+//! layout(location=0) out vec4 f_color;
+//!
+//! layout(set = 0, binding = 0) uniform texture2D u_input;
+//! layout(set = 0, binding = 1) uniform sampler u_input_sampler;
+//!
+//! // The params given in `Effect::Custom`.
+//! layout(set = 1, binding = 0) uniform EffectParams {
+//!     vec4 u_params[4];
+//! };
+//! ```
+
+pub(crate) mod effect_uniform;
+
+use super::{
+    bind_group::BindGroupable,
+    render_graph::{RenderGraph, TargetHandle, TransientSize, TransientTargetDesc},
+    shaders::{self, Shader, ShaderType},
+    wgpu_state::WgpuState,
+    wgpu_texture::WgpuTexture,
+    RenderTarget,
+};
+use crate::{
+    arena::{Arena, ArenaKey},
+    utils::{unwrap_mut, unwrap_ref},
+};
+use anyhow::Result as AResult;
+use effect_uniform::EffectUniformComponent;
+
+/// A single effect in a `PostProcessStack`, along with its parameters.
+#[derive(Copy, Clone, Debug)]
+pub enum Effect {
+    /// Color grading using a lookup table. See `PostProcessStack::create_lut_bind_group()` and
+    /// `color_grading.frag` for the expected LUT layout.
+    ColorGrading {
+        /// Bind group key returned by `PostProcessStack::create_lut_bind_group()`.
+        lut: ArenaKey,
+        /// Number of slices in the LUT (for a 256x16 LUT, this is 16).
+        lut_size: u32,
+        /// 0.0 leaves the image untouched, 1.0 applies the LUT fully.
+        strength: f32,
+    },
+    /// Darkens (or tints) the edges of the screen.
+    Vignette {
+        /// 0.0 disables the effect, 1.0 applies it fully.
+        intensity: f32,
+        /// Distance from the center (0.0) to the corners (1.0) at which the vignette starts.
+        radius: f32,
+        /// Distance over which the vignette fades in.
+        softness: f32,
+        /// rgb color of the vignette, usually black.
+        color: [f32; 3],
+    },
+    /// Makes bright areas glow.
+    Bloom {
+        /// Brightness (0.0 to 1.0) above which pixels start to glow.
+        threshold: f32,
+        intensity: f32,
+        /// Blur radius of the glow, in (half resolution) pixels.
+        radius: f32,
+    },
+    /// CRT monitor look.
+    Crt {
+        /// Amount of barrel distortion. 0.0 disables it, around 0.1 gives a subtle curve.
+        curvature: f32,
+        /// 0.0 disables scanlines, 1.0 makes them fully black.
+        scanline_intensity: f32,
+        /// Number of scanlines across the screen's height.
+        scanline_count: f32,
+        /// Horizontal offset of the red and blue channels, in pixels.
+        chromatic_aberration: f32,
+    },
+    /// Gaussian blur.
+    Blur {
+        /// Radius in pixels.
+        radius: f32,
+    },
+    /// User defined effect. See module docs.
+    Custom {
+        /// Key returned by `PostProcessStack::register_custom_effect()`.
+        pipeline: ArenaKey,
+        /// Made available to the shader as `u_params`.
+        params: [[f32; 4]; 4],
+    },
+}
+
+impl Effect {
+    /// Contents of the uniform of each pass this effect consists of.
+    fn pass_params(&self) -> Vec<[[f32; 4]; 4]> {
+        const Z: [f32; 4] = [0.0; 4];
+
+        match *self {
+            Effect::ColorGrading {
+                lut_size, strength, ..
+            } => vec![[[lut_size as f32, strength, 0.0, 0.0], Z, Z, Z]],
+            Effect::Vignette {
+                intensity,
+                radius,
+                softness,
+                color,
+            } => vec![[
+                [intensity, radius, softness, 0.0],
+                [color[0], color[1], color[2], 0.0],
+                Z,
+                Z,
+            ]],
+            Effect::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => vec![
+                [[threshold, 0.0, 0.0, 0.0], Z, Z, Z],
+                [[1.0, 0.0, radius, 0.0], Z, Z, Z],
+                [[0.0, 1.0, radius, 0.0], Z, Z, Z],
+                [[intensity, 0.0, 0.0, 0.0], Z, Z, Z],
+            ],
+            Effect::Crt {
+                curvature,
+                scanline_intensity,
+                scanline_count,
+                chromatic_aberration,
+            } => vec![[
+                [
+                    curvature,
+                    scanline_intensity,
+                    scanline_count,
+                    chromatic_aberration,
+                ],
+                Z,
+                Z,
+                Z,
+            ]],
+            Effect::Blur { radius } => vec![
+                [[1.0, 0.0, radius, 0.0], Z, Z, Z],
+                [[0.0, 1.0, radius, 0.0], Z, Z, Z],
+            ],
+            Effect::Custom { params, .. } => vec![params],
+        }
+    }
+}
+
+struct EffectSlot {
+    effect: Effect,
+    enabled: bool,
+    /// One uniform (and the key of its bind group) per pass, see `Effect::pass_params()`.
+    uniforms: Vec<(EffectUniformComponent, ArenaKey)>,
+}
+
+/// Keys of the pipelines for the built in effects.
+struct BuiltinPipelines {
+    copy: ArenaKey,
+    color_grading: ArenaKey,
+    vignette: ArenaKey,
+    bloom_extract: ArenaKey,
+    bloom_composite: ArenaKey,
+    crt: ArenaKey,
+    blur: ArenaKey,
+}
+
+/// Where the third bind group (set 2) of a full screen pass comes from, if it has one.
+#[derive(Copy, Clone)]
+enum ExtraInput {
+    None,
+    /// A texture bind group stored in `WgpuState`.
+    Texture(ArenaKey),
+    /// The second input of the pass.
+    PassInput,
+}
+
+/// Post processing component. See module docs.
+///
+/// Needs rendering system to be initialized.
+pub struct PostProcessStack {
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    params_bind_group_layout: wgpu::BindGroupLayout,
+    pipelines: BuiltinPipelines,
+    effects: Arena<EffectSlot>,
+    /// Order in which effects are applied.
+    order: Vec<ArenaKey>,
+}
+
+impl PostProcessStack {
+    pub fn new(ctx: &mut crate::EngineContext) -> Self {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let texture_bind_group_layout = wgpu_state
+            .device
+            .create_bind_group_layout(&WgpuTexture::get_wgpu_bind_group_layout_descriptor());
+
+        let params_bind_group_layout = wgpu_state.device.create_bind_group_layout(
+            &EffectUniformComponent::get_wgpu_bind_group_layout_descriptor(),
+        );
+
+        let s = &*POST_PROCESSING_SHADERS;
+        let tex = &texture_bind_group_layout;
+        let params = &params_bind_group_layout;
+
+        let pipelines = BuiltinPipelines {
+            copy: make_pipeline(wgpu_state, &s.copy, &[tex]),
+            color_grading: make_pipeline(wgpu_state, &s.color_grading, &[tex, params, tex]),
+            vignette: make_pipeline(wgpu_state, &s.vignette, &[tex, params]),
+            bloom_extract: make_pipeline(wgpu_state, &s.bloom_extract, &[tex, params]),
+            bloom_composite: make_pipeline(wgpu_state, &s.bloom_composite, &[tex, params, tex]),
+            crt: make_pipeline(wgpu_state, &s.crt, &[tex, params]),
+            blur: make_pipeline(wgpu_state, &s.blur, &[tex, params]),
+        };
+
+        Self {
+            texture_bind_group_layout,
+            params_bind_group_layout,
+            pipelines,
+            effects: Arena::new(),
+            order: vec![],
+        }
+    }
+
+    /// Adds `effect` to the end of the stack, returning a key that may be used to modify or
+    /// remove it later.
+    pub fn push(&mut self, ctx: &mut crate::EngineContext, effect: Effect) -> ArenaKey {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let uniforms = self.make_uniforms(wgpu_state, &effect);
+
+        let key = self.effects.insert(EffectSlot {
+            effect,
+            enabled: true,
+            uniforms,
+        });
+        self.order.push(key);
+
+        key
+    }
+
+    /// Replaces the effect at `key` (usually with the same kind of effect, but different parameters).
+    ///
+    /// Panics if there is no effect at `key`.
+    pub fn set_effect(&mut self, ctx: &mut crate::EngineContext, key: ArenaKey, effect: Effect) {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let pass_params = effect.pass_params();
+        let slot = self.effects.get_mut_unwrap(key);
+
+        if slot.uniforms.len() == pass_params.len() {
+            for ((uniform, _), params) in slot.uniforms.iter_mut().zip(pass_params) {
+                uniform.content.params = params;
+                wgpu_state.update_uniform_buffer(&*uniform);
+            }
+            slot.effect = effect;
+        } else {
+            let old_uniforms = std::mem::take(&mut slot.uniforms);
+            for (_, bind_group_key) in old_uniforms {
+                wgpu_state.bind_groups.remove(bind_group_key);
+            }
+
+            let uniforms = self.make_uniforms(wgpu_state, &effect);
+            let slot = self.effects.get_mut_unwrap(key);
+            slot.uniforms = uniforms;
+            slot.effect = effect;
+        }
+    }
+
+    /// Returns the effect at `key`, if there is one.
+    pub fn effect(&self, key: ArenaKey) -> Option<&Effect> {
+        self.effects.get(key).map(|slot| &slot.effect)
+    }
+
+    /// Disabled effects are skipped, but keep their position in the stack.
+    ///
+    /// Panics if there is no effect at `key`.
+    pub fn set_enabled(&mut self, key: ArenaKey, enabled: bool) {
+        self.effects.get_mut_unwrap(key).enabled = enabled;
+    }
+
+    /// Removes the effect at `key` from the stack. Does nothing if there is no effect at `key`.
+    pub fn remove(&mut self, ctx: &mut crate::EngineContext, key: ArenaKey) {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        if let Some(slot) = self.effects.remove(key) {
+            for (_, bind_group_key) in slot.uniforms {
+                wgpu_state.bind_groups.remove(bind_group_key);
+            }
+        }
+
+        self.order.retain(|k| *k != key);
+    }
+
+    /// Compiles a custom effect (see module docs for the shader interface), returning the key
+    /// to be used in `Effect::Custom`.
+    pub fn register_custom_effect(
+        &mut self,
+        ctx: &mut crate::EngineContext,
+        glsl_fragment_source: &str,
+    ) -> AResult<ArenaKey> {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        // Safety: Bad shaders are the user's responsibility, see `shaders::compile_glsl_to_spirv()`.
+        let frag_shader = unsafe {
+            shaders::try_compile_glsl_to_spirv(
+                glsl_fragment_source,
+                "custom_effect.frag",
+                ShaderType::Fragment,
+            )?
+        };
+
+        wgpu_state.add_new_render_pipeline(
+            &POST_PROCESSING_SHADERS.fullscreen_vert,
+            &frag_shader,
+            &[
+                &self.texture_bind_group_layout,
+                &self.params_bind_group_layout,
+            ],
+            vec![],
+            wgpu::BlendState::REPLACE,
+        )
+    }
+
+    /// Creates a bind group for a color grading lookup table, to be used in `Effect::ColorGrading`.
+    ///
+    /// See `WgpuTexture` for info on the format of the bytes.
+    pub fn create_lut_bind_group(
+        &mut self,
+        ctx: &mut crate::EngineContext,
+        texture_bytes: &[u8],
+        texture_label: Option<&'static str>,
+    ) -> ArenaKey {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let texture_key = wgpu_state.add_new_texture_from_bytes(texture_bytes, texture_label);
+        wgpu_state.add_new_texture_bind_group(
+            &self.texture_bind_group_layout,
+            texture_key,
+            texture_label,
+        )
+    }
+
+    /// Adds the passes needed to apply every enabled effect (in order) to `input`, writing
+    /// the result to `output`. If no effect is enabled, `input` is just copied to `output`.
+    pub fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        input: TargetHandle,
+        output: TargetHandle,
+    ) {
+        let enabled: Vec<&EffectSlot> = self
+            .order
+            .iter()
+            .map(|k| self.effects.get_unwrap(*k))
+            .filter(|slot| slot.enabled)
+            .collect();
+
+        if enabled.is_empty() {
+            self.add_fullscreen_pass(
+                graph,
+                "post processing: copy",
+                &[input],
+                output,
+                self.pipelines.copy,
+                None,
+                ExtraInput::None,
+            );
+            return;
+        }
+
+        let mut current = input;
+        for (i, slot) in enabled.iter().enumerate() {
+            let effect_output = if i == enabled.len() - 1 {
+                output
+            } else {
+                graph.create_transient(TransientTargetDesc::screen_sized())
+            };
+
+            self.add_effect_passes(graph, slot, current, effect_output);
+
+            current = effect_output;
+        }
+    }
+
+    fn add_effect_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        slot: &EffectSlot,
+        input: TargetHandle,
+        output: TargetHandle,
+    ) {
+        let p = &self.pipelines;
+        let params = |i: usize| Some(slot.uniforms[i].1);
+
+        match slot.effect {
+            Effect::ColorGrading { lut, .. } => self.add_fullscreen_pass(
+                graph,
+                "post processing: color grading",
+                &[input],
+                output,
+                p.color_grading,
+                params(0),
+                ExtraInput::Texture(lut),
+            ),
+            Effect::Vignette { .. } => self.add_fullscreen_pass(
+                graph,
+                "post processing: vignette",
+                &[input],
+                output,
+                p.vignette,
+                params(0),
+                ExtraInput::None,
+            ),
+            Effect::Crt { .. } => self.add_fullscreen_pass(
+                graph,
+                "post processing: crt",
+                &[input],
+                output,
+                p.crt,
+                params(0),
+                ExtraInput::None,
+            ),
+            Effect::Custom { pipeline, .. } => self.add_fullscreen_pass(
+                graph,
+                "post processing: custom",
+                &[input],
+                output,
+                pipeline,
+                params(0),
+                ExtraInput::None,
+            ),
+            Effect::Blur { .. } => {
+                let horizontal = graph.create_transient(TransientTargetDesc::screen_sized());
+
+                self.add_fullscreen_pass(
+                    graph,
+                    "post processing: blur (horizontal)",
+                    &[input],
+                    horizontal,
+                    p.blur,
+                    params(0),
+                    ExtraInput::None,
+                );
+                self.add_fullscreen_pass(
+                    graph,
+                    "post processing: blur (vertical)",
+                    &[horizontal],
+                    output,
+                    p.blur,
+                    params(1),
+                    ExtraInput::None,
+                );
+            }
+            Effect::Bloom { .. } => {
+                let half_res = TransientTargetDesc {
+                    size: TransientSize::ScreenRelative(0.5),
+                };
+                let bright = graph.create_transient(half_res);
+                let horizontal = graph.create_transient(half_res);
+                let blurred = graph.create_transient(half_res);
+
+                self.add_fullscreen_pass(
+                    graph,
+                    "post processing: bloom (extract)",
+                    &[input],
+                    bright,
+                    p.bloom_extract,
+                    params(0),
+                    ExtraInput::None,
+                );
+                self.add_fullscreen_pass(
+                    graph,
+                    "post processing: bloom (horizontal blur)",
+                    &[bright],
+                    horizontal,
+                    p.blur,
+                    params(1),
+                    ExtraInput::None,
+                );
+                self.add_fullscreen_pass(
+                    graph,
+                    "post processing: bloom (vertical blur)",
+                    &[horizontal],
+                    blurred,
+                    p.blur,
+                    params(2),
+                    ExtraInput::None,
+                );
+                self.add_fullscreen_pass(
+                    graph,
+                    "post processing: bloom (composite)",
+                    &[input, blurred],
+                    output,
+                    p.bloom_composite,
+                    params(3),
+                    ExtraInput::PassInput,
+                );
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_fullscreen_pass<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        name: &'static str,
+        inputs: &[TargetHandle],
+        output: TargetHandle,
+        pipeline_key: ArenaKey,
+        params_bind_group_key: Option<ArenaKey>,
+        extra_input: ExtraInput,
+    ) {
+        let mut pass = graph.add_pass(name, output);
+        for input in inputs {
+            pass = pass.read(*input);
+        }
+
+        pass.execute(move |ctx, target| {
+            self.draw_fullscreen(
+                ctx,
+                target,
+                pipeline_key,
+                params_bind_group_key,
+                extra_input,
+            )
+        });
+    }
+
+    fn draw_fullscreen(
+        &self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        pipeline_key: ArenaKey,
+        params_bind_group_key: Option<ArenaKey>,
+        extra_input: ExtraInput,
+    ) {
+        let wgpu_state = &unwrap_ref(&ctx.rendering_system).state;
+
+        let input = target.inputs[0];
+        let second_input = target.inputs.get(1).copied();
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass.set_pipeline(pipeline_key, wgpu_state).unwrap();
+
+        render_pass.set_wgpu_bind_group(0, input, &[]);
+
+        if let Some(key) = params_bind_group_key {
+            render_pass.set_bind_group(1, key, &[], wgpu_state).unwrap();
+        }
+
+        match extra_input {
+            ExtraInput::None => {}
+            ExtraInput::Texture(key) => {
+                render_pass.set_bind_group(2, key, &[], wgpu_state).unwrap();
+            }
+            ExtraInput::PassInput => {
+                render_pass.set_wgpu_bind_group(
+                    2,
+                    second_input.expect("Pass needs a second input"),
+                    &[],
+                );
+            }
+        }
+
+        // A single triangle covering the whole screen, see `fullscreen.vert`.
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn make_uniforms(
+        &self,
+        wgpu_state: &mut WgpuState,
+        effect: &Effect,
+    ) -> Vec<(EffectUniformComponent, ArenaKey)> {
+        effect
+            .pass_params()
+            .into_iter()
+            .map(|params| {
+                let uniform = EffectUniformComponent::new(&mut wgpu_state.device, params);
+                let bind_group_key = wgpu_state.add_new_uniform_bind_group(
+                    &self.params_bind_group_layout,
+                    &uniform,
+                    Some("effect_uniform_bind_group"),
+                );
+                (uniform, bind_group_key)
+            })
+            .collect()
+    }
+}
+
+fn make_pipeline(
+    wgpu_state: &mut WgpuState,
+    frag_shader: &Shader,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+) -> ArenaKey {
+    wgpu_state
+        .add_new_render_pipeline(
+            &POST_PROCESSING_SHADERS.fullscreen_vert,
+            frag_shader,
+            bind_group_layouts,
+            vec![], // Full screen passes don't use vertex buffers
+            wgpu::BlendState::REPLACE,
+        )
+        .unwrap()
+}
+
+// --- SHADERS ---
+
+struct PostProcessingShaders {
+    fullscreen_vert: Shader,
+    copy: Shader,
+    color_grading: Shader,
+    vignette: Shader,
+    bloom_extract: Shader,
+    bloom_composite: Shader,
+    crt: Shader,
+    blur: Shader,
+}
+
+lazy_static::lazy_static! {
+    static ref POST_PROCESSING_SHADERS: PostProcessingShaders = {
+        let frag = |file_name| shaders::load_engine_shader("post_processing", file_name, ShaderType::Fragment);
+
+        PostProcessingShaders {
+            fullscreen_vert: shaders::load_engine_shader(
+                "post_processing",
+                "fullscreen.vert",
+                ShaderType::Vertex,
+            ),
+            copy: frag("copy.frag"),
+            color_grading: frag("color_grading.frag"),
+            vignette: frag("vignette.frag"),
+            bloom_extract: frag("bloom_extract.frag"),
+            bloom_composite: frag("bloom_composite.frag"),
+            crt: frag("crt.frag"),
+            blur: frag("blur.frag"),
+        }
+    };
+}
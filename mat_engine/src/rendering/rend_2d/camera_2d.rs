@@ -168,6 +168,23 @@ impl Camera2d {
         out
     }
 
+    /// Returns the bottom left and top right corners of the rectangle of the world that is
+    /// visible on screen.
+    ///
+    /// Assumes the camera has been fed the correct screen size.
+    pub fn visible_world_rect(&self) -> (glm::Vec2, glm::Vec2) {
+        let top_left = self.pixel_screen_to_world_coords(&glm::vec2(0.0, 0.0));
+        let bottom_right = self.pixel_screen_to_world_coords(&glm::vec2(
+            self.screen_width as f32,
+            self.screen_height as f32,
+        ));
+
+        (
+            glm::min2(&top_left, &bottom_right),
+            glm::max2(&top_left, &bottom_right),
+        )
+    }
+
     /// Updates the camera. Should be called on the application's loop `update()` function.
     ///
     /// Recomputes the matrix if any relevant parameters have changed, and, if so, also sends the
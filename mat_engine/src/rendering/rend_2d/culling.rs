@@ -0,0 +1,66 @@
+//! View culling for `Renderer2d`: instances and batches (draw calls) whose bounds are outside of
+//! the camera's view are skipped.
+
+use nalgebra_glm as glm;
+
+use super::{camera_2d::Camera2d, instance::Instance};
+
+/// What `Renderer2d` drew and skipped since its last `update()`.
+///
+/// A batch is a single call to one of the renderer's drawing methods. A batch is culled if all of
+/// its instances are.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub instances_drawn: u32,
+    pub instances_culled: u32,
+    pub batches_drawn: u32,
+    pub batches_culled: u32,
+}
+
+impl CullingStats {
+    /// Records a batch of `drawn` + `culled` instances.
+    pub(super) fn record_batch(&mut self, drawn: u32, culled: u32) {
+        self.instances_drawn += drawn;
+        self.instances_culled += culled;
+
+        if drawn > 0 {
+            self.batches_drawn += 1;
+        } else {
+            self.batches_culled += 1;
+        }
+    }
+}
+
+/// Axis aligned rectangle, in world space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(super) struct Bounds {
+    pub(super) min: glm::Vec2,
+    pub(super) max: glm::Vec2,
+}
+
+impl Bounds {
+    /// The part of the world that `camera` sees.
+    pub(super) fn view(camera: &Camera2d) -> Self {
+        let (min, max) = camera.visible_world_rect();
+        Self { min, max }
+    }
+
+    pub(super) fn overlaps(&self, other: &Bounds) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// Where these (local space) bounds end up once `instance` is applied to them.
+    pub(super) fn transformed(&self, instance: &Instance) -> Self {
+        let a = self.min * instance.scale + instance.position;
+        let b = self.max * instance.scale + instance.position;
+
+        // Negative scales flip the rectangle.
+        Self {
+            min: glm::min2(&a, &b),
+            max: glm::max2(&a, &b),
+        }
+    }
+}
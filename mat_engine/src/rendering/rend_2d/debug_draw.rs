@@ -0,0 +1,456 @@
+//! Immediate mode debug drawing of lines and simple shapes, meant for visualizing things such as
+//! hitboxes and paths.
+//!
+//! Primitives are collected with `DebugDraw`'s methods (usually during update), and all of them
+//! are drawn, and then forgotten, by the next call to `DebugDraw::render()`.
+
+use nalgebra_glm as glm;
+
+use super::camera_2d::{Camera2d, CameraUniformComponent};
+use crate::{
+    arena::ArenaKey,
+    rendering::{
+        bind_group::BindGroupable, colored_vertex::ColoredVertex, shaders,
+        vertex_buffer::VertexBufferable, RenderTarget,
+    },
+    utils::unwrap_mut,
+    EngineContext,
+};
+
+/// Number of segments used to approximate circles.
+const CIRCLE_SEGMENTS: u32 = 32;
+
+/// Coordinate space primitives are given in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DebugSpace {
+    /// World coordinates, drawn through the camera passed to `DebugDraw::render()`.
+    World,
+    /// Pixel coordinates, with (0, 0) at the top left of the render target and y positive
+    /// downwards (like `Camera2d::world_to_pixel_screen_coords()`).
+    Screen,
+}
+
+/// How a debug primitive should look.
+#[derive(Copy, Clone, Debug)]
+pub struct DebugStyle {
+    /// rgba
+    pub color: [f32; 4],
+    /// Width of lines and outlines, in the units of `space`.
+    pub thickness: f32,
+    /// Whether closed shapes (rectangles, circles, closed polylines) are filled or outlined.
+    /// Filled polylines must be convex.
+    pub filled: bool,
+    pub space: DebugSpace,
+}
+
+impl DebugStyle {
+    /// Outlined shapes, drawn in world space.
+    pub fn outlined(color: [f32; 4], thickness: f32) -> Self {
+        Self {
+            color,
+            thickness,
+            filled: false,
+            space: DebugSpace::World,
+        }
+    }
+
+    /// Filled shapes, drawn in world space. Lines are drawn 1 unit thick.
+    pub fn filled(color: [f32; 4]) -> Self {
+        Self {
+            color,
+            thickness: 1.0,
+            filled: true,
+            space: DebugSpace::World,
+        }
+    }
+
+    /// Returns the same style, but in screen space.
+    pub fn in_screen_space(self) -> Self {
+        Self {
+            space: DebugSpace::Screen,
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Primitive {
+    Polyline {
+        points: Vec<glm::Vec2>,
+        closed: bool,
+    },
+    Circle {
+        center: glm::Vec2,
+        radius: f32,
+    },
+    Arrow {
+        from: glm::Vec2,
+        to: glm::Vec2,
+    },
+}
+
+/// Debug drawing component. Meant to be used alongside `Renderer2d`, whose camera it uses.
+///
+/// Needs rendering system to be initialized.
+pub struct DebugDraw {
+    pipeline_key: ArenaKey,
+    /// Screen space primitives are converted to clip space on the CPU, so they are drawn
+    /// through an identity matrix rather than through the camera.
+    #[allow(dead_code)]
+    identity_uniform: CameraUniformComponent,
+    identity_bind_group_key: ArenaKey,
+    primitives: Vec<(Primitive, DebugStyle)>,
+}
+
+impl DebugDraw {
+    pub fn new(ctx: &mut EngineContext) -> Self {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let camera_bind_group_layout = wgpu_state.device.create_bind_group_layout(
+            &CameraUniformComponent::get_wgpu_bind_group_layout_descriptor(),
+        );
+
+        let pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                debug_draw_vert_shader(),
+                debug_draw_frag_shader(),
+                &[&camera_bind_group_layout],
+                vec![ColoredVertex::buffer_descriptor(0..2)], // 0 and 1 -> position and color
+                wgpu::BlendState::ALPHA_BLENDING,
+            )
+            .unwrap();
+
+        // `CameraUniformComponent` starts out as the identity matrix.
+        let identity_uniform = CameraUniformComponent::new(&mut wgpu_state.device);
+        let identity_bind_group_key = wgpu_state.add_new_uniform_bind_group(
+            &camera_bind_group_layout,
+            &identity_uniform,
+            Some("debug_draw_identity_bind_group"),
+        );
+
+        Self {
+            pipeline_key,
+            identity_uniform,
+            identity_bind_group_key,
+            primitives: vec![],
+        }
+    }
+
+    /// Draws a line segment from `a` to `b`.
+    pub fn line(&mut self, a: glm::Vec2, b: glm::Vec2, style: &DebugStyle) {
+        self.polyline(&[a, b], false, style);
+    }
+
+    /// Draws line segments between consecutive points, and, if `closed`, between the last and the
+    /// first. Closed polylines are filled if `style.filled` is set.
+    pub fn polyline(&mut self, points: &[glm::Vec2], closed: bool, style: &DebugStyle) {
+        self.primitives.push((
+            Primitive::Polyline {
+                points: points.to_vec(),
+                closed,
+            },
+            *style,
+        ));
+    }
+
+    /// Draws an axis aligned rectangle with the given opposite corners.
+    pub fn rect(&mut self, corner_a: glm::Vec2, corner_b: glm::Vec2, style: &DebugStyle) {
+        let min = glm::min2(&corner_a, &corner_b);
+        let max = glm::max2(&corner_a, &corner_b);
+
+        self.polyline(
+            &[min, glm::vec2(max.x, min.y), max, glm::vec2(min.x, max.y)],
+            true,
+            style,
+        );
+    }
+
+    pub fn circle(&mut self, center: glm::Vec2, radius: f32, style: &DebugStyle) {
+        self.primitives
+            .push((Primitive::Circle { center, radius }, *style));
+    }
+
+    /// Draws a line from `from` to `to`, with an arrow head at `to`. The head's size is
+    /// proportional to the line's thickness.
+    pub fn arrow(&mut self, from: glm::Vec2, to: glm::Vec2, style: &DebugStyle) {
+        self.primitives
+            .push((Primitive::Arrow { from, to }, *style));
+    }
+
+    /// Draws everything that was collected since the last call, and forgets it. World space
+    /// primitives are drawn through `camera`.
+    ///
+    /// Everything is put into a single vertex buffer, and drawn with at most one draw call per
+    /// coordinate space.
+    pub fn render(
+        &mut self,
+        ctx: &mut EngineContext,
+        target: &mut RenderTarget,
+        camera: &Camera2d,
+    ) {
+        if self.primitives.is_empty() {
+            return;
+        }
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let (width, height) = target.size();
+        let (width, height) = (width as f32, height as f32);
+
+        let mut world = Tessellation::default();
+        let mut screen = Tessellation::default();
+
+        for (primitive, style) in self.primitives.drain(..) {
+            match style.space {
+                DebugSpace::World => world.add(&primitive, &style, |p| p),
+                // Flip y, so that we work in a y-up space, like the world's, which keeps
+                // triangles counter clockwise.
+                DebugSpace::Screen => {
+                    screen.add(&primitive, &style, |p| glm::vec2(p.x, height - p.y))
+                }
+            }
+        }
+
+        // Pixels (y-up) to clip space.
+        for v in &mut screen.vertices {
+            v.position[0] = v.position[0] / width * 2.0 - 1.0;
+            v.position[1] = v.position[1] / height * 2.0 - 1.0;
+        }
+
+        let world_index_count = world.indices.len() as u32;
+        let screen_index_count = screen.indices.len() as u32;
+
+        if world_index_count + screen_index_count == 0 {
+            return;
+        }
+
+        // Merge both into the same buffers.
+        let base_vertex = world.vertices.len() as u32;
+        let mut vertices = world.vertices;
+        vertices.extend(screen.vertices);
+        let mut indices = world.indices;
+        indices.extend(screen.indices.iter().map(|i| i + base_vertex));
+
+        let vertex_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("debug draw vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("debug draw index buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass
+            .set_pipeline(self.pipeline_key, wgpu_state)
+            .unwrap();
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(0, vertex_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        if world_index_count > 0 {
+            render_pass
+                .set_bind_group(0, camera.camera_bind_group_key, &[], wgpu_state)
+                .unwrap();
+
+            render_pass.draw_indexed(0..world_index_count, 0, 0..1);
+        }
+
+        if screen_index_count > 0 {
+            render_pass
+                .set_bind_group(0, self.identity_bind_group_key, &[], wgpu_state)
+                .unwrap();
+
+            render_pass.draw_indexed(
+                world_index_count..(world_index_count + screen_index_count),
+                0,
+                0..1,
+            );
+        }
+    }
+}
+
+/// Triangles for a set of primitives. Every triangle is counter clockwise.
+#[derive(Default)]
+struct Tessellation {
+    vertices: Vec<ColoredVertex>,
+    indices: Vec<u32>,
+}
+
+impl Tessellation {
+    /// Tessellates `primitive`, after mapping each of its points through `transform`.
+    fn add<F>(&mut self, primitive: &Primitive, style: &DebugStyle, transform: F)
+    where
+        F: Fn(glm::Vec2) -> glm::Vec2,
+    {
+        let color = style.color;
+        let thickness = style.thickness;
+
+        match primitive {
+            Primitive::Polyline { points, closed } => {
+                let points: Vec<glm::Vec2> = points.iter().map(|p| transform(*p)).collect();
+
+                if *closed && style.filled {
+                    self.convex_polygon(&points, color);
+                } else {
+                    self.polyline(&points, *closed, thickness, color);
+                }
+            }
+            Primitive::Circle { center, radius } => {
+                let center = transform(*center);
+                let points: Vec<glm::Vec2> = (0..CIRCLE_SEGMENTS)
+                    .map(|i| {
+                        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                        center + glm::vec2(angle.cos(), angle.sin()) * *radius
+                    })
+                    .collect();
+
+                if style.filled {
+                    self.convex_polygon(&points, color);
+                } else {
+                    self.polyline(&points, true, thickness, color);
+                }
+            }
+            Primitive::Arrow { from, to } => {
+                let (from, to) = (transform(*from), transform(*to));
+                let length = glm::distance(&from, &to);
+                if length <= f32::EPSILON {
+                    return;
+                }
+
+                let dir = (to - from) / length;
+                let normal = glm::vec2(-dir.y, dir.x);
+
+                let head_length = (thickness * 4.0).min(length);
+                let head_half_width = thickness * 2.5;
+                let head_base = to - dir * head_length;
+
+                self.polyline(&[from, head_base], false, thickness, color);
+                self.convex_polygon(
+                    &[
+                        head_base - normal * head_half_width,
+                        to,
+                        head_base + normal * head_half_width,
+                    ],
+                    color,
+                );
+            }
+        }
+    }
+
+    /// Each segment becomes a quad. Segments are extended by half the thickness on both ends,
+    /// which fills in the corners between them.
+    fn polyline(&mut self, points: &[glm::Vec2], closed: bool, thickness: f32, color: [f32; 4]) {
+        if points.len() < 2 {
+            return;
+        }
+
+        let segment_count = if closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        let half = thickness / 2.0;
+
+        for i in 0..segment_count {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+
+            let length = glm::distance(&a, &b);
+            if length <= f32::EPSILON {
+                continue;
+            }
+
+            let dir = (b - a) / length * half;
+            let normal = glm::vec2(-dir.y, dir.x);
+            let (a, b) = (a - dir, b + dir);
+
+            self.push_quad([a - normal, b - normal, b + normal, a + normal], color);
+        }
+    }
+
+    /// Triangle fan. Points may be in either winding order.
+    fn convex_polygon(&mut self, points: &[glm::Vec2], color: [f32; 4]) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let clockwise = signed_area(points) < 0.0;
+
+        let base = self.vertices.len() as u32;
+        self.vertices
+            .extend(points.iter().map(|p| vertex(*p, color)));
+
+        for i in 1..(points.len() as u32 - 1) {
+            if clockwise {
+                self.indices
+                    .extend_from_slice(&[base, base + i + 1, base + i]);
+            } else {
+                self.indices
+                    .extend_from_slice(&[base, base + i, base + i + 1]);
+            }
+        }
+    }
+
+    /// `corners` must be counter clockwise.
+    fn push_quad(&mut self, corners: [glm::Vec2; 4], color: [f32; 4]) {
+        let base = self.vertices.len() as u32;
+        self.vertices
+            .extend(corners.iter().map(|p| vertex(*p, color)));
+        self.indices
+            .extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+fn vertex(p: glm::Vec2, color: [f32; 4]) -> ColoredVertex {
+    ColoredVertex {
+        position: [p.x, p.y, 0.0],
+        color,
+    }
+}
+
+/// Positive for counter clockwise polygons (y-up).
+fn signed_area(points: &[glm::Vec2]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+    area / 2.0
+}
+
+// --- SHADERS ---
+
+lazy_static::lazy_static! {
+    static ref DEBUG_DRAW_VERT_SHADER: shaders::Shader =
+        shaders::load_engine_shader(
+            "rend_2d",
+            "debug_draw.vert",
+            shaders::ShaderType::Vertex,
+        );
+
+    static ref DEBUG_DRAW_FRAG_SHADER: shaders::Shader =
+        shaders::load_engine_shader(
+            "rend_2d",
+            "debug_draw.frag",
+            shaders::ShaderType::Fragment,
+        );
+}
+
+fn debug_draw_vert_shader() -> &'static shaders::Shader {
+    &DEBUG_DRAW_VERT_SHADER
+}
+
+fn debug_draw_frag_shader() -> &'static shaders::Shader {
+    &DEBUG_DRAW_FRAG_SHADER
+}
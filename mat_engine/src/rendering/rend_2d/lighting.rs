@@ -0,0 +1,634 @@
+//! Optional 2D lighting, with normal maps and shadows.
+//!
+//! Lighting needs three screen sized images: the scene as usual, a *normal buffer* (the normal
+//! maps of everything in the scene, drawn with `Lighting2d::render_mesh_normals()`), and a *light
+//! accumulation buffer*, which `Lighting2d` fills with the ambient light and the contribution of
+//! every light. The scene is then multiplied by the accumulated light.
+//!
+//! Like `PostProcessStack`, `Lighting2d` adds its passes to a `RenderGraph`:
+//!
+//! ```ignore
+//! lighting.update(ctx);
+//!
+//! let scene = graph.create_transient(TransientTargetDesc::screen_sized());
+//! graph.add_pass("scene", scene).clear([0.0, 0.0, 0.0, 1.0]).execute(|ctx, target| {
+//!     renderer.render_mesh(ctx, target, &mesh, texture_key, &instances);
+//! });
+//!
+//! let normals = graph.create_transient(TransientTargetDesc::screen_sized());
+//! graph.add_pass("normals", normals).clear(Lighting2d::FLAT_NORMAL).execute(|ctx, target| {
+//!     lighting.render_mesh_normals(ctx, target, &renderer, &mesh, normal_map_key, &instances);
+//! });
+//!
+//! lighting.add_passes(&mut graph, &renderer.camera, scene, normals, TargetHandle::SWAPCHAIN);
+//! ```
+//!
+//! Normal maps use the usual convention (x to the right, y up, z towards the camera, and a
+//! flat surface being (0.5, 0.5, 1.0)). Sprites without a normal map may simply be left out of
+//! the normal buffer. Light is accumulated in a target with the screen's format, so it saturates
+//! at full brightness.
+//!
+//! Shadows are cast by `Occluder2d` polygons. Pixels are tested against every edge near the light,
+//! so keep occluders simple, and lights without shadows cheap.
+
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm as glm;
+
+use super::{
+    camera_2d::Camera2d,
+    instance::{Instance, InstanceData},
+    ColoredVertex2d, Mesh2d, Renderer2d,
+};
+use crate::{
+    arena::{Arena, ArenaKey},
+    rendering::{
+        render_graph::{RenderGraph, TargetHandle, TransientTargetDesc},
+        shaders,
+        vertex_buffer::VertexBufferable,
+        wgpu_state::WgpuState,
+        RenderTarget,
+    },
+    utils::{unwrap_mut, unwrap_ref},
+};
+
+/// Initial capacity of the light and occluder edge buffers, which grow as needed.
+const INITIAL_LIGHT_CAPACITY: usize = 16;
+const INITIAL_SEGMENT_CAPACITY: usize = 256;
+
+/// Shape of the light emitted by a `Light2d`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightKind {
+    /// Shines in every direction.
+    Point,
+    /// Shines in a cone. Angles are in radians.
+    Spot {
+        /// Counterclockwise from the x axis.
+        direction: f32,
+        /// Half the angle of the cone, within which the light is at full strength.
+        inner_angle: f32,
+        /// Half the angle of the cone, outside of which there is no light.
+        outer_angle: f32,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shadows {
+    None,
+    /// Sharp edged shadows, as from a tiny light.
+    Hard,
+    /// Shadows with a penumbra, as from a light that is a disk of the given radius (in world units).
+    /// Costs several times more than hard shadows.
+    Soft {
+        source_radius: f32,
+    },
+}
+
+/// A light, see `Lighting2d::add_light()`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Light2d {
+    pub position: glm::Vec2,
+    /// rgb
+    pub color: [f32; 3],
+    /// Multiplies `color`.
+    pub intensity: f32,
+    /// Distance at which the light fades out completely, in world units.
+    pub radius: f32,
+    /// How quickly the light fades with distance: 1.0 is linear, higher values fade faster.
+    pub falloff: f32,
+    /// Height of the light above the scene, in world units. Lower lights make normal mapped
+    /// details stand out more, but light flat surfaces less as they get further away.
+    pub height: f32,
+    pub kind: LightKind,
+    pub shadows: Shadows,
+}
+
+impl Light2d {
+    /// White point light without shadows.
+    pub fn point(position: glm::Vec2, radius: f32) -> Self {
+        Self {
+            position,
+            color: [1.0; 3],
+            intensity: 1.0,
+            radius,
+            falloff: 2.0,
+            height: radius * 0.5,
+            kind: LightKind::Point,
+            shadows: Shadows::None,
+        }
+    }
+
+    /// White spot light without shadows. See `LightKind::Spot` for the angles.
+    pub fn spot(
+        position: glm::Vec2,
+        radius: f32,
+        direction: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        Self {
+            kind: LightKind::Spot {
+                direction,
+                inner_angle,
+                outer_angle,
+            },
+            ..Self::point(position, radius)
+        }
+    }
+}
+
+/// A polygon that casts shadows, see `Lighting2d::add_occluder()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occluder2d {
+    /// Vertices of the polygon, in world space. The last one is connected back to the first one.
+    pub points: Vec<glm::Vec2>,
+}
+
+impl Occluder2d {
+    pub fn new(points: Vec<glm::Vec2>) -> Self {
+        Self { points }
+    }
+
+    /// Axis aligned rectangle, `min` being its bottom left corner.
+    pub fn rectangle(min: glm::Vec2, max: glm::Vec2) -> Self {
+        Self::new(vec![
+            min,
+            glm::vec2(max.x, min.y),
+            max,
+            glm::vec2(min.x, max.y),
+        ])
+    }
+
+    fn segments(&self) -> impl Iterator<Item = (glm::Vec2, glm::Vec2)> + '_ {
+        let next = self.points.iter().cycle().skip(1);
+        // A single point has no edges, and two points make a single edge, not two.
+        let count = match self.points.len() {
+            0 | 1 => 0,
+            2 => 1,
+            n => n,
+        };
+        self.points.iter().copied().zip(next.copied()).take(count)
+    }
+}
+
+/// Must match `Light` in `light.vert` and `light.frag` (std430 layout).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct GpuLight {
+    position: [f32; 2],
+    radius: f32,
+    height: f32,
+    color: [f32; 3],
+    falloff: f32,
+    spot_direction: [f32; 2],
+    spot_cos_inner: f32,
+    spot_cos_outer: f32,
+    first_segment: u32,
+    segment_count: u32,
+    shadow_mode: u32,
+    source_radius: f32,
+}
+
+// Asserts that there is no padding in GpuLight, which the shaders would not expect
+static_assertions::const_assert_eq!(std::mem::size_of::<GpuLight>(), 64);
+
+/// Storage buffers for lights and occluder edges, recreated (along with their bind group) when
+/// they need to grow.
+struct StorageBuffers {
+    lights: wgpu::Buffer,
+    light_capacity: usize,
+    segments: wgpu::Buffer,
+    segment_capacity: usize,
+    bind_group: wgpu::BindGroup,
+}
+
+impl StorageBuffers {
+    fn new(
+        wgpu_state: &WgpuState,
+        layout: &wgpu::BindGroupLayout,
+        light_capacity: usize,
+        segment_capacity: usize,
+    ) -> Self {
+        let make_buffer = |label, size: usize| {
+            wgpu_state.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+
+        let lights = make_buffer(
+            "lighting_2d light buffer",
+            light_capacity * std::mem::size_of::<GpuLight>(),
+        );
+        let segments = make_buffer(
+            "lighting_2d segment buffer",
+            segment_capacity * std::mem::size_of::<[f32; 4]>(),
+        );
+
+        let bind_group = wgpu_state
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("lighting_2d bind group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: lights.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: segments.as_entire_binding(),
+                    },
+                ],
+            });
+
+        Self {
+            lights,
+            light_capacity,
+            segments,
+            segment_capacity,
+            bind_group,
+        }
+    }
+}
+
+/// 2D lighting component. See module docs.
+///
+/// Needs rendering system to be initialized.
+pub struct Lighting2d {
+    /// rgb light every pixel gets, before any light is added.
+    pub ambient: [f32; 3],
+    lights: Arena<Light2d>,
+    occluders: Arena<Occluder2d>,
+    normal_pipeline_key: ArenaKey,
+    light_pipeline_key: ArenaKey,
+    composite_pipeline_key: ArenaKey,
+    storage_bind_group_layout: wgpu::BindGroupLayout,
+    buffers: StorageBuffers,
+    /// Number of lights uploaded by the last `update()`.
+    light_count: u32,
+}
+
+impl Lighting2d {
+    /// Color to clear the normal buffer to: a flat surface, facing the camera.
+    pub const FLAT_NORMAL: [f64; 4] = [0.5, 0.5, 1.0, 1.0];
+
+    /// `renderer` is the renderer the scene and normal maps are drawn with.
+    pub fn new(ctx: &mut crate::EngineContext, renderer: &Renderer2d) -> Self {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let storage_entry =
+            |binding: u32, visibility: wgpu::ShaderStages| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            };
+
+        let storage_bind_group_layout =
+            wgpu_state
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("lighting_2d bind group layout"),
+                    entries: &[
+                        storage_entry(0, wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT),
+                        storage_entry(1, wgpu::ShaderStages::FRAGMENT),
+                    ],
+                });
+
+        // Lights add up. They leave alpha untouched, since they write 0.0 to it.
+        const ADDITIVE: wgpu::BlendComponent = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        let texture_layout = &renderer.texture_bind_group_layout;
+        let camera_layout = &renderer.camera.camera_bind_group_layout;
+
+        let normal_pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                super::colored_textured_vert_shader(),
+                &LIGHTING_SHADERS.normals_frag,
+                &[texture_layout, camera_layout],
+                // Same layout as the mesh pipeline, see `Renderer2d::draw_mesh()`.
+                vec![
+                    ColoredVertex2d::buffer_descriptor(0..3),
+                    InstanceData::buffer_descriptor(3..7),
+                ],
+                wgpu::BlendState::ALPHA_BLENDING,
+            )
+            .unwrap();
+
+        let light_pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                &LIGHTING_SHADERS.light_vert,
+                &LIGHTING_SHADERS.light_frag,
+                &[texture_layout, camera_layout, &storage_bind_group_layout],
+                vec![], // Quads are made up in the vertex shader
+                wgpu::BlendState {
+                    color: ADDITIVE,
+                    alpha: ADDITIVE,
+                },
+            )
+            .unwrap();
+
+        let composite_pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                &LIGHTING_SHADERS.fullscreen_vert,
+                &LIGHTING_SHADERS.composite_frag,
+                &[texture_layout, texture_layout],
+                vec![],
+                wgpu::BlendState::REPLACE,
+            )
+            .unwrap();
+
+        let buffers = StorageBuffers::new(
+            wgpu_state,
+            &storage_bind_group_layout,
+            INITIAL_LIGHT_CAPACITY,
+            INITIAL_SEGMENT_CAPACITY,
+        );
+
+        Self {
+            ambient: [0.1, 0.1, 0.1],
+            lights: Arena::new(),
+            occluders: Arena::new(),
+            normal_pipeline_key,
+            light_pipeline_key,
+            composite_pipeline_key,
+            storage_bind_group_layout,
+            buffers,
+            light_count: 0,
+        }
+    }
+
+    pub fn add_light(&mut self, light: Light2d) -> ArenaKey {
+        self.lights.insert(light)
+    }
+
+    pub fn light(&self, key: ArenaKey) -> Option<&Light2d> {
+        self.lights.get(key)
+    }
+
+    pub fn light_mut(&mut self, key: ArenaKey) -> Option<&mut Light2d> {
+        self.lights.get_mut(key)
+    }
+
+    pub fn remove_light(&mut self, key: ArenaKey) -> Option<Light2d> {
+        self.lights.remove(key)
+    }
+
+    pub fn add_occluder(&mut self, occluder: Occluder2d) -> ArenaKey {
+        self.occluders.insert(occluder)
+    }
+
+    pub fn occluder(&self, key: ArenaKey) -> Option<&Occluder2d> {
+        self.occluders.get(key)
+    }
+
+    pub fn occluder_mut(&mut self, key: ArenaKey) -> Option<&mut Occluder2d> {
+        self.occluders.get_mut(key)
+    }
+
+    pub fn remove_occluder(&mut self, key: ArenaKey) -> Option<Occluder2d> {
+        self.occluders.remove(key)
+    }
+
+    /// Uploads lights and occluders. Call it once per frame, after modifying them and before
+    /// rendering.
+    pub fn update(&mut self, ctx: &mut crate::EngineContext) {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let mut lights = vec![];
+        let mut segments: Vec<[f32; 4]> = vec![];
+
+        for light in self.lights.values() {
+            if light.radius <= 0.0 || light.intensity <= 0.0 {
+                continue;
+            }
+
+            let first_segment = segments.len() as u32;
+
+            // Only edges that may cross the light's square are given to the shader.
+            if light.shadows != Shadows::None {
+                let (light_min, light_max) = (
+                    light.position - glm::vec2(light.radius, light.radius),
+                    light.position + glm::vec2(light.radius, light.radius),
+                );
+
+                for occluder in self.occluders.values() {
+                    for (a, b) in occluder.segments() {
+                        let (min, max) = (glm::min2(&a, &b), glm::max2(&a, &b));
+                        if max.x >= light_min.x
+                            && min.x <= light_max.x
+                            && max.y >= light_min.y
+                            && min.y <= light_max.y
+                        {
+                            segments.push([a.x, a.y, b.x, b.y]);
+                        }
+                    }
+                }
+            }
+
+            let (spot_direction, spot_cos_inner, spot_cos_outer) = match light.kind {
+                // The cone test always passes for these values.
+                LightKind::Point => ([1.0, 0.0], -1.5, -2.0),
+                LightKind::Spot {
+                    direction,
+                    inner_angle,
+                    outer_angle,
+                } => (
+                    [direction.cos(), direction.sin()],
+                    inner_angle.cos(),
+                    // smoothstep() needs its edges to differ.
+                    outer_angle.cos().min(inner_angle.cos() - 1e-4),
+                ),
+            };
+
+            let (shadow_mode, source_radius) = match light.shadows {
+                Shadows::None => (0, 0.0),
+                Shadows::Hard => (1, 0.0),
+                Shadows::Soft { source_radius } => (2, source_radius),
+            };
+
+            lights.push(GpuLight {
+                position: [light.position.x, light.position.y],
+                radius: light.radius,
+                height: light.height,
+                color: [
+                    light.color[0] * light.intensity,
+                    light.color[1] * light.intensity,
+                    light.color[2] * light.intensity,
+                ],
+                falloff: light.falloff,
+                spot_direction,
+                spot_cos_inner,
+                spot_cos_outer,
+                first_segment,
+                segment_count: segments.len() as u32 - first_segment,
+                shadow_mode,
+                source_radius,
+            });
+        }
+
+        if lights.len() > self.buffers.light_capacity
+            || segments.len() > self.buffers.segment_capacity
+        {
+            self.buffers = StorageBuffers::new(
+                wgpu_state,
+                &self.storage_bind_group_layout,
+                lights
+                    .len()
+                    .next_power_of_two()
+                    .max(self.buffers.light_capacity),
+                segments
+                    .len()
+                    .next_power_of_two()
+                    .max(self.buffers.segment_capacity),
+            );
+        }
+
+        if !lights.is_empty() {
+            wgpu_state.write_buffer(&self.buffers.lights, 0, bytemuck::cast_slice(&lights));
+        }
+        if !segments.is_empty() {
+            wgpu_state.write_buffer(&self.buffers.segments, 0, bytemuck::cast_slice(&segments));
+        }
+
+        self.light_count = lights.len() as u32;
+    }
+
+    /// Draws the normal map of `mesh` into the normal buffer. Takes the same arguments as
+    /// `Renderer2d::render_mesh()`, but with the bind group of a normal map instead of a texture.
+    /// Vertex colors only matter through their alpha.
+    pub fn render_mesh_normals(
+        &self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        renderer: &Renderer2d,
+        mesh: &Mesh2d,
+        normal_map_bind_group_key: ArenaKey,
+        instances: &[Instance],
+    ) {
+        // Culled like the scene's meshes, but not counted in the renderer's stats.
+        renderer.draw_mesh(
+            ctx,
+            target,
+            self.normal_pipeline_key,
+            mesh,
+            normal_map_bind_group_key,
+            instances,
+        );
+    }
+
+    /// Adds the passes that light `scene`, using the normal buffer `normals`, and write the result
+    /// to `output`. `camera` must be the one the scene was drawn with.
+    pub fn add_passes<'a>(
+        &'a self,
+        graph: &mut RenderGraph<'a>,
+        camera: &Camera2d,
+        scene: TargetHandle,
+        normals: TargetHandle,
+        output: TargetHandle,
+    ) {
+        let light_buffer = graph.create_transient(TransientTargetDesc::screen_sized());
+        let camera_bind_group_key = camera.camera_bind_group_key;
+
+        graph
+            .add_pass("lighting: lights", light_buffer)
+            .read(normals)
+            .clear([
+                self.ambient[0] as f64,
+                self.ambient[1] as f64,
+                self.ambient[2] as f64,
+                1.0,
+            ])
+            .execute(move |ctx, target| self.draw_lights(ctx, target, camera_bind_group_key));
+
+        graph
+            .add_pass("lighting: composite", output)
+            .read(scene)
+            .read(light_buffer)
+            .execute(move |ctx, target| self.draw_composite(ctx, target));
+    }
+
+    fn draw_lights(
+        &self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        camera_bind_group_key: ArenaKey,
+    ) {
+        if self.light_count == 0 {
+            return;
+        }
+
+        let wgpu_state = &unwrap_ref(&ctx.rendering_system).state;
+
+        let normals = target.inputs[0];
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass
+            .set_pipeline(self.light_pipeline_key, wgpu_state)
+            .unwrap();
+
+        render_pass.set_wgpu_bind_group(0, normals, &[]);
+
+        render_pass
+            .set_bind_group(1, camera_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass.set_wgpu_bind_group(2, &self.buffers.bind_group, &[]);
+
+        // One quad per light, see `light.vert`.
+        render_pass.draw(0..6, 0..self.light_count);
+    }
+
+    fn draw_composite(&self, ctx: &mut crate::EngineContext, target: &mut RenderTarget) {
+        let wgpu_state = &unwrap_ref(&ctx.rendering_system).state;
+
+        let (scene, light_buffer) = (target.inputs[0], target.inputs[1]);
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass
+            .set_pipeline(self.composite_pipeline_key, wgpu_state)
+            .unwrap();
+
+        render_pass.set_wgpu_bind_group(0, scene, &[]);
+        render_pass.set_wgpu_bind_group(1, light_buffer, &[]);
+
+        // A single triangle covering the whole screen, see `fullscreen.vert`.
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+// --- SHADERS ---
+
+struct LightingShaders {
+    normals_frag: shaders::Shader,
+    light_vert: shaders::Shader,
+    light_frag: shaders::Shader,
+    fullscreen_vert: shaders::Shader,
+    composite_frag: shaders::Shader,
+}
+
+lazy_static::lazy_static! {
+    static ref LIGHTING_SHADERS: LightingShaders = {
+        use shaders::ShaderType;
+
+        LightingShaders {
+            normals_frag: shaders::load_engine_shader("rend_2d", "light_normals.frag", ShaderType::Fragment),
+            light_vert: shaders::load_engine_shader("rend_2d", "light.vert", ShaderType::Vertex),
+            light_frag: shaders::load_engine_shader("rend_2d", "light.frag", ShaderType::Fragment),
+            fullscreen_vert: shaders::load_engine_shader("post_processing", "fullscreen.vert", ShaderType::Vertex),
+            composite_frag: shaders::load_engine_shader("rend_2d", "light_composite.frag", ShaderType::Fragment),
+        }
+    };
+}
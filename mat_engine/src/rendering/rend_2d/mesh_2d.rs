@@ -0,0 +1,141 @@
+//! Meshes with arbitrary, user provided, vertex data.
+
+use nalgebra_glm as glm;
+
+use super::{culling::Bounds, vertex_2d::ColoredVertex2d};
+use crate::{rendering::wgpu_state::WgpuState, utils::unwrap_mut, EngineContext};
+
+/// Triangle mesh living on the GPU, drawn with `Renderer2d::render_mesh()`.
+///
+/// Vertices and indices are uploaded once, on creation. They may be replaced later (for
+/// deformable terrain, trails, or animated cutout characters, for example); the existing
+/// buffers are reused when the new data fits in them.
+///
+/// Triangles must be counter clockwise (with y positive upwards), or they will be culled.
+pub struct Mesh2d {
+    vertex_buffer: wgpu::Buffer,
+    vertex_capacity: usize,
+    index_buffer: wgpu::Buffer,
+    index_capacity: usize,
+    index_count: u32,
+    /// Bounding box of the vertices, used for culling.
+    bounds: Bounds,
+}
+
+impl Mesh2d {
+    pub fn new(ctx: &mut EngineContext, vertices: &[ColoredVertex2d], indices: &[u32]) -> Self {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        Self {
+            vertex_buffer: create_buffer(
+                wgpu_state,
+                "mesh_2d vertex buffer",
+                bytemuck::cast_slice(vertices),
+                wgpu::BufferUsages::VERTEX,
+            ),
+            vertex_capacity: vertices.len(),
+            index_buffer: create_buffer(
+                wgpu_state,
+                "mesh_2d index buffer",
+                bytemuck::cast_slice(indices),
+                wgpu::BufferUsages::INDEX,
+            ),
+            index_capacity: indices.len(),
+            index_count: indices.len() as u32,
+            bounds: vertex_bounds(vertices),
+        }
+    }
+
+    /// Replaces the mesh's vertices. Indices are kept, so make sure they are still valid.
+    pub fn set_vertices(&mut self, ctx: &mut EngineContext, vertices: &[ColoredVertex2d]) {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        if vertices.len() <= self.vertex_capacity {
+            wgpu_state.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+        } else {
+            self.vertex_buffer = create_buffer(
+                wgpu_state,
+                "mesh_2d vertex buffer",
+                bytemuck::cast_slice(vertices),
+                wgpu::BufferUsages::VERTEX,
+            );
+            self.vertex_capacity = vertices.len();
+        }
+
+        self.bounds = vertex_bounds(vertices);
+    }
+
+    /// Replaces the mesh's indices.
+    pub fn set_indices(&mut self, ctx: &mut EngineContext, indices: &[u32]) {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        if indices.len() <= self.index_capacity {
+            wgpu_state.write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(indices));
+        } else {
+            self.index_buffer = create_buffer(
+                wgpu_state,
+                "mesh_2d index buffer",
+                bytemuck::cast_slice(indices),
+                wgpu::BufferUsages::INDEX,
+            );
+            self.index_capacity = indices.len();
+        }
+
+        self.index_count = indices.len() as u32;
+    }
+
+    pub fn index_count(&self) -> u32 {
+        self.index_count
+    }
+
+    /// Bottom left and top right corners of the box around the mesh's vertices, before any
+    /// instance is applied.
+    pub fn bounds(&self) -> (glm::Vec2, glm::Vec2) {
+        (self.bounds.min, self.bounds.max)
+    }
+
+    pub(super) fn local_bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    pub(super) fn vertex_buffer(&self) -> &wgpu::Buffer {
+        &self.vertex_buffer
+    }
+
+    pub(super) fn index_buffer(&self) -> &wgpu::Buffer {
+        &self.index_buffer
+    }
+}
+
+fn vertex_bounds(vertices: &[ColoredVertex2d]) -> Bounds {
+    let mut positions = vertices
+        .iter()
+        .map(|vertex| glm::vec2(vertex.position[0], vertex.position[1]));
+
+    let first = positions.next().unwrap_or_else(|| glm::vec2(0.0, 0.0));
+
+    positions.fold(
+        Bounds {
+            min: first,
+            max: first,
+        },
+        |bounds, position| Bounds {
+            min: glm::min2(&bounds.min, &position),
+            max: glm::max2(&bounds.max, &position),
+        },
+    )
+}
+
+/// Buffers are `COPY_DST` so that they may be updated in place.
+fn create_buffer(
+    wgpu_state: &mut WgpuState,
+    label: &'static str,
+    contents: &[u8],
+    usage: wgpu::BufferUsages,
+) -> wgpu::Buffer {
+    wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents,
+        usage: usage | wgpu::BufferUsages::COPY_DST,
+    })
+}
@@ -1,23 +1,40 @@
 //! This module provides a default 2d renderer.
 
-pub(crate) mod camera_2d;
-pub(crate) mod instance;
 pub(crate) mod test_uniform;
 pub(crate) mod vertex_2d;
 
+pub mod camera_2d;
+pub mod culling;
+pub mod debug_draw;
+pub mod instance;
+pub mod lighting;
+pub mod mesh_2d;
+pub mod nine_slice;
+pub mod particles;
+pub mod shape;
+pub mod software_cursor;
+pub mod sprite_animation;
 pub mod sprite_renderer;
+pub mod text;
+pub mod tilemap;
+
+pub use mesh_2d::Mesh2d;
+pub use vertex_2d::ColoredVertex2d;
 
 use super::{
     bind_group::BindGroupable, shaders, vertex_buffer::VertexBufferable, wgpu_state::WgpuState,
-    wgpu_texture::WgpuTexture, FrameRenderTarget,
+    wgpu_texture::WgpuTexture, RenderTarget,
 };
 
 use crate::arena::ArenaKey;
 use crate::utils::unwrap_mut;
 use camera_2d::Camera2d;
+use culling::{Bounds, CullingStats};
 use instance::{Instance, InstanceData};
+use nine_slice::NineSlice;
+use particles::{ParticleBlend, ParticleEmitter, ParticleInstance};
+use software_cursor::SoftwareCursor;
 use vertex_2d::Vertex2d;
-use wgpu::util::DeviceExt;
 
 /// Default 2D renderer component.
 ///
@@ -26,7 +43,21 @@ use wgpu::util::DeviceExt;
 pub struct Renderer2d {
     texture_bind_group_layout: wgpu::BindGroupLayout,
     pipeline_key: ArenaKey,
+    /// Pipeline for `Mesh2d`, which has colored vertices, and may be translucent.
+    mesh_pipeline_key: ArenaKey,
+    /// Same as `pipeline_key`, but with alpha blending, for `NineSlice`.
+    nine_slice_pipeline_key: ArenaKey,
+    /// Pipelines for `ParticleEmitter`, with alpha and additive blending.
+    particle_alpha_pipeline_key: ArenaKey,
+    particle_additive_pipeline_key: ArenaKey,
+    /// Unit quad, which particles are instances of.
+    particle_vertex_buffer: wgpu::Buffer,
+    particle_index_buffer: wgpu::Buffer,
     pub camera: Camera2d,
+    /// Window whose size the camera follows, see `set_window()`.
+    window: ArenaKey,
+    culling_enabled: bool,
+    culling_stats: CullingStats,
 }
 
 #[allow(dead_code)]
@@ -38,11 +69,12 @@ impl Renderer2d {
 
         let texture_bind_group_layout = wgpu_state.device.create_bind_group_layout(&tex_desc);
 
-        let camera = Camera2d::new(
-            wgpu_state.window_inner_size.width,
-            wgpu_state.window_inner_size.height,
-            wgpu_state,
-        );
+        let window = wgpu_state.main_window;
+        let (width, height) = {
+            let cfg = &wgpu_state.main_surface().cfg;
+            (cfg.width, cfg.height)
+        };
+        let camera = Camera2d::new(width, height, wgpu_state);
 
         let pipeline_key = wgpu_state
             .add_new_render_pipeline(
@@ -54,32 +86,167 @@ impl Renderer2d {
                     Vertex2d::buffer_descriptor(0..2), // 0 and 1 -> color and tex coords
                     InstanceData::buffer_descriptor(2..6), // 2 through 5 inclusive -> single mat4
                 ],
+                wgpu::BlendState::REPLACE,
             )
             .unwrap();
 
+        let mesh_pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                colored_textured_vert_shader(),
+                colored_textured_frag_shader(),
+                &[&texture_bind_group_layout, &camera.camera_bind_group_layout],
+                vec![
+                    ColoredVertex2d::buffer_descriptor(0..3), // position, tex coords and color
+                    InstanceData::buffer_descriptor(3..7), // 3 through 6 inclusive -> single mat4
+                ],
+                wgpu::BlendState::ALPHA_BLENDING,
+            )
+            .unwrap();
+
+        let nine_slice_pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                rend_2d_vert_shader(),
+                rend_2d_frag_shader(),
+                &[&texture_bind_group_layout, &camera.camera_bind_group_layout],
+                vec![
+                    Vertex2d::buffer_descriptor(0..2),
+                    InstanceData::buffer_descriptor(2..6),
+                ],
+                wgpu::BlendState::ALPHA_BLENDING,
+            )
+            .unwrap();
+
+        let mut add_particle_pipeline = |blend: ParticleBlend| {
+            wgpu_state
+                .add_new_render_pipeline(
+                    particle_vert_shader(),
+                    colored_textured_frag_shader(),
+                    &[&texture_bind_group_layout, &camera.camera_bind_group_layout],
+                    vec![
+                        Vertex2d::buffer_descriptor(0..2), // position and tex coords
+                        ParticleInstance::buffer_descriptor(2..7), // 2 through 6 inclusive
+                    ],
+                    blend.blend_state(),
+                )
+                .unwrap()
+        };
+
+        let particle_alpha_pipeline_key = add_particle_pipeline(ParticleBlend::Alpha);
+        let particle_additive_pipeline_key = add_particle_pipeline(ParticleBlend::Additive);
+
+        let particle_vertices = &[
+            Vertex2d {
+                position: [-0.5, -0.5],
+                tex_coords: [0.0, 1.0],
+            },
+            Vertex2d {
+                position: [0.5, -0.5],
+                tex_coords: [1.0, 1.0],
+            },
+            Vertex2d {
+                position: [0.5, 0.5],
+                tex_coords: [1.0, 0.0],
+            },
+            Vertex2d {
+                position: [-0.5, 0.5],
+                tex_coords: [0.0, 0.0],
+            },
+        ];
+
+        let particle_vertex_buffer =
+            wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("particle vertex buffer"),
+                contents: bytemuck::cast_slice(particle_vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let particle_index_buffer =
+            wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("particle index buffer"),
+                contents: bytemuck::cast_slice(&PARTICLE_INDICES),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
         Self {
             texture_bind_group_layout,
             pipeline_key,
+            mesh_pipeline_key,
+            nine_slice_pipeline_key,
+            particle_alpha_pipeline_key,
+            particle_additive_pipeline_key,
+            particle_vertex_buffer,
+            particle_index_buffer,
             camera,
+            window,
+            culling_enabled: true,
+            culling_stats: CullingStats::default(),
         }
     }
 
     pub fn update(&mut self, ctx: &mut crate::EngineContext) {
         let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
 
-        self.camera.feed_screen_size(
-            wgpu_state.window_inner_size.width,
-            wgpu_state.window_inner_size.height,
-        );
+        // The surface keeps its last size while the window is minimized (and zero sized).
+        if let Some(surface) = wgpu_state.surfaces.get(&self.window) {
+            self.camera
+                .feed_screen_size(surface.cfg.width, surface.cfg.height);
+        }
 
         self.camera.update(wgpu_state);
+
+        self.culling_stats = CullingStats::default();
+    }
+
+    /// Sets the window the renderer draws to, which is the main window by default. The camera
+    /// follows its size. See `windowing::open_window()`.
+    pub fn set_window(&mut self, window: ArenaKey) {
+        self.window = window;
+    }
+
+    /// Culling is enabled by default: instances (and whole batches) outside of the camera's view
+    /// aren't drawn.
+    pub fn set_culling_enabled(&mut self, enabled: bool) {
+        self.culling_enabled = enabled;
+    }
+
+    pub fn is_culling_enabled(&self) -> bool {
+        self.culling_enabled
+    }
+
+    /// What was drawn and culled since the last `update()`. Batches and instances drawn while
+    /// culling is disabled count as drawn.
+    pub fn culling_stats(&self) -> CullingStats {
+        self.culling_stats
+    }
+
+    /// Model matrices of the `instances` of something whose local bounds are `local_bounds`, that
+    /// are visible (or all of them, if culling is disabled). Also returns how many were culled.
+    fn visible_instances(
+        &self,
+        local_bounds: &Bounds,
+        instances: &[Instance],
+    ) -> (Vec<InstanceData>, u32) {
+        if !self.culling_enabled {
+            return (instances.iter().map(Instance::to_data).collect(), 0);
+        }
+
+        let view = Bounds::view(&self.camera);
+
+        let visible: Vec<InstanceData> = instances
+            .iter()
+            .filter(|instance| view.overlaps(&local_bounds.transformed(instance)))
+            .map(Instance::to_data)
+            .collect();
+
+        let culled = (instances.len() - visible.len()) as u32;
+        (visible, culled)
     }
 
     /// You may obtain a new `texture_bind_group_key` by calling `create_new_texture_bind_group()`.
     pub fn render_sample_texture(
         &mut self,
         ctx: &mut crate::EngineContext,
-        frt: &mut FrameRenderTarget,
+        target: &mut RenderTarget,
         texture_bind_group_key: ArenaKey,
     ) {
         let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
@@ -132,38 +299,41 @@ impl Renderer2d {
 
         // BUFFERS
 
-        let vertex_buffer =
-            wgpu_state
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("vertex buffer"),
-                    contents: bytemuck::cast_slice(vertices),
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-
-        let index_buffer =
-            wgpu_state
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("index buffer"),
-                    contents: bytemuck::cast_slice(indices),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
+        let vertex_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("vertex buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("index buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // The quad goes from -0.5 to 0.5.
+        let quad_bounds = Bounds {
+            min: nalgebra_glm::vec2(-0.5, -0.5),
+            max: nalgebra_glm::vec2(0.5, 0.5),
+        };
+
+        let (instance_data, culled) = self.visible_instances(&quad_bounds, &instances);
+        self.culling_stats
+            .record_batch(instance_data.len() as u32, culled);
+
+        if instance_data.is_empty() {
+            return;
+        }
 
-        let instance_data: Vec<InstanceData> = instances.iter().map(Instance::to_data).collect();
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("instance buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
 
-        let instance_buffer =
-            wgpu_state
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("instance buffer"),
-                    contents: bytemuck::cast_slice(&instance_data),
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-
-        // We use a scope here bc we need to borrow frt mutably.
+        // We use a scope here bc we need to borrow target mutably.
         {
-            let mut render_pass = wgpu_state.make_render_pass(frt);
+            let mut render_pass = wgpu_state.make_render_pass(target);
 
             render_pass
                 .set_pipeline(self.pipeline_key, wgpu_state)
@@ -189,14 +359,330 @@ impl Renderer2d {
                 .wgpu_render_pass
                 .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-            render_pass.wgpu_render_pass.draw_indexed(
+            render_pass.draw_indexed(
                 0..(indices.len() as u32),
                 0,
-                0..(instances.len() as u32),
+                0..(instance_data.len() as u32),
             );
         }
     }
 
+    /// Draws `mesh` once per instance, textured with the given texture (see
+    /// `create_new_texture_bind_group()`). Vertex colors are multiplied with the texture.
+    pub fn render_mesh(
+        &mut self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        mesh: &Mesh2d,
+        texture_bind_group_key: ArenaKey,
+        instances: &[Instance],
+    ) {
+        let (drawn, culled) = self.draw_mesh(
+            ctx,
+            target,
+            self.mesh_pipeline_key,
+            mesh,
+            texture_bind_group_key,
+            instances,
+        );
+
+        if drawn + culled > 0 {
+            self.culling_stats.record_batch(drawn, culled);
+        }
+    }
+
+    /// Draws the visible instances of `mesh` with a pipeline that has the same layout as the mesh
+    /// pipeline. Returns how many instances were drawn and culled.
+    fn draw_mesh(
+        &self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        pipeline_key: ArenaKey,
+        mesh: &Mesh2d,
+        texture_bind_group_key: ArenaKey,
+        instances: &[Instance],
+    ) -> (u32, u32) {
+        if instances.is_empty() || mesh.index_count() == 0 {
+            return (0, 0);
+        }
+
+        let (instance_data, culled) = self.visible_instances(mesh.local_bounds(), instances);
+        if instance_data.is_empty() {
+            return (0, culled);
+        }
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("mesh_2d instance buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass.set_pipeline(pipeline_key, wgpu_state).unwrap();
+
+        render_pass
+            .set_bind_group(0, texture_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(1, self.camera.camera_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(1, instance_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_index_buffer(mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+
+        render_pass.draw_indexed(0..mesh.index_count(), 0, 0..(instance_data.len() as u32));
+
+        (instance_data.len() as u32, culled)
+    }
+
+    /// Draws `nine_slice` with its bottom left corner at `position`, `size` big (in world units),
+    /// textured with the given texture (see `create_new_texture_bind_group()`).
+    pub fn render_nine_slice(
+        &mut self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        nine_slice: &NineSlice,
+        texture_bind_group_key: ArenaKey,
+        position: nalgebra_glm::Vec2,
+        size: nalgebra_glm::Vec2,
+    ) {
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+
+        let bounds = Bounds {
+            min: nalgebra_glm::vec2(0.0, 0.0),
+            max: size,
+        };
+        let instance = Instance {
+            position,
+            scale: 1.0,
+        };
+
+        let (instance_data, culled) = self.visible_instances(&bounds, &[instance]);
+        self.culling_stats
+            .record_batch(instance_data.len() as u32, culled);
+
+        if instance_data.is_empty() {
+            return;
+        }
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let (vertices, indices) = nine_slice.mesh(size);
+
+        let vertex_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("nine slice vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("nine slice index buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("nine slice instance buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass
+            .set_pipeline(self.nine_slice_pipeline_key, wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(0, texture_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(1, self.camera.camera_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(0, vertex_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(1, instance_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        render_pass.draw_indexed(0..(indices.len() as u32), 0, 0..1);
+    }
+
+    /// Draws the particles of `emitter`, textured with the given texture (see
+    /// `create_new_texture_bind_group()`). Call `ParticleEmitter::update()` first.
+    pub fn render_particles(
+        &mut self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        emitter: &ParticleEmitter,
+        texture_bind_group_key: ArenaKey,
+    ) {
+        if emitter.instance_count() == 0 {
+            return;
+        }
+
+        // Particles are culled as a whole. Emitters simulated on the GPU have unknown bounds.
+        if let Some(bounds) = emitter.bounds() {
+            if self.culling_enabled && !Bounds::view(&self.camera).overlaps(bounds) {
+                self.culling_stats.record_batch(0, emitter.instance_count());
+                return;
+            }
+        }
+
+        self.culling_stats.record_batch(emitter.instance_count(), 0);
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let pipeline_key = match emitter.config().blend {
+            ParticleBlend::Alpha => self.particle_alpha_pipeline_key,
+            ParticleBlend::Additive => self.particle_additive_pipeline_key,
+        };
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass.set_pipeline(pipeline_key, wgpu_state).unwrap();
+
+        render_pass
+            .set_bind_group(0, texture_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(1, self.camera.camera_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(0, self.particle_vertex_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(1, emitter.instance_buffer().slice(..));
+
+        render_pass.wgpu_render_pass.set_index_buffer(
+            self.particle_index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+
+        render_pass.draw_indexed(
+            0..(PARTICLE_INDICES.len() as u32),
+            0,
+            0..emitter.instance_count(),
+        );
+    }
+
+    /// Draws `cursor` at the cursor's position in the renderer's window (see `set_window()`),
+    /// unless the cursor is outside of it. Should be drawn last, see `SoftwareCursor`.
+    pub fn render_software_cursor(
+        &mut self,
+        ctx: &mut crate::EngineContext,
+        target: &mut RenderTarget,
+        cursor: &SoftwareCursor,
+    ) {
+        let position = match crate::input::cursor::get_window_cursor_info(ctx, self.window) {
+            (_, Some(position)) => nalgebra_glm::vec2(position.x as f32, position.y as f32),
+            (_, None) => return,
+        };
+
+        // Drawn with the world camera, so the corners are converted to world coordinates.
+        let (top_left, bottom_right) = cursor.screen_rect(position);
+        let corner = |x: f32, y: f32| {
+            let world = self
+                .camera
+                .pixel_screen_to_world_coords(&nalgebra_glm::vec2(x, y));
+            [world.x, world.y]
+        };
+        let vertices = &[
+            Vertex2d {
+                position: corner(top_left.x, bottom_right.y),
+                tex_coords: [0.0, 1.0],
+            },
+            Vertex2d {
+                position: corner(bottom_right.x, bottom_right.y),
+                tex_coords: [1.0, 1.0],
+            },
+            Vertex2d {
+                position: corner(bottom_right.x, top_left.y),
+                tex_coords: [1.0, 0.0],
+            },
+            Vertex2d {
+                position: corner(top_left.x, top_left.y),
+                tex_coords: [0.0, 0.0],
+            },
+        ];
+        let instance_data = [Instance {
+            position: nalgebra_glm::vec2(0.0, 0.0),
+            scale: 1.0,
+        }
+        .to_data()];
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let vertex_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("software cursor vertex buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("software cursor instance buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        // Same vertex layout as nine-slices, and also alpha blended.
+        render_pass
+            .set_pipeline(self.nine_slice_pipeline_key, wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(0, cursor.texture_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(1, self.camera.camera_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(0, vertex_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(1, instance_buffer.slice(..));
+
+        render_pass.wgpu_render_pass.set_index_buffer(
+            self.particle_index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+
+        render_pass.draw_indexed(0..(PARTICLE_INDICES.len() as u32), 0, 0..1);
+    }
+
     pub fn create_new_texture_bind_group(
         &mut self,
         ctx: &mut crate::EngineContext,
@@ -206,9 +692,7 @@ impl Renderer2d {
         let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
 
         let texture_key = self.create_new_texture(wgpu_state, texture_bytes, texture_label);
-        let bind_group_key =
-            self.create_new_bind_group_from_texture(wgpu_state, texture_key, texture_label);
-        bind_group_key
+        self.create_new_bind_group_from_texture(wgpu_state, texture_key, texture_label)
     }
 
     fn create_new_texture(
@@ -234,6 +718,9 @@ impl Renderer2d {
     }
 }
 
+/// Two triangles of the particle quad, counterclockwise.
+const PARTICLE_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
 // --- SHADERS ---
 
 lazy_static::lazy_static! {
@@ -273,3 +760,31 @@ fn rend_2d_vert_shader() -> &'static shaders::Shader {
 fn rend_2d_frag_shader() -> &'static shaders::Shader {
     &*COMPILED_DEFAULT_FRAG_SHADER
 }
+
+lazy_static::lazy_static! {
+    static ref COLORED_TEXTURED_VERT_SHADER: shaders::Shader =
+        shaders::load_engine_shader("rend_2d", "colored_textured.vert", shaders::ShaderType::Vertex);
+
+    static ref COLORED_TEXTURED_FRAG_SHADER: shaders::Shader =
+        shaders::load_engine_shader("rend_2d", "colored_textured.frag", shaders::ShaderType::Fragment);
+}
+
+/// Shaders for `ColoredVertex2d` + `InstanceData` + texture (set 0) + camera (set 1).
+fn colored_textured_vert_shader() -> &'static shaders::Shader {
+    &COLORED_TEXTURED_VERT_SHADER
+}
+
+fn colored_textured_frag_shader() -> &'static shaders::Shader {
+    &COLORED_TEXTURED_FRAG_SHADER
+}
+
+lazy_static::lazy_static! {
+    static ref PARTICLE_VERT_SHADER: shaders::Shader =
+        shaders::load_engine_shader("rend_2d", "particle.vert", shaders::ShaderType::Vertex);
+}
+
+/// Vertex shader for `Vertex2d` + `ParticleInstance`, used with the colored textured fragment
+/// shader.
+fn particle_vert_shader() -> &'static shaders::Shader {
+    &PARTICLE_VERT_SHADER
+}
@@ -0,0 +1,252 @@
+//! Nine-slice (nine-patch) sprites, for UI panels and buttons that may be drawn at any size.
+//!
+//! The texture region is cut into a 3x3 grid by four insets. When drawn, the corners keep their
+//! size, the top and bottom edges only stretch (or tile) horizontally, the left and right edges
+//! vertically, and the center in both directions. Draw them with `Renderer2d::render_nine_slice()`.
+
+use nalgebra_glm as glm;
+
+use super::{sprite_animation::UvRect, vertex_2d::Vertex2d};
+
+/// Widths of the borders of a nine-slice sprite, in texture pixels.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct NineSliceInsets {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl NineSliceInsets {
+    /// The same inset on every side.
+    pub fn uniform(inset: f32) -> Self {
+        Self {
+            left: inset,
+            right: inset,
+            top: inset,
+            bottom: inset,
+        }
+    }
+}
+
+/// How the edges and center of a nine-slice sprite fill the space they are given.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SliceFill {
+    Stretch,
+    /// Repeats the slice at its own size. The last repetition is cut short.
+    Tile,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NineSlice {
+    /// Region of the texture the sprite is cut from.
+    pub region: UvRect,
+    /// Size of `region`, in texture pixels.
+    pub region_size: glm::Vec2,
+    pub insets: NineSliceInsets,
+    pub edge_fill: SliceFill,
+    pub center_fill: SliceFill,
+    /// Size of a texture pixel, in world units. Borders are this many times their inset.
+    pub pixel_scale: f32,
+}
+
+impl NineSlice {
+    /// Uses the whole `texture_width` x `texture_height` texture.
+    pub fn new(texture_width: u32, texture_height: u32, insets: NineSliceInsets) -> Self {
+        Self::from_region(UvRect::full(), texture_width, texture_height, insets)
+    }
+
+    /// Uses a region of a texture (e.g. from an atlas, see `UvRect::from_pixels()`), that is
+    /// `region_width` x `region_height` pixels.
+    pub fn from_region(
+        region: UvRect,
+        region_width: u32,
+        region_height: u32,
+        insets: NineSliceInsets,
+    ) -> Self {
+        Self {
+            region,
+            region_size: glm::vec2(region_width as f32, region_height as f32),
+            insets,
+            edge_fill: SliceFill::Stretch,
+            center_fill: SliceFill::Stretch,
+            pixel_scale: 1.0,
+        }
+    }
+
+    /// Vertices and indices of the sprite drawn `size` big, its bottom left corner being at the
+    /// origin.
+    ///
+    /// If `size` is smaller than the borders, they are shrunk to fit.
+    pub(super) fn mesh(&self, size: glm::Vec2) -> (Vec<Vertex2d>, Vec<u32>) {
+        let insets = &self.insets;
+
+        let columns = Axis::new(
+            size.x,
+            insets.left * self.pixel_scale,
+            insets.right * self.pixel_scale,
+            (self.region_size.x - insets.left - insets.right) * self.pixel_scale,
+            [
+                self.region.min.x,
+                self.region.min.x + self.uv_width(insets.left),
+                self.region.max.x - self.uv_width(insets.right),
+                self.region.max.x,
+            ],
+        );
+
+        // Rows go downwards, like texture coordinates.
+        let rows = Axis::new(
+            size.y,
+            insets.top * self.pixel_scale,
+            insets.bottom * self.pixel_scale,
+            (self.region_size.y - insets.top - insets.bottom) * self.pixel_scale,
+            [
+                self.region.min.y,
+                self.region.min.y + self.uv_height(insets.top),
+                self.region.max.y - self.uv_height(insets.bottom),
+                self.region.max.y,
+            ],
+        );
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+
+        for row in 0..3 {
+            for column in 0..3 {
+                let fill = if row == 1 && column == 1 {
+                    self.center_fill
+                } else {
+                    self.edge_fill
+                };
+
+                for y in rows.spans(row, fill) {
+                    for x in columns.spans(column, fill) {
+                        let (top, bottom) = (size.y - y.start, size.y - y.end);
+
+                        let first = vertices.len() as u32;
+                        vertices.extend_from_slice(&[
+                            Vertex2d {
+                                position: [x.start, bottom],
+                                tex_coords: [x.uv_start, y.uv_end],
+                            },
+                            Vertex2d {
+                                position: [x.end, bottom],
+                                tex_coords: [x.uv_end, y.uv_end],
+                            },
+                            Vertex2d {
+                                position: [x.end, top],
+                                tex_coords: [x.uv_end, y.uv_start],
+                            },
+                            Vertex2d {
+                                position: [x.start, top],
+                                tex_coords: [x.uv_start, y.uv_start],
+                            },
+                        ]);
+                        indices.extend_from_slice(&[
+                            first,
+                            first + 1,
+                            first + 2,
+                            first,
+                            first + 2,
+                            first + 3,
+                        ]);
+                    }
+                }
+            }
+        }
+
+        (vertices, indices)
+    }
+
+    fn uv_width(&self, pixels: f32) -> f32 {
+        pixels / self.region_size.x * (self.region.max.x - self.region.min.x)
+    }
+
+    fn uv_height(&self, pixels: f32) -> f32 {
+        pixels / self.region_size.y * (self.region.max.y - self.region.min.y)
+    }
+}
+
+/// Part of one of the slices, along one axis.
+#[derive(Copy, Clone, Debug)]
+struct Span {
+    start: f32,
+    end: f32,
+    uv_start: f32,
+    uv_end: f32,
+}
+
+/// How the slices are laid out along one axis.
+struct Axis {
+    /// Where the middle slice starts and ends.
+    middle_start: f32,
+    middle_end: f32,
+    length: f32,
+    /// Size of the middle slice when it isn't stretched.
+    tile_length: f32,
+    /// Texture coordinates of the start of the region, of the two insets, and of its end.
+    uvs: [f32; 4],
+}
+
+impl Axis {
+    fn new(
+        length: f32,
+        start_border: f32,
+        end_border: f32,
+        tile_length: f32,
+        uvs: [f32; 4],
+    ) -> Self {
+        let borders = start_border + end_border;
+        let shrink = if borders > length && borders > 0.0 {
+            length / borders
+        } else {
+            1.0
+        };
+
+        Self {
+            middle_start: start_border * shrink,
+            middle_end: length - end_border * shrink,
+            length,
+            tile_length,
+            uvs,
+        }
+    }
+
+    /// Spans of the first (0), middle (1) or last (2) slice. Empty slices have no spans.
+    fn spans(&self, slice: usize, fill: SliceFill) -> Vec<Span> {
+        let uvs = &self.uvs;
+
+        let span = |start, end, uv_start, uv_end| Span {
+            start,
+            end,
+            uv_start,
+            uv_end,
+        };
+
+        let spans = match slice {
+            0 => vec![span(0.0, self.middle_start, uvs[0], uvs[1])],
+            2 => vec![span(self.middle_end, self.length, uvs[2], uvs[3])],
+            _ if fill == SliceFill::Stretch || self.tile_length <= 0.0 => {
+                vec![span(self.middle_start, self.middle_end, uvs[1], uvs[2])]
+            }
+            _ => {
+                let mut spans = vec![];
+                let mut start = self.middle_start;
+                while start < self.middle_end {
+                    let end = (start + self.tile_length).min(self.middle_end);
+                    let fraction = (end - start) / self.tile_length;
+                    spans.push(span(
+                        start,
+                        end,
+                        uvs[1],
+                        uvs[1] + (uvs[2] - uvs[1]) * fraction,
+                    ));
+                    start = end;
+                }
+                spans
+            }
+        };
+
+        spans.into_iter().filter(|s| s.end > s.start).collect()
+    }
+}
@@ -0,0 +1,50 @@
+use crate::chrono::tween::Lerp;
+
+/// Piecewise linear curve over a particle's lifetime, going from 0.0 (when spawned) to 1.0
+/// (when it dies). Values before the first key and after the last one are held.
+#[derive(Clone, Debug)]
+pub struct Curve<T: Lerp> {
+    /// Sorted by time, never empty.
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// Goes from `from` to `to` over the whole lifetime.
+    pub fn linear(from: T, to: T) -> Self {
+        Self {
+            keys: vec![(0.0, from), (1.0, to)],
+        }
+    }
+
+    /// Adds a key at time `t`, replacing any key already there.
+    pub fn with_key(mut self, t: f32, value: T) -> Self {
+        match self.keys.iter().position(|(key_t, _)| *key_t >= t) {
+            Some(i) if self.keys[i].0 == t => self.keys[i].1 = value,
+            Some(i) => self.keys.insert(i, (t, value)),
+            None => self.keys.push((t, value)),
+        }
+        self
+    }
+
+    pub fn sample(&self, t: f32) -> T {
+        let first = &self.keys[0];
+        if t <= first.0 {
+            return first.1;
+        }
+
+        for window in self.keys.windows(2) {
+            let ((t0, v0), (t1, v1)) = (&window[0], &window[1]);
+            if t <= *t1 {
+                return v0.lerp(v1, (t - t0) / (t1 - t0));
+            }
+        }
+
+        self.keys[self.keys.len() - 1].1
+    }
+}
@@ -0,0 +1,243 @@
+//! Compute shader simulation of particles, see `ParticleSimulation::Gpu`.
+
+use super::{Particle, ParticleEmitterConfig, ParticleInstance};
+use crate::{
+    arena::ArenaKey,
+    rendering::{shaders, wgpu_state::WgpuState},
+};
+
+/// Size of the lookup tables the lifetime curves are sampled into.
+const LUT_SIZE: usize = 16;
+
+/// Must match the workgroup size in `particle_update.comp`.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Header of the compute shader's parameters, followed by the uv rects of the frames. Must match
+/// `Params` in `particle_update.comp` (std430 layout).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuParams {
+    dt: f32,
+    particle_count: u32,
+    frame_count: u32,
+    animate_frames: u32,
+    size_lut: [f32; LUT_SIZE],
+    color_lut: [[f32; 4]; LUT_SIZE],
+}
+
+static_assertions::const_assert_eq!(std::mem::size_of::<GpuParams>(), 336);
+
+pub(super) struct GpuSimulation {
+    pipeline_key: ArenaKey,
+    bind_group: wgpu::BindGroup,
+    params: GpuParams,
+    params_buffer: wgpu::Buffer,
+    particle_buffer: wgpu::Buffer,
+    /// Slot the next spawned particle goes into. Slots are reused in order, so when every slot
+    /// is taken, the oldest particles are replaced.
+    next_slot: u32,
+    /// Number of slots that were ever used, up to the emitter's capacity.
+    used_slots: u32,
+}
+
+impl GpuSimulation {
+    /// `instance_buffer` must be a `STORAGE` buffer, with room for `max_particles` instances.
+    pub(super) fn new(
+        wgpu_state: &mut WgpuState,
+        config: &ParticleEmitterConfig,
+        instance_buffer: &wgpu::Buffer,
+    ) -> Self {
+        let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let bind_group_layout =
+            wgpu_state
+                .device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("particle simulation bind group layout"),
+                    entries: &[
+                        storage_entry(0, true),
+                        storage_entry(1, false),
+                        storage_entry(2, false),
+                    ],
+                });
+
+        let pipeline_key = wgpu_state
+            .add_new_compute_pipeline(particle_update_shader(), &[&bind_group_layout])
+            .unwrap();
+
+        let mut params = GpuParams {
+            dt: 0.0,
+            particle_count: config.max_particles,
+            frame_count: 0,
+            animate_frames: config.animate_frames as u32,
+            size_lut: [0.0; LUT_SIZE],
+            color_lut: [[0.0; 4]; LUT_SIZE],
+        };
+
+        for i in 0..LUT_SIZE {
+            let t = i as f32 / (LUT_SIZE - 1) as f32;
+            params.size_lut[i] = config.size_over_lifetime.sample(t);
+            params.color_lut[i] = config.color_over_lifetime.sample(t);
+        }
+
+        let uv_frames = super::uv_frames(config);
+        params.frame_count = uv_frames.len() as u32;
+
+        let mut params_bytes = bytemuck::bytes_of(&params).to_vec();
+        params_bytes.extend_from_slice(bytemuck::cast_slice(&uv_frames));
+
+        let params_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("particle simulation params buffer"),
+            contents: &params_bytes,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Zeroed particles are dead, as their age isn't below their lifetime.
+        let particle_buffer = wgpu_state.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("particle simulation particle buffer"),
+            size: (config.max_particles as usize * std::mem::size_of::<Particle>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = wgpu_state
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("particle simulation bind group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: particle_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: instance_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+        Self {
+            pipeline_key,
+            bind_group,
+            params,
+            params_buffer,
+            particle_buffer,
+            next_slot: 0,
+            used_slots: 0,
+        }
+    }
+
+    /// Number of instances to draw: every slot that was ever used, alive or not.
+    pub(super) fn used_slots(&self) -> u32 {
+        self.used_slots
+    }
+
+    pub(super) fn clear(&mut self, wgpu_state: &mut WgpuState) {
+        let zeroed = vec![0u8; self.used_slots as usize * std::mem::size_of::<Particle>()];
+        if !zeroed.is_empty() {
+            wgpu_state.write_buffer(&self.particle_buffer, 0, &zeroed);
+        }
+        self.next_slot = 0;
+    }
+
+    /// Uploads freshly spawned particles.
+    pub(super) fn spawn(&mut self, wgpu_state: &mut WgpuState, particles: &[Particle]) {
+        let capacity = self.params.particle_count;
+        if capacity == 0 {
+            return;
+        }
+
+        // If more particles were spawned at once than fit, only the last ones would survive.
+        let particles = &particles[particles.len().saturating_sub(capacity as usize)..];
+
+        let mut written = 0;
+        while written < particles.len() {
+            let count = (particles.len() - written).min((capacity - self.next_slot) as usize);
+
+            wgpu_state.write_buffer(
+                &self.particle_buffer,
+                (self.next_slot as usize * std::mem::size_of::<Particle>()) as wgpu::BufferAddress,
+                bytemuck::cast_slice(&particles[written..(written + count)]),
+            );
+
+            written += count;
+            self.next_slot = (self.next_slot + count as u32) % capacity;
+            self.used_slots = self.used_slots.max(if self.next_slot == 0 {
+                capacity
+            } else {
+                self.next_slot
+            });
+        }
+    }
+
+    /// Advances every particle by `dt` seconds, and writes the instances to draw them with.
+    pub(super) fn simulate(&mut self, wgpu_state: &mut WgpuState, dt: f32) {
+        if self.used_slots == 0 {
+            return;
+        }
+
+        self.params.dt = dt;
+        // Slots that were never used don't need to be simulated.
+        let mut params = self.params;
+        params.particle_count = self.used_slots;
+        wgpu_state.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+
+        let mut encoder =
+            wgpu_state
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("particle simulation encoder"),
+                });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("particle simulation pass"),
+            });
+
+            compute_pass.set_pipeline(
+                wgpu_state
+                    .compute_pipelines
+                    .get(self.pipeline_key)
+                    .expect("Pipeline doesn't exist"),
+            );
+            compute_pass.set_bind_group(0, &self.bind_group, &[]);
+            compute_pass.dispatch(self.used_slots.div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+
+        wgpu_state.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// Instances are written by the compute shader, which needs them to be storage buffers.
+pub(super) fn instance_buffer_usage() -> wgpu::BufferUsages {
+    wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE
+}
+
+// Checks that particles and instances match the compute shader's std430 layout, where their
+// vec4 members are 16 bytes aligned.
+static_assertions::const_assert_eq!(std::mem::size_of::<Particle>(), 48);
+static_assertions::const_assert_eq!(std::mem::size_of::<ParticleInstance>(), 48);
+
+lazy_static::lazy_static! {
+    static ref PARTICLE_UPDATE_SHADER: shaders::Shader =
+        shaders::load_engine_shader("rend_2d", "particle_update.comp", shaders::ShaderType::Compute);
+}
+
+fn particle_update_shader() -> &'static shaders::Shader {
+    &PARTICLE_UPDATE_SHADER
+}
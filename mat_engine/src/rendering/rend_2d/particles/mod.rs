@@ -0,0 +1,541 @@
+//! Particle emitters, for effects such as smoke, sparks or explosions.
+//!
+//! A `ParticleEmitter` spawns particles according to its `ParticleEmitterConfig`, and simulates
+//! them every time it is updated, either on the CPU or in a compute shader. Particles live in world
+//! space: moving the emitter doesn't move the particles it already spawned. Draw the emitter with
+//! `Renderer2d::render_particles()`, which takes the texture the particles are drawn with.
+//!
+//! ```ignore
+//! let mut emitter = ParticleEmitter::new(ctx, ParticleEmitterConfig {
+//!     spawn_rate: 200.0,
+//!     color_over_lifetime: Curve::linear([1.0, 0.8, 0.2, 1.0], [1.0, 0.1, 0.0, 0.0]),
+//!     blend: ParticleBlend::Additive,
+//!     ..Default::default()
+//! });
+//!
+//! // Every frame:
+//! emitter.update(ctx);
+//! renderer.render_particles(ctx, &mut target, &emitter, texture_bind_group_key);
+//! ```
+
+mod curve;
+mod gpu;
+
+pub use curve::Curve;
+
+use std::{
+    collections::VecDeque,
+    ops::Range,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use bytemuck::{Pod, Zeroable};
+use nalgebra_glm as glm;
+
+use super::{culling::Bounds, sprite_animation::UvRect};
+use crate::{
+    rendering::{vertex_buffer::VertexBufferable, wgpu_pipeline::VertexBufferSetting},
+    utils::unwrap_mut,
+};
+use gpu::GpuSimulation;
+
+/// How particles are blended with what is behind them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParticleBlend {
+    Alpha,
+    /// Particles brighten what is behind them, as fire or sparks do. Their alpha scales how much.
+    Additive,
+}
+
+impl ParticleBlend {
+    pub(super) fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            ParticleBlend::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            ParticleBlend::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+        }
+    }
+}
+
+/// Where particles are simulated.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParticleSimulation {
+    Cpu,
+    /// In a compute shader. Scales to many more particles, but `ParticleEmitter::particle_count()`
+    /// is then an estimate, since particles are never read back.
+    Gpu,
+}
+
+/// Describes the particles a `ParticleEmitter` spawns. Values given as a range are picked at
+/// random within it, for each particle.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitterConfig {
+    /// Once reached, new particles replace the oldest ones on the GPU, and are dropped on the CPU.
+    pub max_particles: u32,
+    /// Particles per second.
+    pub spawn_rate: f32,
+    /// In seconds.
+    pub lifetime: Range<f32>,
+    /// In world units per second.
+    pub speed: Range<f32>,
+    /// Angle of the initial velocity, in radians, counterclockwise from the x axis.
+    pub direction: Range<f32>,
+    /// Constant for each particle, e.g. gravity or wind.
+    pub acceleration: Range<glm::Vec2>,
+    /// Particles spawn within a rectangle of this size, centered on the emitter.
+    pub spawn_extents: glm::Vec2,
+    /// Initial rotation, in radians.
+    pub rotation: Range<f32>,
+    /// In radians per second.
+    pub angular_velocity: Range<f32>,
+    /// Multiplies `size_over_lifetime`.
+    pub size_scale: Range<f32>,
+    /// Side of the particles' quads, in world units.
+    pub size_over_lifetime: Curve<f32>,
+    /// RGBA, multiplied with the texture.
+    pub color_over_lifetime: Curve<[f32; 4]>,
+    /// Regions of the texture particles are drawn with, e.g. from a `SpriteSheetGrid`. When empty,
+    /// the whole texture is used.
+    pub uv_frames: Vec<UvRect>,
+    /// If true, particles go through every frame of `uv_frames` over their lifetime. Otherwise,
+    /// each particle is drawn with a single, random frame.
+    pub animate_frames: bool,
+    pub blend: ParticleBlend,
+    pub simulation: ParticleSimulation,
+}
+
+impl Default for ParticleEmitterConfig {
+    fn default() -> Self {
+        Self {
+            max_particles: 1000,
+            spawn_rate: 50.0,
+            lifetime: 1.0..2.0,
+            speed: 50.0..100.0,
+            direction: 0.0..std::f32::consts::TAU,
+            acceleration: glm::vec2(0.0, 0.0)..glm::vec2(0.0, 0.0),
+            spawn_extents: glm::vec2(0.0, 0.0),
+            rotation: 0.0..0.0,
+            angular_velocity: 0.0..0.0,
+            size_scale: 1.0..1.0,
+            size_over_lifetime: Curve::constant(8.0),
+            color_over_lifetime: Curve::linear([1.0; 4], [1.0, 1.0, 1.0, 0.0]),
+            uv_frames: vec![],
+            animate_frames: false,
+            blend: ParticleBlend::Alpha,
+            simulation: ParticleSimulation::Cpu,
+        }
+    }
+}
+
+/// Must match `Particle` in `particle_update.comp`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub(super) struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+    acceleration: [f32; 2],
+    /// In seconds. The particle is dead once it reaches `lifetime`.
+    age: f32,
+    lifetime: f32,
+    rotation: f32,
+    angular_velocity: f32,
+    size_scale: f32,
+    /// Index in `uv_frames`, unless frames are animated.
+    frame: f32,
+}
+
+/// Per particle vertex data. Must match `Instance` in `particle_update.comp`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub(super) struct ParticleInstance {
+    position: [f32; 2],
+    size: f32,
+    rotation: f32,
+    color: [f32; 4],
+    /// Top left (x, y) and bottom right (z, w) texture coordinates.
+    uv_rect: [f32; 4],
+}
+
+impl VertexBufferable for ParticleInstance {
+    fn buffer_descriptor(shader_locations: Range<u32>) -> VertexBufferSetting {
+        let start_shader_location = shader_locations.start;
+
+        assert!(shader_locations.len() == 5);
+
+        let attribute =
+            |offset: usize, format: wgpu::VertexFormat, location: u32| wgpu::VertexAttribute {
+                offset: offset as wgpu::BufferAddress,
+                format,
+                shader_location: start_shader_location + location,
+            };
+
+        VertexBufferSetting {
+            stride: std::mem::size_of::<ParticleInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: vec![
+                attribute(0, wgpu::VertexFormat::Float32x2, 0), // Position
+                attribute(8, wgpu::VertexFormat::Float32, 1),   // Size
+                attribute(12, wgpu::VertexFormat::Float32, 2),  // Rotation
+                attribute(16, wgpu::VertexFormat::Float32x4, 3), // Color
+                attribute(32, wgpu::VertexFormat::Float32x4, 4), // Uv rect
+            ],
+        }
+    }
+}
+
+/// Spawns, simulates and holds the particles of an effect. See the module documentation.
+pub struct ParticleEmitter {
+    config: ParticleEmitterConfig,
+    position: glm::Vec2,
+    active: bool,
+    /// Fraction of a particle left to spawn, carried over to the next update.
+    spawn_accumulator: f32,
+    pending_burst: u32,
+    rng: Rng,
+    /// Written to every update on the CPU, and by the compute shader on the GPU.
+    instance_buffer: wgpu::Buffer,
+    /// Number of instances in `instance_buffer` to draw.
+    instance_count: u32,
+    /// Box around every particle, used for culling. Unknown on the GPU.
+    bounds: Option<Bounds>,
+    /// Alive particles, on the CPU.
+    particles: Vec<Particle>,
+    gpu: Option<GpuSimulation>,
+    /// On the GPU, how long each particle has left to live, oldest first. Only used to count them.
+    gpu_lifetimes: VecDeque<f32>,
+}
+
+impl ParticleEmitter {
+    pub fn new(ctx: &mut crate::EngineContext, config: ParticleEmitterConfig) -> Self {
+        assert!(config.max_particles > 0, "Emitter must hold particles");
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let usage = match config.simulation {
+            ParticleSimulation::Cpu => wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            ParticleSimulation::Gpu => gpu::instance_buffer_usage(),
+        };
+
+        // Zeroed instances are empty quads.
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("particle instance buffer"),
+            contents: bytemuck::cast_slice(&vec![
+                ParticleInstance::zeroed();
+                config.max_particles as usize
+            ]),
+            usage,
+        });
+
+        let gpu = match config.simulation {
+            ParticleSimulation::Cpu => None,
+            ParticleSimulation::Gpu => {
+                Some(GpuSimulation::new(wgpu_state, &config, &instance_buffer))
+            }
+        };
+
+        Self {
+            config,
+            position: glm::vec2(0.0, 0.0),
+            active: true,
+            spawn_accumulator: 0.0,
+            pending_burst: 0,
+            rng: Rng::new(),
+            instance_buffer,
+            instance_count: 0,
+            bounds: None,
+            particles: vec![],
+            gpu,
+            gpu_lifetimes: VecDeque::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ParticleEmitterConfig {
+        &self.config
+    }
+
+    pub fn position(&self) -> glm::Vec2 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: glm::Vec2) {
+        self.position = position;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Inactive emitters stop spawning particles, but keep simulating the ones they spawned.
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+        self.spawn_accumulator = 0.0;
+    }
+
+    /// Spawns `count` particles at once on the next update, whether the emitter is active or not.
+    pub fn burst(&mut self, count: u32) {
+        self.pending_burst += count;
+    }
+
+    /// Number of alive particles.
+    pub fn particle_count(&self) -> usize {
+        match self.gpu {
+            Some(_) => self.gpu_lifetimes.len(),
+            None => self.particles.len(),
+        }
+    }
+
+    /// Kills every particle.
+    pub fn clear(&mut self, ctx: &mut crate::EngineContext) {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        self.particles.clear();
+        self.gpu_lifetimes.clear();
+        self.pending_burst = 0;
+        self.spawn_accumulator = 0.0;
+
+        match &mut self.gpu {
+            Some(gpu) => gpu.clear(wgpu_state),
+            None => {
+                self.instance_count = 0;
+                self.bounds = None;
+            }
+        }
+    }
+
+    /// Spawns new particles, and advances every particle by the frame's delta time.
+    pub fn update(&mut self, ctx: &mut crate::EngineContext) {
+        let dt = crate::chrono::delta_time(ctx) as f32;
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let mut spawn_count = std::mem::take(&mut self.pending_burst);
+        if self.active {
+            self.spawn_accumulator += self.config.spawn_rate * dt;
+            let whole = self.spawn_accumulator.floor();
+            self.spawn_accumulator -= whole;
+            spawn_count += whole as u32;
+        }
+
+        let capacity = self.config.max_particles as usize;
+
+        match &mut self.gpu {
+            Some(gpu) => {
+                // Closures capture the whole of `self` in edition 2018, which `gpu` borrows.
+                let (config, position, rng) = (&self.config, self.position, &mut self.rng);
+                let spawned: Vec<Particle> = (0..spawn_count.min(capacity as u32))
+                    .map(|_| spawn_particle(config, position, rng))
+                    .collect();
+
+                self.gpu_lifetimes
+                    .extend(spawned.iter().map(|particle| particle.lifetime));
+                while self.gpu_lifetimes.len() > capacity {
+                    self.gpu_lifetimes.pop_front();
+                }
+                self.gpu_lifetimes.retain(|lifetime| *lifetime > dt);
+                for lifetime in self.gpu_lifetimes.iter_mut() {
+                    *lifetime -= dt;
+                }
+
+                gpu.spawn(wgpu_state, &spawned);
+                gpu.simulate(wgpu_state, dt);
+                self.instance_count = gpu.used_slots();
+            }
+            None => {
+                let room = capacity - self.particles.len();
+                for _ in 0..(spawn_count as usize).min(room) {
+                    let particle = spawn_particle(&self.config, self.position, &mut self.rng);
+                    self.particles.push(particle);
+                }
+
+                let mut i = 0;
+                while i < self.particles.len() {
+                    let particle = &mut self.particles[i];
+                    particle.age += dt;
+
+                    if particle.age >= particle.lifetime {
+                        self.particles.swap_remove(i);
+                        continue;
+                    }
+
+                    particle.velocity[0] += particle.acceleration[0] * dt;
+                    particle.velocity[1] += particle.acceleration[1] * dt;
+                    particle.position[0] += particle.velocity[0] * dt;
+                    particle.position[1] += particle.velocity[1] * dt;
+                    particle.rotation += particle.angular_velocity * dt;
+                    i += 1;
+                }
+
+                let uv_frames = uv_frames(&self.config);
+                let instances: Vec<ParticleInstance> = self
+                    .particles
+                    .iter()
+                    .map(|particle| particle_instance(&self.config, &uv_frames, particle))
+                    .collect();
+
+                if !instances.is_empty() {
+                    wgpu_state.write_buffer(
+                        &self.instance_buffer,
+                        0,
+                        bytemuck::cast_slice(&instances),
+                    );
+                }
+                self.instance_count = instances.len() as u32;
+                self.bounds = instance_bounds(&instances);
+            }
+        }
+    }
+
+    pub(super) fn instance_buffer(&self) -> &wgpu::Buffer {
+        &self.instance_buffer
+    }
+
+    pub(super) fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    pub(super) fn bounds(&self) -> Option<&Bounds> {
+        self.bounds.as_ref()
+    }
+}
+
+/// Box around the quads of `instances`, whatever their rotation.
+fn instance_bounds(instances: &[ParticleInstance]) -> Option<Bounds> {
+    instances.iter().fold(None, |bounds, instance| {
+        let half_diagonal = instance.size * std::f32::consts::FRAC_1_SQRT_2;
+        let min = glm::vec2(instance.position[0], instance.position[1])
+            - glm::vec2(half_diagonal, half_diagonal);
+        let max = glm::vec2(instance.position[0], instance.position[1])
+            + glm::vec2(half_diagonal, half_diagonal);
+
+        Some(match bounds {
+            Some(bounds) => Bounds {
+                min: glm::min2(&bounds.min, &min),
+                max: glm::max2(&bounds.max, &max),
+            },
+            None => Bounds { min, max },
+        })
+    })
+}
+
+/// Uv rects of the frames particles are drawn with, as (min x, min y, max x, max y).
+fn uv_frames(config: &ParticleEmitterConfig) -> Vec<[f32; 4]> {
+    let frames: Vec<[f32; 4]> = config
+        .uv_frames
+        .iter()
+        .map(|rect| [rect.min.x, rect.min.y, rect.max.x, rect.max.y])
+        .collect();
+
+    if frames.is_empty() {
+        vec![[0.0, 0.0, 1.0, 1.0]]
+    } else {
+        frames
+    }
+}
+
+fn spawn_particle(config: &ParticleEmitterConfig, origin: glm::Vec2, rng: &mut Rng) -> Particle {
+    let offset = glm::vec2(
+        (rng.next_f32() - 0.5) * config.spawn_extents.x,
+        (rng.next_f32() - 0.5) * config.spawn_extents.y,
+    );
+    let position = origin + offset;
+
+    let direction = rng.in_range(&config.direction);
+    let speed = rng.in_range(&config.speed);
+
+    let acceleration = glm::vec2(
+        rng.in_range(&(config.acceleration.start.x..config.acceleration.end.x)),
+        rng.in_range(&(config.acceleration.start.y..config.acceleration.end.y)),
+    );
+
+    let frame_count = config.uv_frames.len().max(1);
+
+    Particle {
+        position: [position.x, position.y],
+        velocity: [direction.cos() * speed, direction.sin() * speed],
+        acceleration: [acceleration.x, acceleration.y],
+        age: 0.0,
+        lifetime: rng.in_range(&config.lifetime),
+        rotation: rng.in_range(&config.rotation),
+        angular_velocity: rng.in_range(&config.angular_velocity),
+        size_scale: rng.in_range(&config.size_scale),
+        frame: ((rng.next_f32() * frame_count as f32) as usize).min(frame_count - 1) as f32,
+    }
+}
+
+/// CPU version of what `particle_update.comp` writes for each particle.
+fn particle_instance(
+    config: &ParticleEmitterConfig,
+    uv_frames: &[[f32; 4]],
+    particle: &Particle,
+) -> ParticleInstance {
+    let t = particle.age / particle.lifetime;
+
+    let frame = if config.animate_frames {
+        (t * uv_frames.len() as f32) as usize
+    } else {
+        particle.frame as usize
+    };
+
+    ParticleInstance {
+        position: particle.position,
+        size: config.size_over_lifetime.sample(t) * particle.size_scale,
+        rotation: particle.rotation,
+        color: config.color_over_lifetime.sample(t),
+        uv_rect: uv_frames[frame.min(uv_frames.len() - 1)],
+    }
+}
+
+/// Small xorshift generator. Particles don't need good randomness, just a fast one.
+#[derive(Clone, Debug)]
+struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    /// Each emitter gets a different seed, so that emitters created on the same frame differ.
+    fn new() -> Self {
+        static EMITTER_COUNT: AtomicU32 = AtomicU32::new(0);
+
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default();
+
+        let seed = nanos
+            ^ EMITTER_COUNT
+                .fetch_add(1, Ordering::Relaxed)
+                .wrapping_mul(0x9E37_79B9);
+
+        // Xorshift never leaves 0.
+        Self {
+            state: if seed == 0 { 0x2545_F491 } else { seed },
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// In [0, 1).
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn in_range(&mut self, range: &Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.next_f32()
+    }
+}
@@ -0,0 +1,176 @@
+//! Anti-aliased vector shapes for the 2D renderer.
+//!
+//! Describe shapes with `Path`s (lines, bezier curves, arcs, rounded rectangles, polygons...),
+//! fill and/or stroke them into a `ShapeMesh`, and draw the mesh with a `ShapeRenderer`, through
+//! the same camera as the rest of the 2D scene.
+//!
+//! Meshes are plain CPU side data, so for shapes that don't change (such as most UI elements),
+//! build the mesh once and queue it every frame, rather than tessellating every frame.
+
+mod path;
+mod tessellate;
+
+pub use path::Path;
+pub use tessellate::{FillOptions, LineCap, LineJoin, ShapeMesh, StrokeOptions};
+
+use super::{
+    camera_2d::{Camera2d, CameraUniformComponent},
+    instance::{Instance, InstanceData},
+    vertex_2d::ColoredVertex2d,
+};
+use crate::{
+    arena::ArenaKey,
+    rendering::{
+        bind_group::BindGroupable, vertex_buffer::VertexBufferable, wgpu_texture::WgpuTexture,
+        RenderTarget,
+    },
+    utils::unwrap_mut,
+    EngineContext,
+};
+
+/// Shape drawing component. Meant to be used alongside `Renderer2d`, whose camera it uses.
+///
+/// Needs rendering system to be initialized.
+pub struct ShapeRenderer {
+    pipeline_key: ArenaKey,
+    /// 1x1 white texture, so that shapes can go through the same (textured) pipeline as text.
+    white_bind_group_key: ArenaKey,
+    vertices: Vec<ColoredVertex2d>,
+    indices: Vec<u32>,
+    /// Index range and instance of each queued mesh.
+    draws: Vec<(std::ops::Range<u32>, Instance)>,
+}
+
+impl ShapeRenderer {
+    pub fn new(ctx: &mut EngineContext) -> Self {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let texture_bind_group_layout = wgpu_state
+            .device
+            .create_bind_group_layout(&WgpuTexture::get_wgpu_bind_group_layout_descriptor());
+
+        let camera_bind_group_layout = wgpu_state.device.create_bind_group_layout(
+            &CameraUniformComponent::get_wgpu_bind_group_layout_descriptor(),
+        );
+
+        let pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                super::colored_textured_vert_shader(),
+                super::colored_textured_frag_shader(),
+                &[&texture_bind_group_layout, &camera_bind_group_layout],
+                vec![
+                    ColoredVertex2d::buffer_descriptor(0..3), // position, tex coords and color
+                    InstanceData::buffer_descriptor(3..7), // 3 through 6 inclusive -> single mat4
+                ],
+                wgpu::BlendState::ALPHA_BLENDING,
+            )
+            .unwrap();
+
+        let white_texture_key = wgpu_state.add_new_empty_texture(1, 1, Some("shape_white_texture"));
+        wgpu_state.write_texture_region(white_texture_key, (0, 0), (1, 1), &[255; 4]);
+
+        let white_bind_group_key = wgpu_state.add_new_texture_bind_group(
+            &texture_bind_group_layout,
+            white_texture_key,
+            Some("shape_white_texture_bind_group"),
+        );
+
+        Self {
+            pipeline_key,
+            white_bind_group_key,
+            vertices: vec![],
+            indices: vec![],
+            draws: vec![],
+        }
+    }
+
+    /// Queues `mesh` to be drawn on the next call to `render()`, transformed by `instance`.
+    pub fn queue_mesh(&mut self, mesh: &ShapeMesh, instance: Instance) {
+        if mesh.is_empty() {
+            return;
+        }
+
+        let base_vertex = self.vertices.len() as u32;
+        let first_index = self.indices.len() as u32;
+
+        self.vertices.extend_from_slice(&mesh.vertices);
+        self.indices
+            .extend(mesh.indices.iter().map(|i| i + base_vertex));
+
+        self.draws
+            .push((first_index..(self.indices.len() as u32), instance));
+    }
+
+    /// Draws every mesh queued since the last call, emptying the queue. Everything shares a
+    /// single vertex, index and instance buffer.
+    pub fn render(
+        &mut self,
+        ctx: &mut EngineContext,
+        target: &mut RenderTarget,
+        camera: &Camera2d,
+    ) {
+        if self.draws.is_empty() {
+            return;
+        }
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let instance_data: Vec<InstanceData> =
+            self.draws.iter().map(|(_, i)| i.to_data()).collect();
+
+        let vertex_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shape vertex buffer"),
+            contents: bytemuck::cast_slice(&self.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shape index buffer"),
+            contents: bytemuck::cast_slice(&self.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shape instance buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        {
+            let mut render_pass = wgpu_state.make_render_pass(target);
+
+            render_pass
+                .set_pipeline(self.pipeline_key, wgpu_state)
+                .unwrap();
+
+            render_pass
+                .set_bind_group(0, self.white_bind_group_key, &[], wgpu_state)
+                .unwrap();
+
+            render_pass
+                .set_bind_group(1, camera.camera_bind_group_key, &[], wgpu_state)
+                .unwrap();
+
+            render_pass
+                .wgpu_render_pass
+                .set_vertex_buffer(0, vertex_buffer.slice(..));
+
+            render_pass
+                .wgpu_render_pass
+                .set_vertex_buffer(1, instance_buffer.slice(..));
+
+            render_pass
+                .wgpu_render_pass
+                .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+            for (i, (indices, _)) in self.draws.iter().enumerate() {
+                let i = i as u32;
+                render_pass.draw_indexed(indices.clone(), 0, i..(i + 1));
+            }
+        }
+
+        self.vertices.clear();
+        self.indices.clear();
+        self.draws.clear();
+    }
+}
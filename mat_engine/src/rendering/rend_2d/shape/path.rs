@@ -0,0 +1,293 @@
+//! Vector paths, made out of straight lines, bezier curves and arcs.
+
+use nalgebra_glm as glm;
+
+/// Maximum number of segments a single curve is flattened into.
+const MAX_CURVE_SEGMENTS: u32 = 256;
+
+#[derive(Copy, Clone, Debug)]
+enum Command {
+    MoveTo(glm::Vec2),
+    LineTo(glm::Vec2),
+    QuadraticTo {
+        ctrl: glm::Vec2,
+        to: glm::Vec2,
+    },
+    CubicTo {
+        ctrl_1: glm::Vec2,
+        ctrl_2: glm::Vec2,
+        to: glm::Vec2,
+    },
+    Arc {
+        center: glm::Vec2,
+        radius: glm::Vec2,
+        start_angle: f32,
+        sweep_angle: f32,
+    },
+    Close,
+}
+
+/// A path, made out of one or more subpaths. Coordinates are in world units, with y positive
+/// upwards, and angles are in radians, counter clockwise from the positive x axis.
+///
+/// Build one with the builder methods, starting each subpath with `move_to()`:
+///
+/// ```ignore
+/// let mut path = Path::new();
+/// path.move_to(glm::vec2(0.0, 0.0))
+///     .line_to(glm::vec2(100.0, 0.0))
+///     .quadratic_to(glm::vec2(100.0, 100.0), glm::vec2(0.0, 100.0))
+///     .close();
+/// ```
+///
+/// or use one of the shape constructors (`Path::rect()`, `Path::rounded_rect()`, ...).
+#[derive(Clone, Debug, Default)]
+pub struct Path {
+    commands: Vec<Command>,
+}
+
+/// A subpath, flattened into straight lines.
+#[derive(Clone, Debug)]
+pub(super) struct Polyline {
+    pub(super) points: Vec<glm::Vec2>,
+    pub(super) closed: bool,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self { commands: vec![] }
+    }
+
+    /// Starts a new subpath at `to`.
+    pub fn move_to(&mut self, to: glm::Vec2) -> &mut Self {
+        self.commands.push(Command::MoveTo(to));
+        self
+    }
+
+    pub fn line_to(&mut self, to: glm::Vec2) -> &mut Self {
+        self.commands.push(Command::LineTo(to));
+        self
+    }
+
+    /// Quadratic bezier curve from the current point to `to`.
+    pub fn quadratic_to(&mut self, ctrl: glm::Vec2, to: glm::Vec2) -> &mut Self {
+        self.commands.push(Command::QuadraticTo { ctrl, to });
+        self
+    }
+
+    /// Cubic bezier curve from the current point to `to`.
+    pub fn cubic_to(&mut self, ctrl_1: glm::Vec2, ctrl_2: glm::Vec2, to: glm::Vec2) -> &mut Self {
+        self.commands.push(Command::CubicTo { ctrl_1, ctrl_2, to });
+        self
+    }
+
+    /// Circular arc around `center`, going from `start_angle` through `sweep_angle` (negative
+    /// sweeps go clockwise). A line is added from the current point to the start of the arc,
+    /// if there is a current point.
+    pub fn arc(
+        &mut self,
+        center: glm::Vec2,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> &mut Self {
+        self.elliptical_arc(center, glm::vec2(radius, radius), start_angle, sweep_angle)
+    }
+
+    /// Like `arc()`, but with different x and y radii.
+    pub fn elliptical_arc(
+        &mut self,
+        center: glm::Vec2,
+        radius: glm::Vec2,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> &mut Self {
+        self.commands.push(Command::Arc {
+            center,
+            radius,
+            start_angle,
+            sweep_angle,
+        });
+        self
+    }
+
+    /// Closes the current subpath with a line back to its first point.
+    pub fn close(&mut self) -> &mut Self {
+        self.commands.push(Command::Close);
+        self
+    }
+
+    /// Closed polygon going through `points`.
+    pub fn polygon(points: &[glm::Vec2]) -> Self {
+        let mut path = Self::polyline(points);
+        path.close();
+        path
+    }
+
+    /// Open path going through `points`.
+    pub fn polyline(points: &[glm::Vec2]) -> Self {
+        let mut path = Self::new();
+        if let Some((first, rest)) = points.split_first() {
+            path.move_to(*first);
+            for p in rest {
+                path.line_to(*p);
+            }
+        }
+        path
+    }
+
+    /// Axis aligned rectangle with `min` as its bottom left corner.
+    pub fn rect(min: glm::Vec2, size: glm::Vec2) -> Self {
+        Self::polygon(&[
+            min,
+            min + glm::vec2(size.x, 0.0),
+            min + size,
+            min + glm::vec2(0.0, size.y),
+        ])
+    }
+
+    /// Axis aligned rectangle with rounded corners. `radius` is clamped to half the smallest
+    /// side.
+    pub fn rounded_rect(min: glm::Vec2, size: glm::Vec2, radius: f32) -> Self {
+        let r = radius.min(size.x / 2.0).min(size.y / 2.0).max(0.0);
+        if r == 0.0 {
+            return Self::rect(min, size);
+        }
+
+        let max = min + size;
+        let half_pi = std::f32::consts::FRAC_PI_2;
+
+        let mut path = Self::new();
+        path.move_to(glm::vec2(min.x + r, min.y))
+            .arc(glm::vec2(max.x - r, min.y + r), r, -half_pi, half_pi)
+            .arc(glm::vec2(max.x - r, max.y - r), r, 0.0, half_pi)
+            .arc(glm::vec2(min.x + r, max.y - r), r, half_pi, half_pi)
+            .arc(glm::vec2(min.x + r, min.y + r), r, 2.0 * half_pi, half_pi)
+            .close();
+        path
+    }
+
+    pub fn circle(center: glm::Vec2, radius: f32) -> Self {
+        Self::ellipse(center, glm::vec2(radius, radius))
+    }
+
+    pub fn ellipse(center: glm::Vec2, radius: glm::Vec2) -> Self {
+        let mut path = Self::new();
+        path.elliptical_arc(center, radius, 0.0, std::f32::consts::TAU)
+            .close();
+        path
+    }
+
+    /// Regular polygon with `sides` sides, with a vertex pointing up.
+    pub fn regular_polygon(center: glm::Vec2, radius: f32, sides: u32) -> Self {
+        let points: Vec<glm::Vec2> = (0..sides.max(3))
+            .map(|i| {
+                let angle = std::f32::consts::FRAC_PI_2
+                    + i as f32 / sides.max(3) as f32 * std::f32::consts::TAU;
+                center + glm::vec2(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+
+        Self::polygon(&points)
+    }
+
+    /// Flattens every subpath into straight lines, such that no point of a curve is further
+    /// than `tolerance` from the lines approximating it.
+    pub(super) fn flatten(&self, tolerance: f32) -> Vec<Polyline> {
+        let tolerance = tolerance.max(0.001);
+
+        let mut polylines = vec![];
+        let mut current: Vec<glm::Vec2> = vec![];
+
+        let finish = |current: &mut Vec<glm::Vec2>, closed: bool, polylines: &mut Vec<Polyline>| {
+            let mut points = std::mem::take(current);
+            points.dedup_by(|a, b| glm::distance2(a, b) < 1e-12);
+            if closed
+                && points.len() > 2
+                && glm::distance2(&points[0], &points[points.len() - 1]) < 1e-12
+            {
+                points.pop();
+            }
+            if points.len() >= 2 {
+                polylines.push(Polyline { points, closed });
+            }
+        };
+
+        for command in &self.commands {
+            match *command {
+                Command::MoveTo(to) => {
+                    finish(&mut current, false, &mut polylines);
+                    current.push(to);
+                }
+                Command::LineTo(to) => current.push(to),
+                Command::QuadraticTo { ctrl, to } => {
+                    let from = current.last().copied().unwrap_or(ctrl);
+                    let dd = glm::length(&(from - ctrl * 2.0 + to));
+                    let n = segment_count((dd / (4.0 * tolerance)).sqrt());
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        current.push(from * (mt * mt) + ctrl * (2.0 * mt * t) + to * (t * t));
+                    }
+                }
+                Command::CubicTo { ctrl_1, ctrl_2, to } => {
+                    let from = current.last().copied().unwrap_or(ctrl_1);
+                    let dd = glm::length(&(from - ctrl_1 * 2.0 + ctrl_2))
+                        .max(glm::length(&(ctrl_1 - ctrl_2 * 2.0 + to)));
+                    let n = segment_count((3.0 * dd / (4.0 * tolerance)).sqrt());
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        current.push(
+                            from * (mt * mt * mt)
+                                + ctrl_1 * (3.0 * mt * mt * t)
+                                + ctrl_2 * (3.0 * mt * t * t)
+                                + to * (t * t * t),
+                        );
+                    }
+                }
+                Command::Arc {
+                    center,
+                    radius,
+                    start_angle,
+                    sweep_angle,
+                } => {
+                    let r = radius.x.abs().max(radius.y.abs());
+                    // Angle per segment so that the sagitta is at most `tolerance`.
+                    let step = if r > tolerance {
+                        2.0 * (1.0 - tolerance / r).acos()
+                    } else {
+                        std::f32::consts::FRAC_PI_2
+                    };
+                    let n = segment_count(sweep_angle.abs() / step);
+                    for i in 0..=n {
+                        let angle = start_angle + sweep_angle * (i as f32 / n as f32);
+                        current.push(
+                            center + glm::vec2(angle.cos() * radius.x, angle.sin() * radius.y),
+                        );
+                    }
+                }
+                Command::Close => {
+                    let first = current.first().copied();
+                    finish(&mut current, true, &mut polylines);
+                    // Following commands continue from the start of the closed subpath.
+                    if let Some(first) = first {
+                        current.push(first);
+                    }
+                }
+            }
+        }
+
+        finish(&mut current, false, &mut polylines);
+
+        polylines
+    }
+}
+
+fn segment_count(n: f32) -> u32 {
+    if n.is_finite() {
+        (n.ceil() as u32).clamp(1, MAX_CURVE_SEGMENTS)
+    } else {
+        1
+    }
+}
@@ -0,0 +1,524 @@
+//! Turns flattened paths into triangles.
+//!
+//! Anti-aliasing is done with geometry: every edge gets a thin "fringe" of triangles, whose
+//! alpha goes from the shape's alpha to 0, centered on the exact edge.
+
+use nalgebra_glm as glm;
+
+use super::path::Polyline;
+use crate::rendering::rend_2d::vertex_2d::ColoredVertex2d;
+
+/// Miter scale is clamped to this when offsetting polygon vertices, so very sharp corners
+/// don't produce spikes.
+const MAX_OFFSET_SCALE: f32 = 4.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Bevel,
+    Round,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Square,
+    Round,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FillOptions {
+    /// rgba
+    pub color: [f32; 4],
+    /// Width of the anti-aliasing fringe, in world units. Should be about one pixel, that is,
+    /// `1.0 / camera scale`. 0 disables anti-aliasing.
+    pub anti_alias_width: f32,
+    /// Maximum distance between curves and the lines approximating them, in world units.
+    pub tolerance: f32,
+}
+
+impl Default for FillOptions {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0, 1.0],
+            anti_alias_width: 1.0,
+            tolerance: 0.25,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct StrokeOptions {
+    /// rgba
+    pub color: [f32; 4],
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Miter joins longer than `miter_limit * width / 2` become bevel joins.
+    pub miter_limit: f32,
+    /// See `FillOptions::anti_alias_width`.
+    pub anti_alias_width: f32,
+    /// See `FillOptions::tolerance`.
+    pub tolerance: f32,
+}
+
+impl Default for StrokeOptions {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0, 1.0],
+            width: 1.0,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+            anti_alias_width: 1.0,
+            tolerance: 0.25,
+        }
+    }
+}
+
+/// Colored triangles, built from filled and stroked `Path`s. Draw them with a `ShapeRenderer`.
+#[derive(Clone, Debug, Default)]
+pub struct ShapeMesh {
+    pub(crate) vertices: Vec<ColoredVertex2d>,
+    pub(crate) indices: Vec<u32>,
+}
+
+impl ShapeMesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    /// Fills every closed subpath of `path` (open ones are closed implicitly).
+    ///
+    /// Subpaths are filled independently, so they may be concave, but shouldn't intersect
+    /// themselves, and can't be used to make holes.
+    pub fn fill(&mut self, path: &super::Path, options: &FillOptions) -> &mut Self {
+        for polyline in path.flatten(options.tolerance) {
+            self.fill_polygon(polyline.points, options);
+        }
+        self
+    }
+
+    /// Strokes every subpath of `path`.
+    pub fn stroke(&mut self, path: &super::Path, options: &StrokeOptions) -> &mut Self {
+        for polyline in path.flatten(options.tolerance) {
+            self.stroke_polyline(&polyline, options);
+        }
+        self
+    }
+
+    fn fill_polygon(&mut self, mut points: Vec<glm::Vec2>, options: &FillOptions) {
+        if points.len() < 3 {
+            return;
+        }
+
+        if signed_area(&points) < 0.0 {
+            points.reverse();
+        }
+
+        let color = options.color;
+        let aa = options.anti_alias_width.max(0.0);
+        let n = points.len();
+
+        // Vertex normals, pointing outwards (the polygon is counter clockwise).
+        let normals: Vec<glm::Vec2> = (0..n)
+            .map(|i| {
+                let prev = points[(i + n - 1) % n];
+                let next = points[(i + 1) % n];
+                offset_normal(edge_normal(prev, points[i]), edge_normal(points[i], next))
+            })
+            .collect();
+
+        let base = self.vertices.len() as u32;
+
+        // Interior, slightly shrunk so that it ends where the fringe is fully opaque.
+        for (p, normal) in points.iter().zip(&normals) {
+            self.push_vertex(p - normal * (aa / 2.0), color, 1.0);
+        }
+
+        for [a, b, c] in ear_clip(&points) {
+            self.push_triangle(base + a, base + b, base + c);
+        }
+
+        if aa > 0.0 {
+            let outer_base = self.vertices.len() as u32;
+            for (p, normal) in points.iter().zip(&normals) {
+                self.push_vertex(p + normal * (aa / 2.0), color, 0.0);
+            }
+
+            for i in 0..(n as u32) {
+                let j = (i + 1) % n as u32;
+                self.push_quad(base + i, outer_base + i, outer_base + j, base + j);
+            }
+        }
+    }
+
+    fn stroke_polyline(&mut self, polyline: &Polyline, options: &StrokeOptions) {
+        let points = &polyline.points;
+        let n = points.len();
+        if n < 2 || options.width <= 0.0 {
+            return;
+        }
+
+        let stroke = Stroke {
+            half_width: options.width / 2.0,
+            aa: options.anti_alias_width.max(0.0),
+            color: options.color,
+            tolerance: options.tolerance,
+        };
+
+        let segment_count = if polyline.closed { n } else { n - 1 };
+
+        for i in 0..segment_count {
+            self.stroke_segment(&stroke, points[i], points[(i + 1) % n]);
+        }
+
+        let (first_join, last_join) = if polyline.closed { (0, n) } else { (1, n - 1) };
+        for i in first_join..last_join {
+            let prev = points[(i + n - 1) % n];
+            let next = points[(i + 1) % n];
+            self.stroke_join(&stroke, options, prev, points[i], next);
+        }
+
+        if !polyline.closed {
+            self.stroke_cap(&stroke, options.cap, points[0], points[0] - points[1]);
+            self.stroke_cap(
+                &stroke,
+                options.cap,
+                points[n - 1],
+                points[n - 1] - points[n - 2],
+            );
+        }
+    }
+
+    /// A quad along the segment, with a fringe on both long sides.
+    fn stroke_segment(&mut self, stroke: &Stroke, a: glm::Vec2, b: glm::Vec2) {
+        if glm::distance2(&a, &b) < 1e-12 {
+            return;
+        }
+
+        let normal = glm::normalize(&perp(b - a));
+        let hw = stroke.half_width;
+        let aa = stroke.aa;
+
+        // Rows across the stroke, from the right side to the left side.
+        let mut rows = vec![];
+        if aa > 0.0 {
+            rows.push((-(hw + aa / 2.0), 0.0));
+        }
+        rows.push((-(hw - aa / 2.0).max(0.0), 1.0));
+        rows.push(((hw - aa / 2.0).max(0.0), 1.0));
+        if aa > 0.0 {
+            rows.push((hw + aa / 2.0, 0.0));
+        }
+
+        let base = self.vertices.len() as u32;
+        for (offset, alpha) in &rows {
+            self.push_vertex(a + normal * *offset, stroke.color, *alpha);
+            self.push_vertex(b + normal * *offset, stroke.color, *alpha);
+        }
+
+        for row in 0..(rows.len() as u32 - 1) {
+            let i = base + row * 2;
+            self.push_quad(i, i + 1, i + 3, i + 2);
+        }
+    }
+
+    fn stroke_join(
+        &mut self,
+        stroke: &Stroke,
+        options: &StrokeOptions,
+        prev: glm::Vec2,
+        p: glm::Vec2,
+        next: glm::Vec2,
+    ) {
+        let d0 = p - prev;
+        let d1 = next - p;
+        if glm::length2(&d0) < 1e-12 || glm::length2(&d1) < 1e-12 {
+            return;
+        }
+
+        let n0 = glm::normalize(&perp(d0));
+        let n1 = glm::normalize(&perp(d1));
+
+        // The join goes on the outer side of the turn.
+        let side = if cross(d0, d1) > 0.0 { -1.0 } else { 1.0 };
+        let hw = stroke.half_width;
+        let a = p + n0 * (side * hw);
+        let b = p + n1 * (side * hw);
+
+        let chain = match options.join {
+            LineJoin::Bevel => vec![a, b],
+            LineJoin::Miter => {
+                let cos = glm::dot(&n0, &n1);
+                // Length of the miter, relative to the half width.
+                let miter_scale = (2.0 / (1.0 + cos).max(1e-6)).sqrt();
+                if miter_scale <= options.miter_limit {
+                    let dir = glm::normalize(&(n0 + n1));
+                    vec![a, p + dir * (side * hw * miter_scale), b]
+                } else {
+                    vec![a, b]
+                }
+            }
+            LineJoin::Round => arc_points(p, hw, a - p, b - p, stroke.tolerance),
+        };
+
+        self.fan_with_fringe(stroke, p, &chain);
+    }
+
+    /// `outward` points away from the line, along its direction.
+    fn stroke_cap(&mut self, stroke: &Stroke, cap: LineCap, p: glm::Vec2, outward: glm::Vec2) {
+        if glm::length2(&outward) < 1e-12 {
+            return;
+        }
+
+        let forward = glm::normalize(&outward);
+        let normal = perp(forward);
+        let hw = stroke.half_width;
+
+        let chain = match cap {
+            LineCap::Butt => vec![p + normal * hw, p - normal * hw],
+            LineCap::Square => vec![
+                p + normal * hw,
+                p + (normal + forward) * hw,
+                p + (forward - normal) * hw,
+                p - normal * hw,
+            ],
+            LineCap::Round => arc_points(p, hw, normal * hw, -normal * hw, stroke.tolerance),
+        };
+
+        if cap == LineCap::Butt {
+            // Nothing to fill, only the fringe across the end of the line.
+            if stroke.aa > 0.0 {
+                let base = self.vertices.len() as u32;
+                let half = forward * (stroke.aa / 2.0);
+                self.push_vertex(chain[0] - half, stroke.color, 1.0);
+                self.push_vertex(chain[1] - half, stroke.color, 1.0);
+                self.push_vertex(chain[1] + half, stroke.color, 0.0);
+                self.push_vertex(chain[0] + half, stroke.color, 0.0);
+                self.push_quad(base, base + 1, base + 2, base + 3);
+            }
+            return;
+        }
+
+        self.fan_with_fringe(stroke, p, &chain);
+    }
+
+    /// Fills the fan from `center` through every point of `chain`, and adds a fringe along the
+    /// chain, pointing away from `center`.
+    fn fan_with_fringe(&mut self, stroke: &Stroke, center: glm::Vec2, chain: &[glm::Vec2]) {
+        let aa = stroke.aa;
+
+        let center_index = self.vertices.len() as u32;
+        self.push_vertex(center, stroke.color, 1.0);
+
+        let inner_base = self.vertices.len() as u32;
+        for p in chain {
+            let dir = safe_normalize(p - center);
+            self.push_vertex(p - dir * (aa / 2.0), stroke.color, 1.0);
+        }
+
+        for i in 0..(chain.len() as u32 - 1) {
+            self.push_triangle(center_index, inner_base + i, inner_base + i + 1);
+        }
+
+        if aa > 0.0 {
+            let outer_base = self.vertices.len() as u32;
+            for p in chain {
+                let dir = safe_normalize(p - center);
+                self.push_vertex(p + dir * (aa / 2.0), stroke.color, 0.0);
+            }
+
+            for i in 0..(chain.len() as u32 - 1) {
+                self.push_quad(
+                    inner_base + i,
+                    outer_base + i,
+                    outer_base + i + 1,
+                    inner_base + i + 1,
+                );
+            }
+        }
+    }
+
+    fn push_vertex(&mut self, position: glm::Vec2, color: [f32; 4], alpha: f32) {
+        self.vertices.push(ColoredVertex2d {
+            position: [position.x, position.y],
+            // Shapes are drawn with a 1x1 white texture.
+            tex_coords: [0.5, 0.5],
+            color: [color[0], color[1], color[2], color[3] * alpha],
+        });
+    }
+
+    /// Pushes a triangle, making it counter clockwise (which the pipeline expects) regardless
+    /// of the order the vertices are given in.
+    fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+        let pos = |i: u32| {
+            let p = self.vertices[i as usize].position;
+            glm::vec2(p[0], p[1])
+        };
+
+        if cross(pos(b) - pos(a), pos(c) - pos(a)) < 0.0 {
+            self.indices.extend_from_slice(&[a, c, b]);
+        } else {
+            self.indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+
+    /// Quad with the vertices in order around it (in either direction).
+    fn push_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.push_triangle(a, b, c);
+        self.push_triangle(a, c, d);
+    }
+}
+
+struct Stroke {
+    half_width: f32,
+    aa: f32,
+    color: [f32; 4],
+    tolerance: f32,
+}
+
+/// Left perpendicular.
+fn perp(v: glm::Vec2) -> glm::Vec2 {
+    glm::vec2(-v.y, v.x)
+}
+
+fn cross(a: glm::Vec2, b: glm::Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+fn safe_normalize(v: glm::Vec2) -> glm::Vec2 {
+    let len = glm::length(&v);
+    if len > 1e-6 {
+        v / len
+    } else {
+        glm::vec2(0.0, 0.0)
+    }
+}
+
+/// Outward (right hand) normal of the edge from `a` to `b` of a counter clockwise polygon.
+fn edge_normal(a: glm::Vec2, b: glm::Vec2) -> glm::Vec2 {
+    -safe_normalize(perp(b - a))
+}
+
+/// Direction to move a vertex in so that both of its edges move by one unit.
+fn offset_normal(n0: glm::Vec2, n1: glm::Vec2) -> glm::Vec2 {
+    let sum = n0 + n1;
+    let len2 = glm::length2(&sum);
+    if len2 < 1e-12 {
+        return n0;
+    }
+    let dir = sum / len2.sqrt();
+    let cos = glm::dot(&dir, &n0).max(1.0 / MAX_OFFSET_SCALE);
+    dir / cos
+}
+
+/// Points on the shortest arc around `center` from direction `from` to direction `to`.
+fn arc_points(
+    center: glm::Vec2,
+    radius: f32,
+    from: glm::Vec2,
+    to: glm::Vec2,
+    tolerance: f32,
+) -> Vec<glm::Vec2> {
+    let start = from.y.atan2(from.x);
+    let mut sweep = to.y.atan2(to.x) - start;
+    if sweep > std::f32::consts::PI {
+        sweep -= std::f32::consts::TAU;
+    } else if sweep < -std::f32::consts::PI {
+        sweep += std::f32::consts::TAU;
+    }
+    // Half circles (caps) are ambiguous; `from`/`to` are given so that going clockwise is right.
+    if (sweep.abs() - std::f32::consts::PI).abs() < 1e-4 {
+        sweep = -std::f32::consts::PI;
+    }
+
+    let tolerance = tolerance.max(0.001);
+    let step = if radius > tolerance {
+        2.0 * (1.0 - tolerance / radius).acos()
+    } else {
+        std::f32::consts::FRAC_PI_2
+    };
+    let n = ((sweep.abs() / step).ceil() as u32).clamp(1, 64);
+
+    (0..=n)
+        .map(|i| {
+            let angle = start + sweep * (i as f32 / n as f32);
+            center + glm::vec2(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Positive for counter clockwise polygons.
+fn signed_area(points: &[glm::Vec2]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        area += cross(points[i], points[(i + 1) % points.len()]);
+    }
+    area / 2.0
+}
+
+/// Triangulates a simple, counter clockwise polygon by ear clipping.
+fn ear_clip(points: &[glm::Vec2]) -> Vec<[u32; 3]> {
+    let mut remaining: Vec<u32> = (0..points.len() as u32).collect();
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+
+    let p = |i: u32| points[i as usize];
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let mut clipped = false;
+
+        for i in 0..n {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+
+            // Reflex (or degenerate) vertices can't be ears.
+            if cross(p(b) - p(a), p(c) - p(b)) <= 0.0 {
+                continue;
+            }
+
+            let contains_other = remaining
+                .iter()
+                .any(|&j| j != a && j != b && j != c && point_in_triangle(p(j), p(a), p(b), p(c)));
+
+            if !contains_other {
+                triangles.push([a, b, c]);
+                remaining.remove(i);
+                clipped = true;
+                break;
+            }
+        }
+
+        // Self intersecting or otherwise degenerate polygon: fall back to a fan, so at least
+        // something is drawn.
+        if !clipped {
+            for i in 1..(remaining.len() - 1) {
+                triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+            }
+            return triangles;
+        }
+    }
+
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+
+    triangles
+}
+
+fn point_in_triangle(p: glm::Vec2, a: glm::Vec2, b: glm::Vec2, c: glm::Vec2) -> bool {
+    cross(b - a, p - a) >= 0.0 && cross(c - b, p - b) >= 0.0 && cross(a - c, p - c) >= 0.0
+}
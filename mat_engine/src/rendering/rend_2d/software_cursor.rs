@@ -0,0 +1,46 @@
+//! Custom cursor images, drawn by the renderer, see `SoftwareCursor`.
+
+use nalgebra_glm as glm;
+
+use crate::arena::ArenaKey;
+
+/// A cursor image, drawn by `Renderer2d::render_software_cursor()` at the cursor's position.
+///
+/// The windowing library the engine uses cannot set custom cursor images, so this is drawn like
+/// any other sprite instead. Hide the system cursor with `input::set_cursor_visible(false)`, and
+/// draw this last, so that it is on top of everything else. Being drawn by the game, it lags
+/// behind the cursor by a frame or so.
+#[derive(Copy, Clone, Debug)]
+pub struct SoftwareCursor {
+    /// See `Renderer2d::create_new_texture_bind_group()`.
+    pub texture_bind_group_key: ArenaKey,
+    /// Size the image is drawn at, in physical pixels. Multiply by `windowing::scale_factor()`
+    /// to keep the same apparent size on every display.
+    pub size: glm::Vec2,
+    /// Point of the image that is placed at the cursor's position (e.g. the tip of an arrow),
+    /// in physical pixels from its top left corner.
+    pub hotspot: glm::Vec2,
+}
+
+impl SoftwareCursor {
+    /// The hotspot is the top left corner, see `with_hotspot()`.
+    pub fn new(texture_bind_group_key: ArenaKey, width: f32, height: f32) -> Self {
+        Self {
+            texture_bind_group_key,
+            size: glm::vec2(width, height),
+            hotspot: glm::vec2(0.0, 0.0),
+        }
+    }
+
+    pub fn with_hotspot(mut self, x: f32, y: f32) -> Self {
+        self.hotspot = glm::vec2(x, y);
+        self
+    }
+
+    /// Top left and bottom right corners of the image, in screen pixels (y down), when the
+    /// cursor is at `cursor`.
+    pub(super) fn screen_rect(&self, cursor: glm::Vec2) -> (glm::Vec2, glm::Vec2) {
+        let top_left = cursor - self.hotspot;
+        (top_left, top_left + self.size)
+    }
+}
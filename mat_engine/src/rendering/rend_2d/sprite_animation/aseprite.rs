@@ -0,0 +1,163 @@
+//! Importer for sprite sheets exported from Aseprite (File > Export Sprite Sheet, with JSON data),
+//! in either its "Hash" or "Array" layout.
+//!
+//! Each frame tag becomes an animation, named after the tag. Tags played in reverse get their
+//! frames reversed, and tags that repeat once play once. Sheets without tags give a single
+//! looping animation, called `""`, going through every frame.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result as AResult;
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use super::{PlaybackMode, SpriteAnimation, UvRect};
+
+pub struct AsepriteSheet {
+    /// Sheet image, resolved relative to the JSON file. Load it as the texture to draw the
+    /// animations with.
+    pub image: PathBuf,
+    pub animations: HashMap<String, SpriteAnimation>,
+}
+
+impl AsepriteSheet {
+    pub fn load_from_path(json_path: PathBuf) -> AResult<Self> {
+        let source = crate::assets::read_file_at_path_to_string(json_path.clone())
+            .map_err(|e| anyhow!("Cannot read Aseprite sheet {:?}: {}", json_path, e))?;
+
+        let folder = json_path.parent().map(PathBuf::from).unwrap_or_default();
+
+        Self::parse(&source, &folder)
+            .with_context(|| format!("Invalid Aseprite sheet {:?}", json_path))
+    }
+
+    /// `folder` is the one the image path is relative to.
+    pub fn parse(source: &str, folder: &Path) -> AResult<Self> {
+        let json: Value = serde_json::from_str(source)?;
+
+        let meta = json
+            .get("meta")
+            .ok_or_else(|| anyhow!("Missing \"meta\" object"))?;
+
+        let image = meta
+            .get("image")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Missing sheet image name"))?;
+
+        let size = meta
+            .get("size")
+            .ok_or_else(|| anyhow!("Missing sheet size"))?;
+        let (sheet_width, sheet_height) = (num(size, "w")?, num(size, "h")?);
+
+        // Frames are either an array, or an object keyed by file name, in frame order.
+        let frames: Vec<&Value> = match json.get("frames") {
+            Some(Value::Array(frames)) => frames.iter().collect(),
+            Some(Value::Object(frames)) => frames.values().collect(),
+            _ => return Err(anyhow!("Missing \"frames\"")),
+        };
+
+        let frames = frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let rect = frame
+                    .get("frame")
+                    .ok_or_else(|| anyhow!("Frame {} has no rectangle", i))?;
+
+                if frame.get("rotated").and_then(Value::as_bool) == Some(true) {
+                    return Err(anyhow!("Frame {} is rotated, which isn't supported", i));
+                }
+
+                let uv_rect = UvRect::from_pixels(
+                    num(rect, "x")?,
+                    num(rect, "y")?,
+                    num(rect, "w")?,
+                    num(rect, "h")?,
+                    sheet_width,
+                    sheet_height,
+                );
+
+                // In milliseconds.
+                let duration = num(frame, "duration")? as f32 / 1000.0;
+
+                Ok((uv_rect, duration))
+            })
+            .collect::<AResult<Vec<_>>>()?;
+
+        let mut animations = HashMap::new();
+
+        let tags = meta
+            .get("frameTags")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        for tag in tags {
+            let name = tag
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("Frame tag without a name"))?;
+
+            let (from, to) = (num(tag, "from")? as usize, num(tag, "to")? as usize);
+            if from > to || to >= frames.len() {
+                return Err(anyhow!(
+                    "Frame tag \"{}\" goes from frame {} to {}, out of {} frames",
+                    name,
+                    from,
+                    to,
+                    frames.len()
+                ));
+            }
+
+            let direction = tag
+                .get("direction")
+                .and_then(Value::as_str)
+                .unwrap_or("forward");
+
+            // Only recent versions of Aseprite export this, as a string.
+            let plays_once = tag.get("repeat").and_then(Value::as_str) == Some("1");
+
+            let mode = match direction {
+                "pingpong" | "pingpong_reverse" => PlaybackMode::PingPong,
+                _ if plays_once => PlaybackMode::Once,
+                _ => PlaybackMode::Loop,
+            };
+
+            let mut tag_frames: Vec<usize> = (from..=to).collect();
+            if direction == "reverse" || direction == "pingpong_reverse" {
+                tag_frames.reverse();
+            }
+
+            let mut animation = SpriteAnimation::new(mode);
+            for i in tag_frames {
+                animation.add_frame(frames[i].0, frames[i].1);
+            }
+
+            animations.insert(name.to_string(), animation);
+        }
+
+        if animations.is_empty() {
+            let mut animation = SpriteAnimation::new(PlaybackMode::Loop);
+            for (uv_rect, duration) in &frames {
+                animation.add_frame(*uv_rect, *duration);
+            }
+            animations.insert(String::new(), animation);
+        }
+
+        Ok(Self {
+            image: folder.join(image),
+            animations,
+        })
+    }
+}
+
+fn num(value: &Value, key: &str) -> AResult<u32> {
+    value
+        .get(key)
+        .and_then(Value::as_u64)
+        .map(|n| n as u32)
+        .ok_or_else(|| anyhow!("Missing or invalid \"{}\"", key))
+}
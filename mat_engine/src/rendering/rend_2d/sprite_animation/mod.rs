@@ -0,0 +1,474 @@
+//! Frame based sprite animations.
+//!
+//! A `SpriteAnimation` is a list of frames, each being a region of a texture (a `UvRect`) shown
+//! for some time. Build them from a grid shaped sprite sheet (`SpriteSheetGrid`), from arbitrary
+//! atlas regions, or import them from an Aseprite JSON export (see the `aseprite` module).
+//!
+//! A `SpriteAnimator` holds named animations and plays one of them at a time, advancing with
+//! `chrono::delta_time`. Draw its current frame with the quad from `UvRect::quad()`, for example
+//! through a `Mesh2d`:
+//!
+//! ```ignore
+//! animator.update(ctx);
+//! let (vertices, indices) = animator.uv_rect().quad(glm::vec2(32.0, 32.0), [1.0; 4]);
+//! mesh.set_vertices(ctx, &vertices);
+//! ```
+
+pub mod aseprite;
+
+use std::collections::HashMap;
+
+use nalgebra_glm as glm;
+
+use super::vertex_2d::ColoredVertex2d;
+
+/// Region of a texture, in texture coordinates (from 0.0 to 1.0, y going down).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UvRect {
+    /// Top left corner.
+    pub min: glm::Vec2,
+    /// Bottom right corner.
+    pub max: glm::Vec2,
+}
+
+impl UvRect {
+    /// The whole texture.
+    pub fn full() -> Self {
+        Self {
+            min: glm::vec2(0.0, 0.0),
+            max: glm::vec2(1.0, 1.0),
+        }
+    }
+
+    /// Region of a `texture_width` x `texture_height` texture, in pixels, (`x`, `y`) being its
+    /// top left corner.
+    pub fn from_pixels(
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        texture_width: u32,
+        texture_height: u32,
+    ) -> Self {
+        let (tw, th) = (texture_width as f32, texture_height as f32);
+        Self {
+            min: glm::vec2(x as f32 / tw, y as f32 / th),
+            max: glm::vec2((x + width) as f32 / tw, (y + height) as f32 / th),
+        }
+    }
+
+    /// Same region, mirrored horizontally, for characters facing the other way.
+    pub fn flipped_horizontally(&self) -> Self {
+        Self {
+            min: glm::vec2(self.max.x, self.min.y),
+            max: glm::vec2(self.min.x, self.max.y),
+        }
+    }
+
+    /// Quad of `size` (in world units), centered on the origin and showing this region. Indices
+    /// are counter clockwise, ready for `Mesh2d`.
+    pub fn quad(&self, size: glm::Vec2, color: [f32; 4]) -> ([ColoredVertex2d; 4], [u32; 6]) {
+        let (hw, hh) = (size.x / 2.0, size.y / 2.0);
+
+        let vertex = |x: f32, y: f32, u: f32, v: f32| ColoredVertex2d {
+            position: [x, y],
+            tex_coords: [u, v],
+            color,
+        };
+
+        (
+            [
+                vertex(-hw, -hh, self.min.x, self.max.y),
+                vertex(hw, -hh, self.max.x, self.max.y),
+                vertex(hw, hh, self.max.x, self.min.y),
+                vertex(-hw, hh, self.min.x, self.min.y),
+            ],
+            [0, 1, 2, 0, 2, 3],
+        )
+    }
+}
+
+/// Layout of a sprite sheet made of equally sized frames, numbered from 0, left to right and
+/// then top to bottom. Sizes are in pixels.
+#[derive(Copy, Clone, Debug)]
+pub struct SpriteSheetGrid {
+    pub texture_width: u32,
+    pub texture_height: u32,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Space between adjacent frames.
+    pub spacing: u32,
+    /// Space between the frames and the edges of the texture.
+    pub margin: u32,
+}
+
+impl SpriteSheetGrid {
+    pub fn new(
+        texture_width: u32,
+        texture_height: u32,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> Self {
+        Self {
+            texture_width,
+            texture_height,
+            frame_width,
+            frame_height,
+            spacing: 0,
+            margin: 0,
+        }
+    }
+
+    pub fn columns(&self) -> u32 {
+        let usable = self.texture_width.saturating_sub(2 * self.margin) + self.spacing;
+        usable / (self.frame_width + self.spacing).max(1)
+    }
+
+    pub fn rows(&self) -> u32 {
+        let usable = self.texture_height.saturating_sub(2 * self.margin) + self.spacing;
+        usable / (self.frame_height + self.spacing).max(1)
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.columns() * self.rows()
+    }
+
+    pub fn uv_rect(&self, frame: u32) -> UvRect {
+        let columns = self.columns().max(1);
+        let (column, row) = (frame % columns, frame / columns);
+
+        UvRect::from_pixels(
+            self.margin + column * (self.frame_width + self.spacing),
+            self.margin + row * (self.frame_height + self.spacing),
+            self.frame_width,
+            self.frame_height,
+            self.texture_width,
+            self.texture_height,
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlaybackMode {
+    /// Restarts from the first frame after the last one.
+    Loop,
+    /// Stops on the last frame.
+    Once,
+    /// Goes back and forth between the first and last frames.
+    PingPong,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationFrame {
+    pub uv_rect: UvRect,
+    /// In seconds.
+    pub duration: f32,
+    /// Events fired when the frame is reached, see `SpriteAnimator::events()`.
+    pub events: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpriteAnimation {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlaybackMode,
+}
+
+impl SpriteAnimation {
+    /// An animation without any frame. Add some with `add_frame()`.
+    pub fn new(mode: PlaybackMode) -> Self {
+        Self {
+            frames: vec![],
+            mode,
+        }
+    }
+
+    /// Animation going through `frames` of `grid`, in the given order, showing each of them for
+    /// `frame_duration` seconds.
+    pub fn from_grid(
+        grid: &SpriteSheetGrid,
+        frames: impl IntoIterator<Item = u32>,
+        frame_duration: f32,
+        mode: PlaybackMode,
+    ) -> Self {
+        let mut animation = Self::new(mode);
+        for frame in frames {
+            animation.add_frame(grid.uv_rect(frame), frame_duration);
+        }
+        animation
+    }
+
+    pub fn add_frame(&mut self, uv_rect: UvRect, duration: f32) -> &mut Self {
+        self.frames.push(AnimationFrame {
+            uv_rect,
+            duration,
+            events: vec![],
+        });
+        self
+    }
+
+    /// Fires `event` whenever `frame` is reached.
+    pub fn add_event(&mut self, frame: usize, event: &str) -> &mut Self {
+        self.frames[frame].events.push(event.to_string());
+        self
+    }
+
+    /// Sum of the durations of the frames, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+}
+
+/// Event fired by a `SpriteAnimator`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnimationEvent {
+    pub animation: String,
+    pub frame: usize,
+    pub name: String,
+}
+
+/// Frames shorter than this are shown for this long, so that animations made of zero duration
+/// frames don't loop forever in a single update.
+const MIN_FRAME_DURATION: f64 = 0.001;
+
+/// Plays named `SpriteAnimation`s, one at a time.
+pub struct SpriteAnimator {
+    animations: HashMap<String, SpriteAnimation>,
+    current: Option<String>,
+    frame: usize,
+    /// Time spent on the current frame, in seconds.
+    frame_time: f64,
+    /// 1 or -1, for ping-pong animations.
+    direction: i32,
+    /// Whether the current frame's events have been fired already.
+    entered_frame: bool,
+    finished: bool,
+    paused: bool,
+    speed: f32,
+    events: Vec<AnimationEvent>,
+}
+
+impl Default for SpriteAnimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpriteAnimator {
+    pub fn new() -> Self {
+        Self {
+            animations: HashMap::new(),
+            current: None,
+            frame: 0,
+            frame_time: 0.0,
+            direction: 1,
+            entered_frame: false,
+            finished: false,
+            paused: false,
+            speed: 1.0,
+            events: vec![],
+        }
+    }
+
+    /// Animator holding `animations`, such as those imported from an Aseprite file. Nothing plays
+    /// until `play()` is called.
+    pub fn from_animations(animations: HashMap<String, SpriteAnimation>) -> Self {
+        Self {
+            animations,
+            ..Self::new()
+        }
+    }
+
+    /// Adds (or replaces) an animation. The first animation added starts playing.
+    pub fn add_animation(&mut self, name: &str, animation: SpriteAnimation) {
+        self.animations.insert(name.to_string(), animation);
+        if self.current.is_none() {
+            self.play(name);
+        }
+    }
+
+    pub fn animation(&self, name: &str) -> Option<&SpriteAnimation> {
+        self.animations.get(name)
+    }
+
+    pub fn animation_mut(&mut self, name: &str) -> Option<&mut SpriteAnimation> {
+        self.animations.get_mut(name)
+    }
+
+    /// Switches to the animation called `name`, from its first frame. Does nothing if it is
+    /// already playing, so this may be called every frame; use `restart()` to start over.
+    ///
+    /// Panics if there is no such animation.
+    pub fn play(&mut self, name: &str) {
+        assert!(
+            self.animations.contains_key(name),
+            "No animation called \"{}\"",
+            name
+        );
+
+        if self.current.as_deref() != Some(name) {
+            self.current = Some(name.to_string());
+            self.restart();
+        }
+    }
+
+    /// Restarts the current animation from its first frame.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.frame_time = 0.0;
+        self.direction = 1;
+        self.entered_frame = false;
+        self.finished = false;
+    }
+
+    pub fn current_animation(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    pub fn current_frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Region of the texture to show, or the whole texture if nothing is playing.
+    pub fn uv_rect(&self) -> UvRect {
+        self.current_animation_ref()
+            .and_then(|a| a.frames.get(self.frame))
+            .map(|f| f.uv_rect)
+            .unwrap_or_else(UvRect::full)
+    }
+
+    /// Whether a `PlaybackMode::Once` animation reached the end of its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Playback speed multiplier, 1.0 by default.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Events fired during the last call to `update()` or `advance()`, in order.
+    pub fn events(&self) -> &[AnimationEvent] {
+        &self.events
+    }
+
+    /// Advances the animation. Should be called on the application's loop `update()` function.
+    pub fn update(&mut self, ctx: &mut crate::EngineContext) {
+        self.advance(crate::chrono::delta_time(ctx));
+    }
+
+    /// Advances the animation by `seconds`.
+    pub fn advance(&mut self, seconds: f64) {
+        self.events.clear();
+
+        let (name, animation) = match &self.current {
+            Some(name) => (name, &self.animations[name]),
+            None => return,
+        };
+
+        let frame_count = animation.frames.len();
+        if frame_count == 0 || self.paused {
+            return;
+        }
+
+        if !self.entered_frame {
+            self.entered_frame = true;
+            fire_events(&mut self.events, name, animation, self.frame);
+        }
+
+        if self.finished {
+            return;
+        }
+
+        self.frame_time += seconds * self.speed as f64;
+
+        // Skip whole cycles of looping animations at once, so that huge steps stay cheap (their
+        // events aren't fired). A cycle ends on the frame, and direction, it started from.
+        let durations = animation
+            .frames
+            .iter()
+            .map(|f| (f.duration as f64).max(MIN_FRAME_DURATION));
+        let cycle_duration = match animation.mode {
+            PlaybackMode::Once => None,
+            PlaybackMode::Loop => Some(durations.sum::<f64>()),
+            // There and back, without repeating the first and last frames.
+            PlaybackMode::PingPong => Some(
+                durations
+                    .enumerate()
+                    .map(|(i, d)| {
+                        if i == 0 || i == frame_count - 1 {
+                            d
+                        } else {
+                            2.0 * d
+                        }
+                    })
+                    .sum::<f64>(),
+            ),
+        };
+        if let Some(cycle_duration) = cycle_duration {
+            if self.frame_time > 2.0 * cycle_duration {
+                self.frame_time %= cycle_duration;
+            }
+        }
+
+        loop {
+            let duration = (animation.frames[self.frame].duration as f64).max(MIN_FRAME_DURATION);
+            if self.frame_time < duration {
+                break;
+            }
+
+            let next = match animation.mode {
+                PlaybackMode::Loop => (self.frame + 1) % frame_count,
+                PlaybackMode::Once => {
+                    if self.frame + 1 == frame_count {
+                        self.finished = true;
+                        self.frame_time = duration;
+                        break;
+                    }
+                    self.frame + 1
+                }
+                PlaybackMode::PingPong => {
+                    if frame_count == 1 {
+                        0
+                    } else {
+                        let next = self.frame as i32 + self.direction;
+                        if next < 0 || next >= frame_count as i32 {
+                            self.direction = -self.direction;
+                        }
+                        (self.frame as i32 + self.direction) as usize
+                    }
+                }
+            };
+
+            self.frame_time -= duration;
+            self.frame = next;
+            fire_events(&mut self.events, name, animation, self.frame);
+        }
+    }
+
+    fn current_animation_ref(&self) -> Option<&SpriteAnimation> {
+        self.current.as_ref().map(|name| &self.animations[name])
+    }
+}
+
+fn fire_events(
+    events: &mut Vec<AnimationEvent>,
+    animation_name: &str,
+    animation: &SpriteAnimation,
+    frame: usize,
+) {
+    for event in &animation.frames[frame].events {
+        events.push(AnimationEvent {
+            animation: animation_name.to_string(),
+            frame,
+            name: event.clone(),
+        });
+    }
+}
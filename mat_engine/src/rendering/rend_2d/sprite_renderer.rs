@@ -1,5 +1,3 @@
-use wgpu::util::DeviceExt;
-
 use super::instance::Instance;
 use crate::{
     arena::{Arena, ArenaKey},
@@ -26,20 +24,16 @@ impl CachedSprite {
     fn new_from_sprite(wgpu_state: &mut WgpuState, sprite: Sprite) -> Self {
         Self {
             sprite,
-            vertex_buffer: wgpu_state.device.create_buffer_init(
-                &wgpu::util::BufferInitDescriptor {
-                    label: Some("vertex buffer"),
-                    contents: bytemuck::cast_slice(&sprite.vertices),
-                    usage: wgpu::BufferUsages::VERTEX,
-                },
-            ),
-            index_buffer: wgpu_state
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("index buffer"),
-                    contents: bytemuck::cast_slice(&sprite.indices),
-                    usage: wgpu::BufferUsages::VERTEX,
-                }),
+            vertex_buffer: wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("vertex buffer"),
+                contents: bytemuck::cast_slice(&sprite.vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            index_buffer: wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("index buffer"),
+                contents: bytemuck::cast_slice(&sprite.indices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
         }
     }
 }
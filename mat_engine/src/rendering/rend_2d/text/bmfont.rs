@@ -0,0 +1,180 @@
+//! Parser for the text format of AngelCode BMFont `.fnt` descriptor files.
+//!
+//! See http://www.angelcode.com/products/bmfont/doc/file_format.html
+
+use std::collections::HashMap;
+
+use anyhow::Result as AResult;
+use anyhow::{anyhow, Context};
+
+#[derive(Copy, Clone, Debug)]
+pub(super) struct BmChar {
+    /// Rectangle of the glyph in its page, in pixels.
+    pub(super) x: f32,
+    pub(super) y: f32,
+    pub(super) width: f32,
+    pub(super) height: f32,
+    /// Offset from the pen position (on the top of the line) to the rectangle's top left corner.
+    pub(super) xoffset: f32,
+    pub(super) yoffset: f32,
+    pub(super) xadvance: f32,
+    pub(super) page: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct BmFont {
+    /// Size the font was generated at, in pixels.
+    pub(super) size: f32,
+    pub(super) line_height: f32,
+    /// Distance from the top of the line to the baseline.
+    pub(super) base: f32,
+    pub(super) scale_w: f32,
+    pub(super) scale_h: f32,
+    /// Page texture file names, relative to the `.fnt` file, indexed by page id.
+    pub(super) pages: Vec<String>,
+    pub(super) chars: HashMap<char, BmChar>,
+    pub(super) kernings: HashMap<(char, char), f32>,
+}
+
+pub(super) fn parse(source: &str) -> AResult<BmFont> {
+    let mut font = BmFont {
+        size: 0.0,
+        line_height: 0.0,
+        base: 0.0,
+        scale_w: 1.0,
+        scale_h: 1.0,
+        pages: vec![],
+        chars: HashMap::new(),
+        kernings: HashMap::new(),
+    };
+
+    let mut found_common = false;
+
+    for (line_number, line) in source.lines().enumerate() {
+        let (tag, attributes) = tokenize_line(line);
+
+        let attr = |key: &str| -> AResult<&str> {
+            attributes
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.as_str())
+                .ok_or_else(|| anyhow!("Missing attribute \"{}\" in \"{}\" tag", key, tag))
+        };
+        let num = |key: &str| -> AResult<f32> {
+            let value = attr(key)?;
+            value
+                .parse::<f32>()
+                .map_err(|_| anyhow!("Attribute \"{}\" is not a number: \"{}\"", key, value))
+        };
+        let character = |key: &str| -> AResult<char> {
+            let id = num(key)? as u32;
+            std::char::from_u32(id).ok_or_else(|| anyhow!("Invalid character id {}", id))
+        };
+
+        let result: AResult<()> = (|| {
+            match tag {
+                "info" => {
+                    // Negative sizes mean the font was generated matching the character height
+                    // rather than the cell height; we don't need to tell them apart.
+                    font.size = num("size")?.abs();
+                }
+                "common" => {
+                    font.line_height = num("lineHeight")?;
+                    font.base = num("base")?;
+                    font.scale_w = num("scaleW")?;
+                    font.scale_h = num("scaleH")?;
+                    found_common = true;
+                }
+                "page" => {
+                    let id = num("id")? as usize;
+                    if font.pages.len() <= id {
+                        font.pages.resize(id + 1, String::new());
+                    }
+                    font.pages[id] = attr("file")?.to_string();
+                }
+                "char" => {
+                    font.chars.insert(
+                        character("id")?,
+                        BmChar {
+                            x: num("x")?,
+                            y: num("y")?,
+                            width: num("width")?,
+                            height: num("height")?,
+                            xoffset: num("xoffset")?,
+                            yoffset: num("yoffset")?,
+                            xadvance: num("xadvance")?,
+                            page: num("page")? as usize,
+                        },
+                    );
+                }
+                "kerning" => {
+                    font.kernings
+                        .insert((character("first")?, character("second")?), num("amount")?);
+                }
+                // "chars" and "kernings" only hold counts.
+                _ => {}
+            }
+            Ok(())
+        })();
+
+        result.with_context(|| format!("Invalid BMFont file at line {}", line_number + 1))?;
+    }
+
+    if !found_common {
+        return Err(anyhow!("Invalid BMFont file: missing \"common\" tag"));
+    }
+
+    if font.size == 0.0 {
+        font.size = font.line_height;
+    }
+
+    for (c, bm_char) in &font.chars {
+        if bm_char.page >= font.pages.len() {
+            return Err(anyhow!(
+                "Invalid BMFont file: character {:?} is on page {}, which isn't declared",
+                c,
+                bm_char.page
+            ));
+        }
+    }
+
+    Ok(font)
+}
+
+/// Splits a line such as `page id=0 file="my font.png"` into its tag and its `key=value` pairs.
+fn tokenize_line(line: &str) -> (&str, Vec<(&str, String)>) {
+    let line = line.trim();
+    let (tag, mut rest) = match line.find(char::is_whitespace) {
+        Some(i) => (&line[..i], &line[i..]),
+        None => (line, ""),
+    };
+
+    let mut attributes = vec![];
+
+    loop {
+        rest = rest.trim_start();
+        let eq = match rest.find('=') {
+            Some(eq) => eq,
+            None => break,
+        };
+
+        let key = rest[..eq].trim();
+        rest = &rest[eq + 1..];
+
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let value = quoted[..end].to_string();
+            rest = &quoted[(end + 1).min(quoted.len())..];
+            value
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let value = rest[..end].to_string();
+            rest = &rest[end..];
+            value
+        };
+
+        attributes.push((key, value));
+    }
+
+    (tag, attributes)
+}
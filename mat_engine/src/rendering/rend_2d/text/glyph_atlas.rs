@@ -0,0 +1,227 @@
+//! Dynamic glyph atlas: glyphs are rasterized on demand and packed into a single texture,
+//! using a simple shelf packer.
+
+use std::collections::HashMap;
+
+use crate::{arena::ArenaKey, rendering::wgpu_state::WgpuState};
+
+const INITIAL_SIZE: u32 = 512;
+const MAX_SIZE: u32 = 4096;
+
+/// Empty pixels left around every glyph, so that linear filtering doesn't bleed neighbours in.
+const PADDING: u32 = 1;
+
+/// Identifies a rasterized glyph: the same glyph rasterized at different sizes is a
+/// different entry.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(super) struct GlyphCacheKey {
+    pub(super) font: ArenaKey,
+    pub(super) glyph_id: u16,
+    /// `f32::to_bits()` of the size in pixels.
+    pub(super) size_bits: u32,
+}
+
+/// Coverage of a single glyph, as produced by the rasterizer.
+pub(super) struct RasterizedGlyph {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    /// Top left corner of the bitmap relative to the glyph origin, y positive downwards.
+    pub(super) offset: [f32; 2],
+    /// rgba8, rows top to bottom.
+    pub(super) rgba: Vec<u8>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(super) struct AtlasGlyph {
+    pub(super) uv_min: [f32; 2],
+    pub(super) uv_max: [f32; 2],
+    /// See `RasterizedGlyph::offset`.
+    pub(super) offset: [f32; 2],
+    pub(super) size: [f32; 2],
+}
+
+pub(super) enum CacheResult {
+    /// The glyph is in the atlas (or has nothing to draw, in which case it isn't).
+    Cached,
+    /// The atlas had to grow, which threw away every glyph cached so far, including the ones
+    /// looked up earlier this frame. Everything must be looked up again.
+    Reset,
+    /// The atlas is at its maximum size and full; the glyph won't be drawn.
+    Full,
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    x_cursor: u32,
+}
+
+pub(super) struct GlyphAtlas {
+    width: u32,
+    height: u32,
+    texture_key: ArenaKey,
+    pub(super) bind_group_key: ArenaKey,
+    shelves: Vec<Shelf>,
+    next_shelf_y: u32,
+    /// `None` for glyphs without an outline (such as spaces).
+    entries: HashMap<GlyphCacheKey, Option<AtlasGlyph>>,
+}
+
+impl GlyphAtlas {
+    pub(super) fn new(
+        wgpu_state: &mut WgpuState,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let (texture_key, bind_group_key) =
+            Self::create_texture(wgpu_state, texture_bind_group_layout, INITIAL_SIZE);
+
+        Self {
+            width: INITIAL_SIZE,
+            height: INITIAL_SIZE,
+            texture_key,
+            bind_group_key,
+            shelves: vec![],
+            next_shelf_y: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn create_texture(
+        wgpu_state: &mut WgpuState,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        size: u32,
+    ) -> (ArenaKey, ArenaKey) {
+        let texture_key = wgpu_state.add_new_empty_texture(size, size, Some("glyph_atlas"));
+        let bind_group_key = wgpu_state.add_new_texture_bind_group(
+            texture_bind_group_layout,
+            texture_key,
+            Some("glyph_atlas_bind_group"),
+        );
+        (texture_key, bind_group_key)
+    }
+
+    /// Returns the cached glyph, if it has been cached and has something to draw.
+    pub(super) fn get(&self, key: &GlyphCacheKey) -> Option<AtlasGlyph> {
+        self.entries.get(key).copied().flatten()
+    }
+
+    /// Makes sure the glyph is in the atlas, calling `rasterize` if it isn't yet.
+    pub(super) fn cache<F>(
+        &mut self,
+        wgpu_state: &mut WgpuState,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+        key: GlyphCacheKey,
+        rasterize: F,
+    ) -> CacheResult
+    where
+        F: FnOnce() -> Option<RasterizedGlyph>,
+    {
+        if self.entries.contains_key(&key) {
+            return CacheResult::Cached;
+        }
+
+        let glyph = match rasterize() {
+            Some(glyph) => glyph,
+            None => {
+                self.entries.insert(key, None);
+                return CacheResult::Cached;
+            }
+        };
+
+        match self.allocate(glyph.width, glyph.height) {
+            Some((x, y)) => {
+                wgpu_state.write_texture_region(
+                    self.texture_key,
+                    (x, y),
+                    (glyph.width, glyph.height),
+                    &glyph.rgba,
+                );
+
+                let (w, h) = (self.width as f32, self.height as f32);
+                self.entries.insert(
+                    key,
+                    Some(AtlasGlyph {
+                        uv_min: [x as f32 / w, y as f32 / h],
+                        uv_max: [(x + glyph.width) as f32 / w, (y + glyph.height) as f32 / h],
+                        offset: glyph.offset,
+                        size: [glyph.width as f32, glyph.height as f32],
+                    }),
+                );
+
+                CacheResult::Cached
+            }
+            None if self.width < MAX_SIZE => {
+                self.grow(wgpu_state, texture_bind_group_layout);
+                CacheResult::Reset
+            }
+            None => {
+                log::warn!(
+                    "Glyph atlas is full ({0}x{0}), some text won't be drawn",
+                    MAX_SIZE
+                );
+                CacheResult::Full
+            }
+        }
+    }
+
+    /// Doubles the atlas size, throwing away its contents. Glyphs are re-rasterized as they are
+    /// needed, so glyphs that aren't used anymore don't make it to the new atlas.
+    fn grow(
+        &mut self,
+        wgpu_state: &mut WgpuState,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        wgpu_state.bind_groups.remove(self.bind_group_key);
+        wgpu_state.textures.remove(self.texture_key);
+
+        let size = (self.width * 2).min(MAX_SIZE);
+        let (texture_key, bind_group_key) =
+            Self::create_texture(wgpu_state, texture_bind_group_layout, size);
+
+        self.width = size;
+        self.height = size;
+        self.texture_key = texture_key;
+        self.bind_group_key = bind_group_key;
+        self.shelves.clear();
+        self.next_shelf_y = 0;
+        self.entries.clear();
+    }
+
+    /// Finds room for a `width` x `height` rectangle, returning its top left corner.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let padded_width = width + PADDING;
+        let padded_height = height + PADDING;
+
+        if padded_width > self.width {
+            return None;
+        }
+
+        // Use the shortest shelf that fits, to waste as little space as possible.
+        let atlas_width = self.width;
+        let best_shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|s| s.height >= padded_height && atlas_width - s.x_cursor >= padded_width)
+            .min_by_key(|s| s.height);
+
+        if let Some(shelf) = best_shelf {
+            let pos = (shelf.x_cursor, shelf.y);
+            shelf.x_cursor += padded_width;
+            return Some(pos);
+        }
+
+        if self.height - self.next_shelf_y < padded_height {
+            return None;
+        }
+
+        let y = self.next_shelf_y;
+        self.shelves.push(Shelf {
+            y,
+            height: padded_height,
+            x_cursor: padded_width,
+        });
+        self.next_shelf_y += padded_height;
+
+        Some((0, y))
+    }
+}
@@ -0,0 +1,185 @@
+//! Font independent text layout: line breaking, wrapping, kerning and alignment.
+
+/// Horizontal alignment of each line of text, relative to the text's bounding box.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Metrics needed to lay out text, in world units. Implemented for each kind of font.
+pub(super) trait GlyphMetrics {
+    /// How far the pen moves after drawing `c`.
+    fn advance(&self, c: char) -> f32;
+    /// Extra pen movement between `first` and `second` (usually negative).
+    fn kerning(&self, first: char, second: char) -> f32;
+    /// Distance from the top of a line to its baseline.
+    fn ascent(&self) -> f32;
+    /// Distance between the baselines of two consecutive lines.
+    fn line_height(&self) -> f32;
+}
+
+/// A glyph positioned by `layout_text()`.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct LaidOutGlyph {
+    pub(super) c: char,
+    /// Position of the glyph's origin on the baseline. y is positive upwards, with the top of
+    /// the first line at y = 0 (so every baseline has a negative y).
+    pub(super) x: f32,
+    pub(super) baseline_y: f32,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct TextLayout {
+    pub(super) glyphs: Vec<LaidOutGlyph>,
+    /// Width of the widest line (or `max_width`, when wrapping).
+    pub(super) width: f32,
+    /// Distance from the top of the first line to the bottom of the last one.
+    pub(super) height: f32,
+}
+
+/// Lays out `text`, which may contain multiple lines (separated by `'\n'`).
+///
+/// If `max_width` is given, lines are wrapped at whitespace so that they fit inside it. Words
+/// that don't fit in a line on their own are broken between characters.
+pub(super) fn layout_text<M: GlyphMetrics>(
+    metrics: &M,
+    text: &str,
+    align: HorizontalAlign,
+    max_width: Option<f32>,
+    line_spacing: f32,
+) -> TextLayout {
+    let mut lines: Vec<Vec<char>> = vec![];
+    for paragraph in text.split('\n') {
+        let paragraph: Vec<char> = paragraph.trim_end_matches('\r').chars().collect();
+        break_paragraph(metrics, &paragraph, max_width, &mut lines);
+    }
+
+    let line_widths: Vec<f32> = lines
+        .iter()
+        .map(|line| measure(metrics, trim_trailing_whitespace(line)))
+        .collect();
+
+    let box_width = match max_width {
+        Some(w) => w,
+        None => line_widths.iter().cloned().fold(0.0, f32::max),
+    };
+
+    let line_advance = metrics.line_height() * line_spacing;
+
+    let mut glyphs = vec![];
+    for (i, (line, line_width)) in lines.iter().zip(&line_widths).enumerate() {
+        let baseline_y = -(metrics.ascent() + i as f32 * line_advance);
+
+        let mut x = match align {
+            HorizontalAlign::Left => 0.0,
+            HorizontalAlign::Center => (box_width - line_width) / 2.0,
+            HorizontalAlign::Right => box_width - line_width,
+        };
+
+        let mut prev = None;
+        for &c in line {
+            if let Some(p) = prev {
+                x += metrics.kerning(p, c);
+            }
+            glyphs.push(LaidOutGlyph { c, x, baseline_y });
+            x += metrics.advance(c);
+            prev = Some(c);
+        }
+    }
+
+    let height = if lines.is_empty() {
+        0.0
+    } else {
+        (lines.len() - 1) as f32 * line_advance + metrics.line_height()
+    };
+
+    TextLayout {
+        glyphs,
+        width: box_width,
+        height,
+    }
+}
+
+/// Splits a single paragraph (no newlines) into lines that fit in `max_width`, pushing them
+/// to `lines`. An empty paragraph still produces a (blank) line.
+fn break_paragraph<M: GlyphMetrics>(
+    metrics: &M,
+    paragraph: &[char],
+    max_width: Option<f32>,
+    lines: &mut Vec<Vec<char>>,
+) {
+    let max_width = match max_width {
+        Some(w) => w,
+        None => {
+            lines.push(paragraph.to_vec());
+            return;
+        }
+    };
+
+    let mut line_start = 0;
+    // Index of the last whitespace in the current line, which is where we prefer to break.
+    let mut last_space: Option<usize> = None;
+
+    let mut i = 0;
+    while i < paragraph.len() {
+        let c = paragraph[i];
+
+        if c.is_whitespace() {
+            last_space = Some(i);
+            i += 1;
+            continue;
+        }
+
+        // Trailing whitespace never causes a line to overflow, so it isn't measured.
+        let width = measure(metrics, &paragraph[line_start..=i]);
+
+        if width > max_width && i > line_start {
+            match last_space {
+                Some(space) => {
+                    lines.push(paragraph[line_start..space].to_vec());
+                    line_start = space + 1;
+                }
+                None => {
+                    lines.push(paragraph[line_start..i].to_vec());
+                    line_start = i;
+                }
+            }
+            last_space = None;
+
+            // Skip any whitespace at the start of the new line.
+            while line_start < paragraph.len() && paragraph[line_start].is_whitespace() {
+                line_start += 1;
+            }
+            i = line_start.max(i);
+            continue;
+        }
+
+        i += 1;
+    }
+
+    lines.push(paragraph[line_start.min(paragraph.len())..].to_vec());
+}
+
+/// Width of `chars` when drawn on a single line, including kerning.
+fn measure<M: GlyphMetrics>(metrics: &M, chars: &[char]) -> f32 {
+    let mut width = 0.0;
+    let mut prev = None;
+    for &c in chars {
+        if let Some(p) = prev {
+            width += metrics.kerning(p, c);
+        }
+        width += metrics.advance(c);
+        prev = Some(c);
+    }
+    width
+}
+
+fn trim_trailing_whitespace(chars: &[char]) -> &[char] {
+    let end = chars
+        .iter()
+        .rposition(|c| !c.is_whitespace())
+        .map_or(0, |i| i + 1);
+    &chars[..end]
+}
@@ -0,0 +1,618 @@
+//! Text rendering for the 2D renderer.
+//!
+//! Two kinds of fonts are supported:
+//!
+//! - TrueType / OpenType fonts, loaded from the font file's bytes. Glyphs are rasterized on
+//!   demand, at the size they are drawn at, into a glyph atlas that grows as needed.
+//! - AngelCode BMFont bitmap fonts (text `.fnt` descriptor + page images).
+//!
+//! Text is queued with `TextRenderer::queue_text()` during the frame, and everything queued is
+//! drawn by a single call to `TextRenderer::render()`, through the same camera as the rest of
+//! the 2D scene.
+
+mod bmfont;
+mod glyph_atlas;
+mod layout;
+
+pub use layout::HorizontalAlign;
+
+use std::path::PathBuf;
+
+use ab_glyph::{Font, FontArc, GlyphId, ScaleFont};
+use anyhow::anyhow;
+use anyhow::Result as AResult;
+use nalgebra_glm as glm;
+
+use super::{
+    camera_2d::{Camera2d, CameraUniformComponent},
+    instance::{Instance, InstanceData},
+    vertex_2d::ColoredVertex2d,
+};
+use crate::{
+    arena::{Arena, ArenaKey},
+    rendering::{
+        bind_group::BindGroupable, vertex_buffer::VertexBufferable, wgpu_state::WgpuState,
+        wgpu_texture::WgpuTexture, RenderTarget,
+    },
+    utils::unwrap_mut,
+    EngineContext,
+};
+use bmfont::BmFont;
+use glyph_atlas::{CacheResult, GlyphAtlas, GlyphCacheKey, RasterizedGlyph};
+use layout::{GlyphMetrics, TextLayout};
+
+/// How a piece of text should look.
+#[derive(Copy, Clone, Debug)]
+pub struct TextStyle {
+    /// Font size in world units. For TrueType fonts, this is the size glyphs are rasterized at,
+    /// in pixels, so text is crisp when the camera scale is 1. Bitmap fonts are scaled from the
+    /// size they were generated at.
+    pub size: f32,
+    /// rgba, multiplied with the glyphs' color (which is white, for TrueType fonts).
+    pub color: [f32; 4],
+    pub align: HorizontalAlign,
+    /// If set, lines are wrapped so that they aren't wider than this.
+    pub max_width: Option<f32>,
+    /// Multiplier for the distance between lines.
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            size: 32.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            align: HorizontalAlign::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+enum FontEntry {
+    TrueType(FontArc),
+    BitMap {
+        font: BmFont,
+        page_bind_group_keys: Vec<ArenaKey>,
+    },
+}
+
+struct QueuedText {
+    font: ArenaKey,
+    text: String,
+    style: TextStyle,
+    instance: Instance,
+}
+
+/// Glyphs sharing a texture, drawn with a single instance.
+struct DrawBatch {
+    bind_group_key: ArenaKey,
+    indices: std::ops::Range<u32>,
+    instance: u32,
+}
+
+/// Text rendering component. Meant to be used alongside `Renderer2d`, whose camera it uses.
+///
+/// Needs rendering system to be initialized.
+pub struct TextRenderer {
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_key: ArenaKey,
+    fonts: Arena<FontEntry>,
+    atlas: GlyphAtlas,
+    queue: Vec<QueuedText>,
+}
+
+impl TextRenderer {
+    pub fn new(ctx: &mut EngineContext) -> Self {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let texture_bind_group_layout = wgpu_state
+            .device
+            .create_bind_group_layout(&WgpuTexture::get_wgpu_bind_group_layout_descriptor());
+
+        let camera_bind_group_layout = wgpu_state.device.create_bind_group_layout(
+            &CameraUniformComponent::get_wgpu_bind_group_layout_descriptor(),
+        );
+
+        let pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                super::colored_textured_vert_shader(),
+                super::colored_textured_frag_shader(),
+                &[&texture_bind_group_layout, &camera_bind_group_layout],
+                vec![
+                    ColoredVertex2d::buffer_descriptor(0..3), // position, tex coords and color
+                    InstanceData::buffer_descriptor(3..7), // 3 through 6 inclusive -> single mat4
+                ],
+                wgpu::BlendState::ALPHA_BLENDING,
+            )
+            .unwrap();
+
+        let atlas = GlyphAtlas::new(wgpu_state, &texture_bind_group_layout);
+
+        Self {
+            texture_bind_group_layout,
+            pipeline_key,
+            fonts: Arena::new(),
+            atlas,
+            queue: vec![],
+        }
+    }
+
+    /// Loads a TrueType (`.ttf`) or OpenType (`.otf`) font from the bytes of the font file, such
+    /// as those returned by `assets::read_file_at_path_to_bytes()`.
+    pub fn load_font(&mut self, font_bytes: Vec<u8>) -> AResult<ArenaKey> {
+        let font = FontArc::try_from_vec(font_bytes)?;
+
+        Ok(self.fonts.insert(FontEntry::TrueType(font)))
+    }
+
+    /// Loads a BMFont bitmap font, given the contents of its (text format) `.fnt` file and the
+    /// bytes of each of its page images, in page id order.
+    ///
+    /// Page images without an alpha channel are treated as coverage masks: their luminance
+    /// becomes the glyphs' alpha.
+    pub fn load_bmfont(
+        &mut self,
+        ctx: &mut EngineContext,
+        fnt_source: &str,
+        page_images: &[&[u8]],
+    ) -> AResult<ArenaKey> {
+        let font = bmfont::parse(fnt_source)?;
+
+        if page_images.len() != font.pages.len() {
+            return Err(anyhow!(
+                "BMFont has {} pages, but {} page images were given",
+                font.pages.len(),
+                page_images.len()
+            ));
+        }
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let mut page_bind_group_keys = vec![];
+        for bytes in page_images {
+            let img = decode_bmfont_page(bytes)?;
+            let texture_key = wgpu_state.add_new_texture_from_image(img, Some("bmfont_page"));
+            page_bind_group_keys.push(wgpu_state.add_new_texture_bind_group(
+                &self.texture_bind_group_layout,
+                texture_key,
+                Some("bmfont_page_bind_group"),
+            ));
+        }
+
+        Ok(self.fonts.insert(FontEntry::BitMap {
+            font,
+            page_bind_group_keys,
+        }))
+    }
+
+    /// Like `load_bmfont()`, but reads the `.fnt` file at `fnt_path`, along with the page images
+    /// it references (which are looked up relative to the `.fnt` file).
+    pub fn load_bmfont_from_path(
+        &mut self,
+        ctx: &mut EngineContext,
+        fnt_path: PathBuf,
+    ) -> AResult<ArenaKey> {
+        let fnt_source = crate::assets::read_file_at_path_to_string(fnt_path.clone())
+            .map_err(|e| anyhow!("Cannot read BMFont file {:?}: {}", fnt_path, e))?;
+
+        let folder = fnt_path.parent().map(PathBuf::from).unwrap_or_default();
+
+        let page_images = bmfont::parse(&fnt_source)?
+            .pages
+            .iter()
+            .map(|file| {
+                let path = folder.join(file);
+                crate::assets::read_file_at_path_to_bytes(path.clone())
+                    .map_err(|e| anyhow!("Cannot read BMFont page {:?}: {}", path, e))
+            })
+            .collect::<AResult<Vec<_>>>()?;
+
+        let page_images: Vec<&[u8]> = page_images.iter().map(Vec::as_slice).collect();
+
+        self.load_bmfont(ctx, &fnt_source, &page_images)
+    }
+
+    /// Queues `text` to be drawn on the next call to `render()`.
+    ///
+    /// The instance's position is the top left corner of the text's bounding box (see
+    /// `measure_text()`), and its scale multiplies the text's size, without re-rasterizing it.
+    /// Lines are separated by `'\n'`.
+    pub fn queue_text(
+        &mut self,
+        font: ArenaKey,
+        text: &str,
+        style: &TextStyle,
+        instance: Instance,
+    ) {
+        assert!(self.fonts.has_key(font), "The font doesn't exist");
+
+        self.queue.push(QueuedText {
+            font,
+            text: text.to_string(),
+            style: *style,
+            instance,
+        });
+    }
+
+    /// Returns the size of the bounding box `text` would have if drawn with `style`
+    /// (before applying the instance's scale).
+    pub fn measure_text(&self, font: ArenaKey, text: &str, style: &TextStyle) -> glm::Vec2 {
+        let layout = layout_for(self.fonts.get_unwrap(font), text, style);
+
+        glm::vec2(layout.width, layout.height)
+    }
+
+    /// Draws all the text queued since the last call, emptying the queue.
+    pub fn render(
+        &mut self,
+        ctx: &mut EngineContext,
+        target: &mut RenderTarget,
+        camera: &Camera2d,
+    ) {
+        if self.queue.is_empty() {
+            return;
+        }
+
+        let queued = std::mem::take(&mut self.queue);
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let layouts: Vec<TextLayout> = queued
+            .iter()
+            .map(|t| layout_for(self.fonts.get_unwrap(t.font), &t.text, &t.style))
+            .collect();
+
+        cache_glyphs(
+            wgpu_state,
+            &self.texture_bind_group_layout,
+            &self.fonts,
+            &mut self.atlas,
+            &queued,
+            &layouts,
+        );
+
+        // BASE DATA: VERTICES, INDICES and INSTANCES
+
+        let mut vertices: Vec<ColoredVertex2d> = vec![];
+        let mut indices: Vec<u32> = vec![];
+        let mut batches: Vec<DrawBatch> = vec![];
+
+        for (instance, (text, layout)) in queued.iter().zip(&layouts).enumerate() {
+            let color = text.style.color;
+
+            match self.fonts.get_unwrap(text.font) {
+                FontEntry::TrueType(font) => {
+                    let start = indices.len() as u32;
+
+                    for glyph in &layout.glyphs {
+                        let key = GlyphCacheKey {
+                            font: text.font,
+                            glyph_id: font.glyph_id(glyph.c).0,
+                            size_bits: text.style.size.to_bits(),
+                        };
+
+                        if let Some(g) = self.atlas.get(&key) {
+                            let left = glyph.x + g.offset[0];
+                            let top = glyph.baseline_y - g.offset[1];
+
+                            push_quad(
+                                &mut vertices,
+                                &mut indices,
+                                [left, top - g.size[1]],
+                                [left + g.size[0], top],
+                                g.uv_min,
+                                g.uv_max,
+                                color,
+                            );
+                        }
+                    }
+
+                    batches.push(DrawBatch {
+                        bind_group_key: self.atlas.bind_group_key,
+                        indices: start..(indices.len() as u32),
+                        instance: instance as u32,
+                    });
+                }
+                FontEntry::BitMap {
+                    font,
+                    page_bind_group_keys,
+                } => {
+                    let scale = text.style.size / font.size;
+
+                    for (page, bind_group_key) in page_bind_group_keys.iter().enumerate() {
+                        let start = indices.len() as u32;
+
+                        for glyph in &layout.glyphs {
+                            let c = match font.chars.get(&glyph.c) {
+                                Some(c) if c.page == page => c,
+                                _ => continue,
+                            };
+
+                            let left = glyph.x + c.xoffset * scale;
+                            let top = glyph.baseline_y + (font.base - c.yoffset) * scale;
+
+                            push_quad(
+                                &mut vertices,
+                                &mut indices,
+                                [left, top - c.height * scale],
+                                [left + c.width * scale, top],
+                                [c.x / font.scale_w, c.y / font.scale_h],
+                                [
+                                    (c.x + c.width) / font.scale_w,
+                                    (c.y + c.height) / font.scale_h,
+                                ],
+                                color,
+                            );
+                        }
+
+                        batches.push(DrawBatch {
+                            bind_group_key: *bind_group_key,
+                            indices: start..(indices.len() as u32),
+                            instance: instance as u32,
+                        });
+                    }
+                }
+            }
+        }
+
+        batches.retain(|b| !b.indices.is_empty());
+
+        if batches.is_empty() {
+            return;
+        }
+
+        let instance_data: Vec<InstanceData> =
+            queued.iter().map(|t| t.instance.to_data()).collect();
+
+        // BUFFERS
+
+        let vertex_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("text vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("text index buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("text instance buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass
+            .set_pipeline(self.pipeline_key, wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(1, camera.camera_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(0, vertex_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(1, instance_buffer.slice(..));
+
+        render_pass
+            .wgpu_render_pass
+            .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        for batch in batches {
+            render_pass
+                .set_bind_group(0, batch.bind_group_key, &[], wgpu_state)
+                .unwrap();
+
+            render_pass.draw_indexed(batch.indices, 0, batch.instance..(batch.instance + 1));
+        }
+    }
+}
+
+struct TrueTypeMetrics<'f> {
+    font: ab_glyph::PxScaleFont<&'f FontArc>,
+}
+
+impl<'f> GlyphMetrics for TrueTypeMetrics<'f> {
+    fn advance(&self, c: char) -> f32 {
+        self.font.h_advance(self.font.glyph_id(c))
+    }
+
+    fn kerning(&self, first: char, second: char) -> f32 {
+        self.font
+            .kern(self.font.glyph_id(first), self.font.glyph_id(second))
+    }
+
+    fn ascent(&self) -> f32 {
+        self.font.ascent()
+    }
+
+    fn line_height(&self) -> f32 {
+        self.font.height() + self.font.line_gap()
+    }
+}
+
+struct BitMapMetrics<'f> {
+    font: &'f BmFont,
+    scale: f32,
+}
+
+impl<'f> GlyphMetrics for BitMapMetrics<'f> {
+    fn advance(&self, c: char) -> f32 {
+        self.font.chars.get(&c).map_or(0.0, |c| c.xadvance) * self.scale
+    }
+
+    fn kerning(&self, first: char, second: char) -> f32 {
+        self.font
+            .kernings
+            .get(&(first, second))
+            .copied()
+            .unwrap_or(0.0)
+            * self.scale
+    }
+
+    fn ascent(&self) -> f32 {
+        self.font.base * self.scale
+    }
+
+    fn line_height(&self) -> f32 {
+        self.font.line_height * self.scale
+    }
+}
+
+fn layout_for(font: &FontEntry, text: &str, style: &TextStyle) -> TextLayout {
+    match font {
+        FontEntry::TrueType(font) => layout::layout_text(
+            &TrueTypeMetrics {
+                font: font.as_scaled(style.size),
+            },
+            text,
+            style.align,
+            style.max_width,
+            style.line_spacing,
+        ),
+        FontEntry::BitMap { font, .. } => layout::layout_text(
+            &BitMapMetrics {
+                font,
+                scale: style.size / font.size,
+            },
+            text,
+            style.align,
+            style.max_width,
+            style.line_spacing,
+        ),
+    }
+}
+
+/// Makes sure every TrueType glyph about to be drawn is in the atlas.
+///
+/// If the atlas has to grow midway through, everything cached before that is lost, so we start
+/// over. This terminates because the atlas only grows up to a maximum size.
+fn cache_glyphs(
+    wgpu_state: &mut WgpuState,
+    texture_bind_group_layout: &wgpu::BindGroupLayout,
+    fonts: &Arena<FontEntry>,
+    atlas: &mut GlyphAtlas,
+    queued: &[QueuedText],
+    layouts: &[TextLayout],
+) {
+    'retry: loop {
+        for (text, layout) in queued.iter().zip(layouts) {
+            let font = match fonts.get_unwrap(text.font) {
+                FontEntry::TrueType(font) => font,
+                FontEntry::BitMap { .. } => continue,
+            };
+
+            for glyph in &layout.glyphs {
+                let glyph_id = font.glyph_id(glyph.c);
+                let key = GlyphCacheKey {
+                    font: text.font,
+                    glyph_id: glyph_id.0,
+                    size_bits: text.style.size.to_bits(),
+                };
+
+                match atlas.cache(wgpu_state, texture_bind_group_layout, key, || {
+                    rasterize_glyph(font, glyph_id, text.style.size)
+                }) {
+                    CacheResult::Cached => {}
+                    CacheResult::Reset => continue 'retry,
+                    CacheResult::Full => return,
+                }
+            }
+        }
+
+        return;
+    }
+}
+
+/// Rasterizes a glyph into white rgba8 pixels, with the coverage as alpha. Returns `None` for
+/// glyphs without an outline, such as spaces.
+fn rasterize_glyph(font: &FontArc, glyph_id: GlyphId, size: f32) -> Option<RasterizedGlyph> {
+    let outlined = font.outline_glyph(glyph_id.with_scale(size))?;
+
+    let bounds = outlined.px_bounds();
+    let width = bounds.width() as u32;
+    let height = bounds.height() as u32;
+
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut rgba = vec![0; (width * height * 4) as usize];
+    outlined.draw(|x, y, coverage| {
+        if x < width && y < height {
+            let i = ((y * width + x) * 4) as usize;
+            let alpha = (coverage.min(1.0) * 255.0).round() as u8;
+            rgba[i..(i + 4)].copy_from_slice(&[255, 255, 255, alpha]);
+        }
+    });
+
+    Some(RasterizedGlyph {
+        width,
+        height,
+        offset: [bounds.min.x, bounds.min.y],
+        rgba,
+    })
+}
+
+fn decode_bmfont_page(bytes: &[u8]) -> AResult<image::DynamicImage> {
+    let img = image::load_from_memory(bytes)?;
+    let has_alpha = img.color().has_alpha();
+
+    let mut rgba = img.to_rgba8();
+    if !has_alpha {
+        for pixel in rgba.pixels_mut() {
+            *pixel = image::Rgba([255, 255, 255, pixel[0]]);
+        }
+    }
+
+    Ok(image::DynamicImage::ImageRgba8(rgba))
+}
+
+/// Pushes a quad spanning from `min` (bottom left) to `max` (top right). Textures have v
+/// growing downwards, so `uv_min` is the top left of the texture rectangle.
+fn push_quad(
+    vertices: &mut Vec<ColoredVertex2d>,
+    indices: &mut Vec<u32>,
+    min: [f32; 2],
+    max: [f32; 2],
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    color: [f32; 4],
+) {
+    let base = vertices.len() as u32;
+
+    vertices.extend_from_slice(&[
+        ColoredVertex2d {
+            position: [min[0], min[1]],
+            tex_coords: [uv_min[0], uv_max[1]],
+            color,
+        },
+        ColoredVertex2d {
+            position: [max[0], min[1]],
+            tex_coords: [uv_max[0], uv_max[1]],
+            color,
+        },
+        ColoredVertex2d {
+            position: [max[0], max[1]],
+            tex_coords: [uv_max[0], uv_min[1]],
+            color,
+        },
+        ColoredVertex2d {
+            position: [min[0], max[1]],
+            tex_coords: [uv_min[0], uv_min[1]],
+            color,
+        },
+    ]);
+
+    // Counter clockwise, see the pipeline's culling settings.
+    indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+}
@@ -0,0 +1,684 @@
+//! Tile based maps.
+//!
+//! A `Tilemap` is a grid of tiles, in one or more layers, drawn from one or more `Tileset`s.
+//! Layers are split into chunks of `CHUNK_SIZE`x`CHUNK_SIZE` tiles, each with its own static
+//! vertex buffer, which is only rebuilt when one of its tiles changes. Chunks outside the
+//! camera's view aren't drawn.
+//!
+//! Animated tiles are kept out of the chunks, and are instead rebuilt every frame (only the
+//! visible ones), so animations don't cause chunks to be rebuilt.
+//!
+//! Maps made with the Tiled editor can be loaded with the `tiled` module.
+
+pub mod tiled;
+mod tileset;
+
+pub use tileset::{TileAnimationFrame, Tileset, TilesetLayout};
+
+use std::ops::Range;
+
+use nalgebra_glm as glm;
+
+use super::{
+    camera_2d::{Camera2d, CameraUniformComponent},
+    instance::{Instance, InstanceData},
+    vertex_2d::ColoredVertex2d,
+};
+use crate::{
+    arena::ArenaKey,
+    rendering::{
+        bind_group::BindGroupable, vertex_buffer::VertexBufferable, wgpu_state::WgpuState,
+        wgpu_texture::WgpuTexture, RenderTarget,
+    },
+    utils::unwrap_mut,
+    EngineContext,
+};
+
+/// Width and height of a chunk, in tiles.
+pub const CHUNK_SIZE: u32 = 16;
+
+/// How the tile grid is projected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TilemapOrientation {
+    /// Rectangular tiles, laid out in rows and columns.
+    Orthogonal,
+    /// Diamond shaped tiles, with the x axis going down to the right and the y axis going down
+    /// to the left (tile (0, 0) being the topmost one).
+    Isometric,
+}
+
+/// A cell of a tilemap layer.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tile {
+    /// Index of the tileset, as returned by `Tilemap::add_tileset()`.
+    pub tileset: usize,
+    /// Index of the tile inside its tileset.
+    pub id: u32,
+    /// Flips are applied diagonal first (which swaps the x and y axes), then horizontal, then
+    /// vertical, like Tiled does.
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    pub fn new(tileset: usize, id: u32) -> Self {
+        Self {
+            tileset,
+            id,
+            ..Default::default()
+        }
+    }
+}
+
+/// Index ranges, in drawing order, and the tileset each of them uses.
+type DrawRanges = Vec<(usize, Range<u32>)>;
+
+struct Chunk {
+    /// `None` until first built, and for chunks without any (static) tile.
+    buffers: Option<(wgpu::Buffer, wgpu::Buffer)>,
+    ranges: DrawRanges,
+    dirty: bool,
+}
+
+struct TileLayer {
+    name: String,
+    tiles: Vec<Option<Tile>>,
+    visible: bool,
+    /// rgba, multiplied with every tile of the layer.
+    tint: [f32; 4],
+    chunks: Vec<Chunk>,
+}
+
+/// Tilemap component. Meant to be used alongside `Renderer2d`, whose camera it uses.
+///
+/// Tile (0, 0) is the top left one (the top one on isometric maps), with x going right and y
+/// going down, like in most tile editors. `position` is the world position of the top left
+/// corner of the map's bounding box.
+///
+/// Tiles are drawn row by row, so overlapping tiles (see `Tileset::set_world_size()`) are
+/// correctly ordered inside of a chunk, but not across chunk borders.
+///
+/// Needs rendering system to be initialized.
+pub struct Tilemap {
+    pipeline_key: ArenaKey,
+    orientation: TilemapOrientation,
+    width: u32,
+    height: u32,
+    /// Size of a tile in world units. On isometric maps, size of the diamond's bounding box.
+    tile_size: glm::Vec2,
+    position: glm::Vec2,
+    tilesets: Vec<Tileset>,
+    layers: Vec<TileLayer>,
+    /// Seconds since creation, for animations.
+    time: f64,
+}
+
+impl Tilemap {
+    /// Creates an orthogonal map that is `width` x `height` tiles big, and without any layer.
+    pub fn new(ctx: &mut EngineContext, width: u32, height: u32, tile_size: glm::Vec2) -> Self {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let texture_bind_group_layout = wgpu_state
+            .device
+            .create_bind_group_layout(&WgpuTexture::get_wgpu_bind_group_layout_descriptor());
+
+        let camera_bind_group_layout = wgpu_state.device.create_bind_group_layout(
+            &CameraUniformComponent::get_wgpu_bind_group_layout_descriptor(),
+        );
+
+        let pipeline_key = wgpu_state
+            .add_new_render_pipeline(
+                super::colored_textured_vert_shader(),
+                super::colored_textured_frag_shader(),
+                &[&texture_bind_group_layout, &camera_bind_group_layout],
+                vec![
+                    ColoredVertex2d::buffer_descriptor(0..3), // position, tex coords and color
+                    InstanceData::buffer_descriptor(3..7), // 3 through 6 inclusive -> single mat4
+                ],
+                wgpu::BlendState::ALPHA_BLENDING,
+            )
+            .unwrap();
+
+        Self {
+            pipeline_key,
+            orientation: TilemapOrientation::Orthogonal,
+            width,
+            height,
+            tile_size,
+            position: glm::vec2(0.0, 0.0),
+            tilesets: vec![],
+            layers: vec![],
+            time: 0.0,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn tile_size(&self) -> glm::Vec2 {
+        self.tile_size
+    }
+
+    pub fn orientation(&self) -> TilemapOrientation {
+        self.orientation
+    }
+
+    pub fn set_orientation(&mut self, orientation: TilemapOrientation) {
+        self.orientation = orientation;
+        self.mark_all_dirty();
+    }
+
+    pub fn position(&self) -> glm::Vec2 {
+        self.position
+    }
+
+    /// Sets the world position of the top left corner of the map's bounding box.
+    pub fn set_position(&mut self, position: glm::Vec2) {
+        self.position = position;
+    }
+
+    /// Returns the index to use for `Tile::tileset`.
+    pub fn add_tileset(&mut self, tileset: Tileset) -> usize {
+        self.tilesets.push(tileset);
+        self.tilesets.len() - 1
+    }
+
+    pub fn tileset(&self, tileset: usize) -> &Tileset {
+        &self.tilesets[tileset]
+    }
+
+    /// Every chunk is rebuilt on the next render, as the tileset's animations or size may
+    /// change.
+    pub fn tileset_mut(&mut self, tileset: usize) -> &mut Tileset {
+        self.mark_all_dirty();
+        &mut self.tilesets[tileset]
+    }
+
+    /// Adds an empty layer on top of the existing ones, returning its index.
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        let chunk_count = (self.chunks_x() * self.chunks_y()) as usize;
+
+        self.layers.push(TileLayer {
+            name: name.to_string(),
+            tiles: vec![None; (self.width * self.height) as usize],
+            visible: true,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            chunks: (0..chunk_count)
+                .map(|_| Chunk {
+                    buffers: None,
+                    ranges: vec![],
+                    dirty: true,
+                })
+                .collect(),
+        });
+
+        self.layers.len() - 1
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn layer_name(&self, layer: usize) -> &str {
+        &self.layers[layer].name
+    }
+
+    /// Index of the first layer called `name`.
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|l| l.name == name)
+    }
+
+    pub fn set_layer_visible(&mut self, layer: usize, visible: bool) {
+        self.layers[layer].visible = visible;
+    }
+
+    /// Sets a color (rgba) that every tile of the layer is multiplied with. Use it to set the
+    /// layer's opacity, for example.
+    pub fn set_layer_tint(&mut self, layer: usize, tint: [f32; 4]) {
+        let layer = &mut self.layers[layer];
+        layer.tint = tint;
+        for chunk in &mut layer.chunks {
+            chunk.dirty = true;
+        }
+    }
+
+    /// Returns `None` for empty cells and for cells outside the map.
+    pub fn tile(&self, layer: usize, x: u32, y: u32) -> Option<Tile> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.layers[layer].tiles[(y * self.width + x) as usize]
+    }
+
+    /// Sets (or, with `None`, clears) a tile. Only the chunk containing it is rebuilt, on the
+    /// next render.
+    pub fn set_tile(&mut self, layer: usize, x: u32, y: u32, tile: Option<Tile>) {
+        assert!(
+            x < self.width && y < self.height,
+            "Tile ({}, {}) is outside the map",
+            x,
+            y
+        );
+
+        if let Some(tile) = tile {
+            assert!(
+                tile.tileset < self.tilesets.len(),
+                "The tileset doesn't exist"
+            );
+        }
+
+        let chunk_index = ((y / CHUNK_SIZE) * self.chunks_x() + x / CHUNK_SIZE) as usize;
+        let width = self.width;
+
+        let layer = &mut self.layers[layer];
+        layer.tiles[(y * width + x) as usize] = tile;
+        layer.chunks[chunk_index].dirty = true;
+    }
+
+    /// Converts (fractional) tile coordinates into a world position. Whole coordinates give the
+    /// top left corner of a tile (its top corner on isometric maps).
+    pub fn tile_to_world(&self, tile: &glm::Vec2) -> glm::Vec2 {
+        let (tw, th) = (self.tile_size.x, self.tile_size.y);
+
+        let local = match self.orientation {
+            TilemapOrientation::Orthogonal => glm::vec2(tile.x * tw, -tile.y * th),
+            TilemapOrientation::Isometric => glm::vec2(
+                (self.height as f32 + tile.x - tile.y) * tw / 2.0,
+                -(tile.x + tile.y) * th / 2.0,
+            ),
+        };
+
+        self.position + local
+    }
+
+    /// Converts a world position into the coordinates of the tile under it, if any.
+    pub fn world_to_tile(&self, world: &glm::Vec2) -> Option<(u32, u32)> {
+        let local = world - self.position;
+        let (tw, th) = (self.tile_size.x, self.tile_size.y);
+
+        let (x, y) = match self.orientation {
+            TilemapOrientation::Orthogonal => (local.x / tw, -local.y / th),
+            TilemapOrientation::Isometric => {
+                let x_minus_y = local.x / (tw / 2.0) - self.height as f32;
+                let x_plus_y = -local.y / (th / 2.0);
+                ((x_plus_y + x_minus_y) / 2.0, (x_plus_y - x_minus_y) / 2.0)
+            }
+        };
+
+        let (x, y) = (x.floor(), y.floor());
+
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            None
+        } else {
+            Some((x as u32, y as u32))
+        }
+    }
+
+    /// Advances tile animations. Should be called on the application's loop `update()` function.
+    pub fn update(&mut self, ctx: &mut EngineContext) {
+        self.time += crate::chrono::delta_time(ctx);
+    }
+
+    /// Draws every visible layer, bottom to top, through `camera`.
+    pub fn render(
+        &mut self,
+        ctx: &mut EngineContext,
+        target: &mut RenderTarget,
+        camera: &Camera2d,
+    ) {
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let visible_chunks = self.visible_chunks(camera);
+
+        // Static geometry: rebuild the visible chunks that changed. Chunks outside the view
+        // are rebuilt once they come into view.
+        for layer_index in 0..self.layers.len() {
+            if !self.layers[layer_index].visible {
+                continue;
+            }
+            for &chunk_index in &visible_chunks {
+                if self.layers[layer_index].chunks[chunk_index].dirty {
+                    self.rebuild_chunk(wgpu_state, layer_index, chunk_index);
+                }
+            }
+        }
+
+        // Animated geometry, for every layer at once.
+        let mut anim_vertices: Vec<ColoredVertex2d> = vec![];
+        let mut anim_indices: Vec<u32> = vec![];
+        let mut anim_ranges: Vec<DrawRanges> = vec![];
+
+        for layer in &self.layers {
+            let mut ranges = vec![];
+            if layer.visible {
+                for &chunk_index in &visible_chunks {
+                    let (xs, ys) = self.chunk_tiles(chunk_index);
+                    for y in ys {
+                        for x in xs.clone() {
+                            let tile = match layer.tiles[(y * self.width + x) as usize] {
+                                Some(t) if self.tilesets[t.tileset].is_animated(t.id) => t,
+                                _ => continue,
+                            };
+
+                            let shown_tile = Tile {
+                                id: self.tilesets[tile.tileset]
+                                    .animated_tile_id(tile.id, self.time),
+                                ..tile
+                            };
+
+                            let start = anim_indices.len() as u32;
+                            self.push_tile_quad(
+                                &mut anim_vertices,
+                                &mut anim_indices,
+                                x,
+                                y,
+                                shown_tile,
+                                layer.tint,
+                            );
+                            push_range(&mut ranges, tile.tileset, start, anim_indices.len() as u32);
+                        }
+                    }
+                }
+            }
+            anim_ranges.push(ranges);
+        }
+
+        let instance_data = [Instance {
+            position: self.position,
+            scale: 1.0,
+        }
+        .to_data()];
+
+        let instance_buffer = wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("tilemap instance buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let anim_buffers = if anim_indices.is_empty() {
+            None
+        } else {
+            Some(create_buffers(
+                wgpu_state,
+                "tilemap animated tiles",
+                &anim_vertices,
+                &anim_indices,
+            ))
+        };
+
+        let mut render_pass = wgpu_state.make_render_pass(target);
+
+        render_pass
+            .set_pipeline(self.pipeline_key, wgpu_state)
+            .unwrap();
+
+        render_pass
+            .set_bind_group(1, camera.camera_bind_group_key, &[], wgpu_state)
+            .unwrap();
+
+        render_pass
+            .wgpu_render_pass
+            .set_vertex_buffer(1, instance_buffer.slice(..));
+
+        for (layer, layer_anim_ranges) in self.layers.iter().zip(&anim_ranges) {
+            if !layer.visible {
+                continue;
+            }
+
+            let chunk_draws = visible_chunks.iter().filter_map(|&chunk_index| {
+                let chunk = &layer.chunks[chunk_index];
+                chunk
+                    .buffers
+                    .as_ref()
+                    .map(|buffers| (buffers, &chunk.ranges))
+            });
+
+            let anim_draw = anim_buffers
+                .as_ref()
+                .filter(|_| !layer_anim_ranges.is_empty())
+                .map(|buffers| (buffers, layer_anim_ranges));
+
+            for ((vertex_buffer, index_buffer), ranges) in chunk_draws.chain(anim_draw) {
+                render_pass
+                    .wgpu_render_pass
+                    .set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass
+                    .wgpu_render_pass
+                    .set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+                for (tileset, indices) in ranges {
+                    render_pass
+                        .set_bind_group(0, self.tilesets[*tileset].bind_group_key, &[], wgpu_state)
+                        .unwrap();
+                    render_pass.draw_indexed(indices.clone(), 0, 0..1);
+                }
+            }
+        }
+    }
+
+    fn chunks_x(&self) -> u32 {
+        self.width.div_ceil(CHUNK_SIZE)
+    }
+
+    fn chunks_y(&self) -> u32 {
+        self.height.div_ceil(CHUNK_SIZE)
+    }
+
+    fn mark_all_dirty(&mut self) {
+        for layer in &mut self.layers {
+            for chunk in &mut layer.chunks {
+                chunk.dirty = true;
+            }
+        }
+    }
+
+    /// Ranges of tile x and y coordinates inside a chunk.
+    fn chunk_tiles(&self, chunk_index: usize) -> (Range<u32>, Range<u32>) {
+        let cx = chunk_index as u32 % self.chunks_x();
+        let cy = chunk_index as u32 / self.chunks_x();
+
+        (
+            (cx * CHUNK_SIZE)..((cx + 1) * CHUNK_SIZE).min(self.width),
+            (cy * CHUNK_SIZE)..((cy + 1) * CHUNK_SIZE).min(self.height),
+        )
+    }
+
+    /// Indices of the chunks whose bounding box overlaps the camera's view, in drawing order.
+    fn visible_chunks(&self, camera: &Camera2d) -> Vec<usize> {
+        let (view_min, view_max) = camera.visible_world_rect();
+
+        // Tiles bigger than their cell stick out of the chunk's grid bounds.
+        let overflow = self
+            .tilesets
+            .iter()
+            .filter_map(Tileset::world_size)
+            .fold(glm::vec2(0.0, 0.0), |acc, size| {
+                glm::max2(&acc, &(size - self.tile_size))
+            });
+
+        (0..(self.chunks_x() * self.chunks_y()) as usize)
+            .filter(|&chunk_index| {
+                let (xs, ys) = self.chunk_tiles(chunk_index);
+
+                let corners = [
+                    glm::vec2(xs.start as f32, ys.start as f32),
+                    glm::vec2(xs.end as f32, ys.start as f32),
+                    glm::vec2(xs.start as f32, ys.end as f32),
+                    glm::vec2(xs.end as f32, ys.end as f32),
+                ];
+
+                let mut min = glm::vec2(f32::MAX, f32::MAX);
+                let mut max = glm::vec2(f32::MIN, f32::MIN);
+                for corner in &corners {
+                    let world = self.tile_to_world(corner);
+                    min = glm::min2(&min, &world);
+                    max = glm::max2(&max, &world);
+                }
+
+                min.x -= overflow.x;
+                max.x += overflow.x;
+                max.y += overflow.y;
+
+                min.x <= view_max.x
+                    && max.x >= view_min.x
+                    && min.y <= view_max.y
+                    && max.y >= view_min.y
+            })
+            .collect()
+    }
+
+    fn rebuild_chunk(
+        &mut self,
+        wgpu_state: &mut WgpuState,
+        layer_index: usize,
+        chunk_index: usize,
+    ) {
+        let mut vertices: Vec<ColoredVertex2d> = vec![];
+        let mut indices: Vec<u32> = vec![];
+        let mut ranges = vec![];
+
+        let layer = &self.layers[layer_index];
+        let (xs, ys) = self.chunk_tiles(chunk_index);
+
+        for y in ys {
+            for x in xs.clone() {
+                let tile = match layer.tiles[(y * self.width + x) as usize] {
+                    Some(t) => t,
+                    None => continue,
+                };
+
+                // Animated tiles are drawn separately, see `render()`.
+                if self.tilesets[tile.tileset].is_animated(tile.id) {
+                    continue;
+                }
+
+                let start = indices.len() as u32;
+                self.push_tile_quad(&mut vertices, &mut indices, x, y, tile, layer.tint);
+                push_range(&mut ranges, tile.tileset, start, indices.len() as u32);
+            }
+        }
+
+        let buffers = if indices.is_empty() {
+            None
+        } else {
+            Some(create_buffers(
+                wgpu_state,
+                "tilemap chunk",
+                &vertices,
+                &indices,
+            ))
+        };
+
+        let chunk = &mut self.layers[layer_index].chunks[chunk_index];
+        chunk.buffers = buffers;
+        chunk.ranges = ranges;
+        chunk.dirty = false;
+    }
+
+    /// Pushes the quad for the tile at (`x`, `y`), in map local coordinates (the top left corner
+    /// of the map's bounding box being the origin, and y positive upwards).
+    fn push_tile_quad(
+        &self,
+        vertices: &mut Vec<ColoredVertex2d>,
+        indices: &mut Vec<u32>,
+        x: u32,
+        y: u32,
+        tile: Tile,
+        color: [f32; 4],
+    ) {
+        let tileset = &self.tilesets[tile.tileset];
+        if tile.id >= tileset.tile_count() {
+            return;
+        }
+
+        let (uv_min, uv_max) = tileset.uv_rect(tile.id);
+        let size = tileset.world_size().unwrap_or(self.tile_size);
+
+        // Top left (top, when isometric) corner of the cell.
+        let corner = self.tile_to_world(&glm::vec2(x as f32, y as f32)) - self.position;
+
+        let left = match self.orientation {
+            TilemapOrientation::Orthogonal => corner.x,
+            TilemapOrientation::Isometric => corner.x - size.x / 2.0,
+        };
+        let bottom = corner.y - self.tile_size.y;
+        let (right, top) = (left + size.x, bottom + size.y);
+
+        // Corners in counter clockwise order: bottom left, bottom right, top right, top left,
+        // as (x, y) in tile space, with y going down.
+        let corners = [(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)];
+
+        let base = vertices.len() as u32;
+
+        for &(cx, cy) in &corners {
+            // Texture coordinates of this corner, undoing the flips (see `Tile`).
+            let (mut u, mut v) = (cx, cy);
+            if tile.flip_vertical {
+                v = 1.0 - v;
+            }
+            if tile.flip_horizontal {
+                u = 1.0 - u;
+            }
+            if tile.flip_diagonal {
+                std::mem::swap(&mut u, &mut v);
+            }
+
+            vertices.push(ColoredVertex2d {
+                position: [
+                    if cx == 0.0 { left } else { right },
+                    if cy == 0.0 { top } else { bottom },
+                ],
+                tex_coords: [
+                    uv_min[0] + (uv_max[0] - uv_min[0]) * u,
+                    uv_min[1] + (uv_max[1] - uv_min[1]) * v,
+                ],
+                color,
+            });
+        }
+
+        indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+/// Adds `start..end` to `ranges`, merging it with the last range when possible, so that
+/// consecutive tiles of the same tileset are drawn at once.
+fn push_range(ranges: &mut DrawRanges, tileset: usize, start: u32, end: u32) {
+    if start == end {
+        return;
+    }
+
+    if let Some((last_tileset, last)) = ranges.last_mut() {
+        if *last_tileset == tileset && last.end == start {
+            last.end = end;
+            return;
+        }
+    }
+
+    ranges.push((tileset, start..end));
+}
+
+fn create_buffers(
+    wgpu_state: &mut WgpuState,
+    label: &str,
+    vertices: &[ColoredVertex2d],
+    indices: &[u32],
+) -> (wgpu::Buffer, wgpu::Buffer) {
+    (
+        wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} vertex buffer", label)),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        }),
+        wgpu_state.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} index buffer", label)),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        }),
+    )
+}
@@ -0,0 +1,454 @@
+//! Importer for maps made with the Tiled editor (https://www.mapeditor.org).
+//!
+//! Both of Tiled's formats are supported: XML (`.tmx` maps and `.tsx` tilesets) and JSON (`.tmj`
+//! or `.json` maps and `.tsj` tilesets). Maps may be orthogonal or isometric, and must not be
+//! infinite. Tile layers go into a `Tilemap`, object layers into `ObjectLayer`s. Group layers are
+//! flattened, passing their visibility, opacity and tint on to their children. Image layers are
+//! skipped.
+//!
+//! Tiled's pixels become world units, so the map's tile size, in pixels, is its `Tilemap`'s tile
+//! size. External tilesets and tileset images are looked for relative to the file referencing
+//! them.
+
+mod tmj;
+mod tmx;
+
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result as AResult;
+use anyhow::{anyhow, Context};
+use nalgebra_glm as glm;
+
+use super::{Tile, TileAnimationFrame, Tilemap, TilemapOrientation, Tileset, TilesetLayout};
+use crate::EngineContext;
+
+/// Value of a custom property.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    /// rgba, from 0.0 to 1.0.
+    Color([f32; 4]),
+    /// Already resolved relative to the map (or tileset) file.
+    File(PathBuf),
+    /// Id of an object of the map, 0 meaning none.
+    Object(u32),
+    /// Custom class, made of its members' values.
+    Class(Properties),
+}
+
+pub type Properties = HashMap<String, PropertyValue>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    /// Points, relative to the object's position, in world units.
+    Polygon(Vec<glm::Vec2>),
+    Polyline(Vec<glm::Vec2>),
+    Text(String),
+    /// Tile object, drawn from the tilemap's tilesets.
+    Tile(Tile),
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledObject {
+    /// Unique in the map.
+    pub id: u32,
+    pub name: String,
+    /// Called "type" before Tiled 1.9.
+    pub class: String,
+    /// World position of the object's origin: its top left corner, or its bottom left one for
+    /// tile objects, like in Tiled.
+    pub position: glm::Vec2,
+    /// In world units (in Tiled's pixels, on isometric maps).
+    pub size: glm::Vec2,
+    /// In radians, counter clockwise, around `position`.
+    pub rotation: f32,
+    pub visible: bool,
+    pub shape: ObjectShape,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Debug)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub objects: Vec<TiledObject>,
+    pub properties: Properties,
+}
+
+/// A loaded Tiled map.
+pub struct TiledMap {
+    /// Holds every tile layer of the map, in order, and every tileset.
+    pub tilemap: Tilemap,
+    /// Custom properties of each tile layer, indexed like the tilemap's layers.
+    pub tile_layer_properties: Vec<Properties>,
+    pub object_layers: Vec<ObjectLayer>,
+    pub properties: Properties,
+}
+
+impl TiledMap {
+    /// Loads a `.tmx`, `.tmj` or `.json` map, along with its tilesets and their images.
+    pub fn load_from_path(ctx: &mut EngineContext, map_path: PathBuf) -> AResult<Self> {
+        let source = crate::assets::read_file_at_path_to_string(map_path.clone())
+            .map_err(|e| anyhow!("Cannot read Tiled map {:?}: {}", map_path, e))?;
+
+        let folder = map_path.parent().map(PathBuf::from).unwrap_or_default();
+
+        let raw_map = match extension(&map_path).as_str() {
+            "tmx" => tmx::parse_map(&source, &folder),
+            "tmj" | "json" => tmj::parse_map(&source, &folder),
+            other => Err(anyhow!("Unknown Tiled map extension \"{}\"", other)),
+        }
+        .with_context(|| format!("Invalid Tiled map {:?}", map_path))?;
+
+        build_map(ctx, raw_map).with_context(|| format!("Cannot load Tiled map {:?}", map_path))
+    }
+
+    /// First object layer called `name`.
+    pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.object_layers.iter().find(|l| l.name == name)
+    }
+}
+
+/// Map as described in the file, before any of its resources are loaded.
+pub(super) struct RawMap {
+    orientation: TilemapOrientation,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<RawTileset>,
+    layers: Vec<RawLayer>,
+    properties: Properties,
+}
+
+pub(super) struct RawTileset {
+    first_gid: u32,
+    image: PathBuf,
+    layout: TilesetLayout,
+    animations: Vec<(u32, Vec<TileAnimationFrame>)>,
+}
+
+pub(super) enum RawLayer {
+    Tiles {
+        name: String,
+        inherited: Inherited,
+        /// Row by row, already decoded.
+        tiles: Vec<Option<Tile>>,
+        properties: Properties,
+    },
+    Objects {
+        name: String,
+        inherited: Inherited,
+        /// Positions and sizes still are in Tiled's pixels.
+        objects: Vec<TiledObject>,
+        properties: Properties,
+    },
+}
+
+/// Attributes of a layer, combined with those of the groups it is in.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Inherited {
+    visible: bool,
+    opacity: f32,
+    tint: [f32; 4],
+}
+
+impl Inherited {
+    pub(super) fn root() -> Self {
+        Self {
+            visible: true,
+            opacity: 1.0,
+            tint: [1.0; 4],
+        }
+    }
+
+    pub(super) fn child(&self, visible: bool, opacity: f32, tint: [f32; 4]) -> Self {
+        let mut combined = *self;
+        combined.visible &= visible;
+        combined.opacity *= opacity;
+        for (c, t) in combined.tint.iter_mut().zip(&tint) {
+            *c *= t;
+        }
+        combined
+    }
+}
+
+fn build_map(ctx: &mut EngineContext, raw_map: RawMap) -> AResult<TiledMap> {
+    let mut tilemap = Tilemap::new(
+        ctx,
+        raw_map.width,
+        raw_map.height,
+        glm::vec2(raw_map.tile_width as f32, raw_map.tile_height as f32),
+    );
+    tilemap.set_orientation(raw_map.orientation);
+
+    for raw_tileset in raw_map.tilesets {
+        let image = crate::assets::read_file_at_path_to_bytes(raw_tileset.image.clone())
+            .map_err(|e| anyhow!("Cannot read tileset image {:?}: {}", raw_tileset.image, e))?;
+
+        let mut tileset = Tileset::new(ctx, &image, raw_tileset.layout)
+            .with_context(|| format!("Invalid tileset image {:?}", raw_tileset.image))?;
+
+        let layout = &raw_tileset.layout;
+        if (layout.tile_width, layout.tile_height) != (raw_map.tile_width, raw_map.tile_height) {
+            tileset.set_world_size(Some(glm::vec2(
+                layout.tile_width as f32,
+                layout.tile_height as f32,
+            )));
+        }
+
+        for (tile_id, frames) in raw_tileset.animations {
+            tileset.set_animation(tile_id, frames);
+        }
+
+        tilemap.add_tileset(tileset);
+    }
+
+    let mut tile_layer_properties = vec![];
+    let mut object_layers = vec![];
+
+    for raw_layer in raw_map.layers {
+        match raw_layer {
+            RawLayer::Tiles {
+                name,
+                inherited,
+                tiles,
+                properties,
+            } => {
+                let layer = tilemap.add_layer(&name);
+                tilemap.set_layer_visible(layer, inherited.visible);
+
+                let mut tint = inherited.tint;
+                tint[3] *= inherited.opacity;
+                tilemap.set_layer_tint(layer, tint);
+
+                for (i, tile) in tiles.into_iter().enumerate() {
+                    if tile.is_some() {
+                        let i = i as u32;
+                        tilemap.set_tile(layer, i % raw_map.width, i / raw_map.width, tile);
+                    }
+                }
+
+                tile_layer_properties.push(properties);
+            }
+            RawLayer::Objects {
+                name,
+                inherited,
+                mut objects,
+                properties,
+            } => {
+                for object in &mut objects {
+                    pixels_to_world(&tilemap, object);
+                }
+
+                object_layers.push(ObjectLayer {
+                    name,
+                    visible: inherited.visible,
+                    opacity: inherited.opacity,
+                    objects,
+                    properties,
+                });
+            }
+        }
+    }
+
+    Ok(TiledMap {
+        tilemap,
+        tile_layer_properties,
+        object_layers,
+        properties: raw_map.properties,
+    })
+}
+
+/// Converts an object's position and points from Tiled's pixels to world coordinates.
+fn pixels_to_world(tilemap: &Tilemap, object: &mut TiledObject) {
+    let tile_size = tilemap.tile_size();
+
+    // On isometric maps, Tiled measures both axes in tile heights.
+    let pixel_to_tile = match tilemap.orientation() {
+        TilemapOrientation::Orthogonal => tile_size,
+        TilemapOrientation::Isometric => glm::vec2(tile_size.y, tile_size.y),
+    };
+
+    let to_world = |pixel: &glm::Vec2| {
+        tilemap.tile_to_world(&glm::vec2(
+            pixel.x / pixel_to_tile.x,
+            pixel.y / pixel_to_tile.y,
+        ))
+    };
+
+    let position = to_world(&object.position);
+
+    match &mut object.shape {
+        ObjectShape::Polygon(points) | ObjectShape::Polyline(points) => {
+            for point in points {
+                *point = to_world(&(object.position + *point)) - position;
+            }
+        }
+        _ => {}
+    }
+
+    object.position = position;
+    // Tiled rotates clockwise, in degrees.
+    object.rotation = -object.rotation.to_radians();
+}
+
+pub(super) fn parse_orientation(orientation: &str) -> AResult<TilemapOrientation> {
+    match orientation {
+        "orthogonal" => Ok(TilemapOrientation::Orthogonal),
+        "isometric" => Ok(TilemapOrientation::Isometric),
+        other => Err(anyhow!("Unsupported map orientation \"{}\"", other)),
+    }
+}
+
+/// Loads an external `.tsx`, `.tsj` or `.json` tileset.
+pub(super) fn load_external_tileset(path: &Path, first_gid: u32) -> AResult<RawTileset> {
+    let source = crate::assets::read_file_at_path_to_string(path.to_path_buf())
+        .map_err(|e| anyhow!("Cannot read tileset {:?}: {}", path, e))?;
+
+    let folder = path.parent().map(PathBuf::from).unwrap_or_default();
+
+    match extension(path).as_str() {
+        "tsx" => tmx::parse_external_tileset(&source, first_gid, &folder),
+        "tsj" | "json" => tmj::parse_external_tileset(&source, first_gid, &folder),
+        other => Err(anyhow!("Unknown Tiled tileset extension \"{}\"", other)),
+    }
+    .with_context(|| format!("Invalid tileset {:?}", path))
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+/// Only meaningful on hexagonal maps, which aren't supported.
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+
+/// Splits a global tile id into the tileset it belongs to (its index in `first_gids`), its id in
+/// that tileset, and its flip flags. 0 means no tile.
+pub(super) fn decode_gid(first_gids: &[u32], gid: u32) -> AResult<Option<Tile>> {
+    let id = gid
+        & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120);
+
+    if id == 0 {
+        return Ok(None);
+    }
+
+    let (tileset, first_gid) = first_gids
+        .iter()
+        .enumerate()
+        .filter(|(_, first_gid)| **first_gid <= id)
+        .max_by_key(|(_, first_gid)| **first_gid)
+        .ok_or_else(|| anyhow!("Tile {} doesn't belong to any tileset", id))?;
+
+    Ok(Some(Tile {
+        tileset,
+        id: id - first_gid,
+        flip_horizontal: gid & FLIPPED_HORIZONTALLY != 0,
+        flip_vertical: gid & FLIPPED_VERTICALLY != 0,
+        flip_diagonal: gid & FLIPPED_DIAGONALLY != 0,
+    }))
+}
+
+/// Decodes the global tile ids of a tile layer's data, given as text.
+pub(super) fn decode_tile_data(
+    data: &str,
+    encoding: &str,
+    compression: Option<&str>,
+) -> AResult<Vec<u32>> {
+    match encoding {
+        "csv" => data
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse::<u32>()
+                    .map_err(|_| anyhow!("Invalid tile id \"{}\"", gid))
+            })
+            .collect(),
+        "base64" => {
+            let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = base64::decode(&data)?;
+
+            let bytes = match compression.unwrap_or_default() {
+                "" => bytes,
+                "zlib" => {
+                    let mut decompressed = vec![];
+                    flate2::read::ZlibDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+                    decompressed
+                }
+                "gzip" => {
+                    let mut decompressed = vec![];
+                    flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+                    decompressed
+                }
+                other => return Err(anyhow!("Unsupported tile data compression \"{}\"", other)),
+            };
+
+            if bytes.len() % 4 != 0 {
+                return Err(anyhow!("Tile data isn't made of 32 bit ids"));
+            }
+
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        other => Err(anyhow!("Unsupported tile data encoding \"{}\"", other)),
+    }
+}
+
+/// Decodes the ids of a tile layer, checking that there is one per cell.
+pub(super) fn decode_tiles(
+    gids: &[u32],
+    first_gids: &[u32],
+    width: u32,
+    height: u32,
+) -> AResult<Vec<Option<Tile>>> {
+    if gids.len() != (width * height) as usize {
+        return Err(anyhow!(
+            "Tile layer has {} tiles instead of {}x{}",
+            gids.len(),
+            width,
+            height
+        ));
+    }
+
+    gids.iter()
+        .map(|gid| decode_gid(first_gids, *gid))
+        .collect()
+}
+
+/// Parses colors such as `#ff8000` or `#80ff8000` (with alpha first) into rgba.
+pub(super) fn parse_color(color: &str) -> AResult<[f32; 4]> {
+    let hex = color.trim_start_matches('#');
+
+    let channel = |i: usize| -> AResult<f32> {
+        hex.get(i..(i + 2))
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .map(|c| c as f32 / 255.0)
+            .ok_or_else(|| anyhow!("Invalid color \"{}\"", color))
+    };
+
+    match hex.len() {
+        6 => Ok([channel(0)?, channel(2)?, channel(4)?, 1.0]),
+        8 => Ok([channel(2)?, channel(4)?, channel(6)?, channel(0)?]),
+        _ => Err(anyhow!("Invalid color \"{}\"", color)),
+    }
+}
@@ -0,0 +1,423 @@
+//! Parser for Tiled's JSON formats: `.tmj` maps and `.tsj` tilesets.
+//!
+//! See https://doc.mapeditor.org/en/stable/reference/json-map-format/
+
+use std::path::Path;
+
+use anyhow::Result as AResult;
+use anyhow::{anyhow, Context};
+use nalgebra_glm as glm;
+use serde_json::Value;
+
+use super::{
+    Inherited, ObjectShape, Properties, PropertyValue, RawLayer, RawMap, RawTileset, TiledObject,
+};
+use crate::rendering::rend_2d::tilemap::{TileAnimationFrame, TilesetLayout};
+
+pub(super) fn parse_map(source: &str, folder: &Path) -> AResult<RawMap> {
+    let map: Value = serde_json::from_str(source)?;
+
+    if bool_or(&map, "infinite", false)? {
+        return Err(anyhow!("Infinite maps aren't supported"));
+    }
+
+    let tilesets = array_or_empty(&map, "tilesets")?
+        .iter()
+        .map(|tileset| {
+            let first_gid = num(tileset, "firstgid")?;
+            match tileset.get("source") {
+                Some(source) => {
+                    let source = as_str(source, "source")?;
+                    super::load_external_tileset(&folder.join(source), first_gid)
+                }
+                None => parse_tileset(tileset, first_gid, folder),
+            }
+        })
+        .collect::<AResult<Vec<_>>>()?;
+
+    let mut raw_map = RawMap {
+        orientation: super::parse_orientation(string(&map, "orientation")?)?,
+        width: num(&map, "width")?,
+        height: num(&map, "height")?,
+        tile_width: num(&map, "tilewidth")?,
+        tile_height: num(&map, "tileheight")?,
+        tilesets,
+        layers: vec![],
+        properties: parse_properties(&map, folder)?,
+    };
+
+    let first_gids: Vec<u32> = raw_map.tilesets.iter().map(|t| t.first_gid).collect();
+    let mut layers = vec![];
+    parse_layers(
+        &map,
+        &raw_map,
+        &first_gids,
+        Inherited::root(),
+        folder,
+        &mut layers,
+    )?;
+    raw_map.layers = layers;
+
+    Ok(raw_map)
+}
+
+pub(super) fn parse_external_tileset(
+    source: &str,
+    first_gid: u32,
+    folder: &Path,
+) -> AResult<RawTileset> {
+    let tileset: Value = serde_json::from_str(source)?;
+    parse_tileset(&tileset, first_gid, folder)
+}
+
+fn parse_tileset(tileset: &Value, first_gid: u32, folder: &Path) -> AResult<RawTileset> {
+    let name = string_or(tileset, "name", "")?;
+
+    let result: AResult<RawTileset> = (|| {
+        let image = match tileset.get("image") {
+            Some(image) => as_str(image, "image")?,
+            None => return Err(anyhow!("Image collection tilesets aren't supported")),
+        };
+
+        let mut animations = vec![];
+        for tile in array_or_empty(tileset, "tiles")? {
+            let frames = array_or_empty(tile, "animation")?
+                .iter()
+                .map(|frame| {
+                    Ok(TileAnimationFrame {
+                        tile_id: num(frame, "tileid")?,
+                        duration: float(frame, "duration")? / 1000.0,
+                    })
+                })
+                .collect::<AResult<Vec<_>>>()?;
+
+            if !frames.is_empty() {
+                animations.push((num(tile, "id")?, frames));
+            }
+        }
+
+        Ok(RawTileset {
+            first_gid,
+            image: folder.join(image),
+            layout: TilesetLayout {
+                tile_width: num(tileset, "tilewidth")?,
+                tile_height: num(tileset, "tileheight")?,
+                spacing: num_or(tileset, "spacing", 0)?,
+                margin: num_or(tileset, "margin", 0)?,
+            },
+            animations,
+        })
+    })();
+
+    result.with_context(|| format!("Invalid tileset \"{}\"", name))
+}
+
+/// Parses the layers of `parent` (the map or a group), flattening groups.
+fn parse_layers(
+    parent: &Value,
+    raw_map: &RawMap,
+    first_gids: &[u32],
+    inherited: Inherited,
+    folder: &Path,
+    layers: &mut Vec<RawLayer>,
+) -> AResult<()> {
+    for layer in array_or_empty(parent, "layers")? {
+        let name = string_or(layer, "name", "")?.to_string();
+
+        let result: AResult<()> = (|| {
+            let tint = match layer.get("tintcolor") {
+                Some(tint) => super::parse_color(as_str(tint, "tintcolor")?)?,
+                None => [1.0; 4],
+            };
+
+            let inherited = inherited.child(
+                bool_or(layer, "visible", true)?,
+                float_or(layer, "opacity", 1.0)?,
+                tint,
+            );
+
+            match string(layer, "type")? {
+                "tilelayer" => {
+                    let data = field(layer, "data")?;
+
+                    let gids = match data {
+                        Value::Array(gids) => gids
+                            .iter()
+                            .map(|gid| {
+                                gid.as_u64()
+                                    .filter(|gid| *gid <= u32::MAX as u64)
+                                    .map(|gid| gid as u32)
+                                    .ok_or_else(|| anyhow!("Invalid tile id {}", gid))
+                            })
+                            .collect::<AResult<Vec<_>>>()?,
+                        Value::String(data) => super::decode_tile_data(
+                            data,
+                            string_or(layer, "encoding", "csv")?,
+                            layer
+                                .get("compression")
+                                .map(|c| as_str(c, "compression"))
+                                .transpose()?,
+                        )?,
+                        _ => return Err(anyhow!("Invalid tile data")),
+                    };
+
+                    layers.push(RawLayer::Tiles {
+                        name: name.clone(),
+                        inherited,
+                        tiles: super::decode_tiles(
+                            &gids,
+                            first_gids,
+                            raw_map.width,
+                            raw_map.height,
+                        )?,
+                        properties: parse_properties(layer, folder)?,
+                    });
+                }
+                "objectgroup" => {
+                    layers.push(RawLayer::Objects {
+                        name: name.clone(),
+                        inherited,
+                        objects: array_or_empty(layer, "objects")?
+                            .iter()
+                            .map(|object| parse_object(object, first_gids, folder))
+                            .collect::<AResult<Vec<_>>>()?,
+                        properties: parse_properties(layer, folder)?,
+                    });
+                }
+                "group" => {
+                    parse_layers(layer, raw_map, first_gids, inherited, folder, layers)?;
+                }
+                "imagelayer" => {
+                    log::warn!(
+                        "Skipping image layer \"{}\": image layers aren't supported",
+                        name
+                    );
+                }
+                other => return Err(anyhow!("Unknown layer type \"{}\"", other)),
+            }
+
+            Ok(())
+        })();
+
+        result.with_context(|| format!("Invalid layer \"{}\"", name))?;
+    }
+
+    Ok(())
+}
+
+fn parse_object(object: &Value, first_gids: &[u32], folder: &Path) -> AResult<TiledObject> {
+    let id = num_or(object, "id", 0)?;
+
+    let result: AResult<TiledObject> = (|| {
+        if object.get("template").is_some() {
+            log::warn!(
+                "Object {} uses a template, which isn't supported: only its own attributes are loaded",
+                id
+            );
+        }
+
+        let shape = if let Some(gid) = object.get("gid") {
+            let gid = gid
+                .as_u64()
+                .filter(|gid| *gid <= u32::MAX as u64)
+                .ok_or_else(|| anyhow!("Invalid gid {}", gid))?;
+            match super::decode_gid(first_gids, gid as u32)? {
+                Some(tile) => ObjectShape::Tile(tile),
+                None => ObjectShape::Rectangle,
+            }
+        } else if bool_or(object, "ellipse", false)? {
+            ObjectShape::Ellipse
+        } else if bool_or(object, "point", false)? {
+            ObjectShape::Point
+        } else if object.get("polygon").is_some() {
+            ObjectShape::Polygon(parse_points(object, "polygon")?)
+        } else if object.get("polyline").is_some() {
+            ObjectShape::Polyline(parse_points(object, "polyline")?)
+        } else if let Some(text) = object.get("text") {
+            ObjectShape::Text(string_or(text, "text", "")?.to_string())
+        } else {
+            ObjectShape::Rectangle
+        };
+
+        Ok(TiledObject {
+            id,
+            name: string_or(object, "name", "")?.to_string(),
+            class: match object.get("class") {
+                Some(_) => string(object, "class")?,
+                None => string_or(object, "type", "")?,
+            }
+            .to_string(),
+            position: glm::vec2(float_or(object, "x", 0.0)?, float_or(object, "y", 0.0)?),
+            size: glm::vec2(
+                float_or(object, "width", 0.0)?,
+                float_or(object, "height", 0.0)?,
+            ),
+            rotation: float_or(object, "rotation", 0.0)?,
+            visible: bool_or(object, "visible", true)?,
+            shape,
+            properties: parse_properties(object, folder)?,
+        })
+    })();
+
+    result.with_context(|| format!("Invalid object {}", id))
+}
+
+/// Parses arrays of points such as `[{"x": 0, "y": 0}, {"x": 32.5, "y": 16}]`.
+fn parse_points(object: &Value, key: &str) -> AResult<Vec<glm::Vec2>> {
+    array_or_empty(object, key)?
+        .iter()
+        .map(|point| Ok(glm::vec2(float(point, "x")?, float(point, "y")?)))
+        .collect()
+}
+
+/// Parses the `properties` array of `value`, if any.
+fn parse_properties(value: &Value, folder: &Path) -> AResult<Properties> {
+    let mut properties = Properties::new();
+
+    for property in array_or_empty(value, "properties")? {
+        let name = string(property, "name")?;
+        let value = field(property, "value")?;
+
+        let value = match string_or(property, "type", "string")? {
+            "class" => match value {
+                Value::Object(members) => PropertyValue::Class(
+                    members
+                        .iter()
+                        .map(|(member, value)| (member.clone(), untyped_property(value)))
+                        .collect(),
+                ),
+                _ => return Err(anyhow!("Property \"{}\" isn't a class", name)),
+            },
+            property_type => typed_property(name, property_type, value, folder)?,
+        };
+
+        properties.insert(name.to_string(), value);
+    }
+
+    Ok(properties)
+}
+
+fn typed_property(
+    name: &str,
+    property_type: &str,
+    value: &Value,
+    folder: &Path,
+) -> AResult<PropertyValue> {
+    let invalid = || anyhow!("Property \"{}\" has an invalid value {}", name, value);
+
+    Ok(match property_type {
+        "string" => PropertyValue::String(value.as_str().ok_or_else(invalid)?.to_string()),
+        "bool" => PropertyValue::Bool(value.as_bool().ok_or_else(invalid)?),
+        "int" => PropertyValue::Int(value.as_i64().ok_or_else(invalid)?),
+        "float" => PropertyValue::Float(value.as_f64().ok_or_else(invalid)?),
+        "color" => match value.as_str().ok_or_else(invalid)? {
+            "" => PropertyValue::Color([0.0; 4]),
+            color => PropertyValue::Color(super::parse_color(color)?),
+        },
+        "file" => PropertyValue::File(folder.join(value.as_str().ok_or_else(invalid)?)),
+        "object" => PropertyValue::Object(
+            value
+                .as_u64()
+                .filter(|id| *id <= u32::MAX as u64)
+                .ok_or_else(invalid)? as u32,
+        ),
+        other => {
+            return Err(anyhow!(
+                "Property \"{}\" has unknown type \"{}\"",
+                name,
+                other
+            ))
+        }
+    })
+}
+
+/// Class members don't come with their types, so they are guessed from the JSON values. Colors,
+/// files and objects end up as strings and ints.
+fn untyped_property(value: &Value) -> PropertyValue {
+    match value {
+        Value::Bool(b) => PropertyValue::Bool(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => PropertyValue::Int(i),
+            None => PropertyValue::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::Object(members) => PropertyValue::Class(
+            members
+                .iter()
+                .map(|(member, value)| (member.clone(), untyped_property(value)))
+                .collect(),
+        ),
+        Value::String(s) => PropertyValue::String(s.clone()),
+        other => PropertyValue::String(other.to_string()),
+    }
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> AResult<&'a Value> {
+    value
+        .get(key)
+        .ok_or_else(|| anyhow!("Missing field \"{}\"", key))
+}
+
+fn as_str<'a>(value: &'a Value, key: &str) -> AResult<&'a str> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow!("Field \"{}\" is not a string: {}", key, value))
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> AResult<&'a str> {
+    as_str(field(value, key)?, key)
+}
+
+fn string_or<'a>(value: &'a Value, key: &str, default: &'a str) -> AResult<&'a str> {
+    match value.get(key) {
+        Some(v) => as_str(v, key),
+        None => Ok(default),
+    }
+}
+
+fn num(value: &Value, key: &str) -> AResult<u32> {
+    let v = field(value, key)?;
+    v.as_u64()
+        .filter(|n| *n <= u32::MAX as u64)
+        .map(|n| n as u32)
+        .ok_or_else(|| anyhow!("Field \"{}\" is not a positive integer: {}", key, v))
+}
+
+fn num_or(value: &Value, key: &str, default: u32) -> AResult<u32> {
+    match value.get(key) {
+        Some(_) => num(value, key),
+        None => Ok(default),
+    }
+}
+
+fn float(value: &Value, key: &str) -> AResult<f32> {
+    let v = field(value, key)?;
+    v.as_f64()
+        .map(|n| n as f32)
+        .ok_or_else(|| anyhow!("Field \"{}\" is not a number: {}", key, v))
+}
+
+fn float_or(value: &Value, key: &str, default: f32) -> AResult<f32> {
+    match value.get(key) {
+        Some(_) => float(value, key),
+        None => Ok(default),
+    }
+}
+
+fn bool_or(value: &Value, key: &str, default: bool) -> AResult<bool> {
+    match value.get(key) {
+        Some(v) => v
+            .as_bool()
+            .ok_or_else(|| anyhow!("Field \"{}\" is not a boolean: {}", key, v)),
+        None => Ok(default),
+    }
+}
+
+fn array_or_empty<'a>(value: &'a Value, key: &str) -> AResult<&'a [Value]> {
+    match value.get(key) {
+        Some(v) => v
+            .as_array()
+            .map(Vec::as_slice)
+            .ok_or_else(|| anyhow!("Field \"{}\" is not an array: {}", key, v)),
+        None => Ok(&[]),
+    }
+}
@@ -0,0 +1,354 @@
+//! Parser for Tiled's XML formats: `.tmx` maps and `.tsx` tilesets.
+//!
+//! See https://doc.mapeditor.org/en/stable/reference/tmx-map-format/
+
+use std::{path::Path, str::FromStr};
+
+use anyhow::Result as AResult;
+use anyhow::{anyhow, Context};
+use nalgebra_glm as glm;
+use roxmltree::Node;
+
+use super::{
+    Inherited, ObjectShape, Properties, PropertyValue, RawLayer, RawMap, RawTileset, TiledObject,
+};
+use crate::rendering::rend_2d::tilemap::{TileAnimationFrame, TilesetLayout};
+
+pub(super) fn parse_map(source: &str, folder: &Path) -> AResult<RawMap> {
+    let document = roxmltree::Document::parse(source)?;
+    let map = document.root_element();
+
+    if !map.has_tag_name("map") {
+        return Err(anyhow!("Root element isn't a \"map\""));
+    }
+
+    if num_or::<u32>(&map, "infinite", 0)? != 0 {
+        return Err(anyhow!("Infinite maps aren't supported"));
+    }
+
+    let tilesets = children(&map, "tileset")
+        .map(|tileset| {
+            let first_gid = num(&tileset, "firstgid")?;
+            match tileset.attribute("source") {
+                Some(source) => super::load_external_tileset(&folder.join(source), first_gid),
+                None => parse_tileset(&tileset, first_gid, folder),
+            }
+        })
+        .collect::<AResult<Vec<_>>>()?;
+
+    let mut raw_map = RawMap {
+        orientation: super::parse_orientation(attr(&map, "orientation")?)?,
+        width: num(&map, "width")?,
+        height: num(&map, "height")?,
+        tile_width: num(&map, "tilewidth")?,
+        tile_height: num(&map, "tileheight")?,
+        tilesets,
+        layers: vec![],
+        properties: parse_properties(&map, folder)?,
+    };
+
+    let first_gids: Vec<u32> = raw_map.tilesets.iter().map(|t| t.first_gid).collect();
+    let mut layers = vec![];
+    parse_layers(
+        &map,
+        &raw_map,
+        &first_gids,
+        Inherited::root(),
+        folder,
+        &mut layers,
+    )?;
+    raw_map.layers = layers;
+
+    Ok(raw_map)
+}
+
+pub(super) fn parse_external_tileset(
+    source: &str,
+    first_gid: u32,
+    folder: &Path,
+) -> AResult<RawTileset> {
+    let document = roxmltree::Document::parse(source)?;
+    let tileset = document.root_element();
+
+    if !tileset.has_tag_name("tileset") {
+        return Err(anyhow!("Root element isn't a \"tileset\""));
+    }
+
+    parse_tileset(&tileset, first_gid, folder)
+}
+
+fn parse_tileset(tileset: &Node, first_gid: u32, folder: &Path) -> AResult<RawTileset> {
+    let name = tileset.attribute("name").unwrap_or_default();
+
+    let result: AResult<RawTileset> = (|| {
+        let image = children(tileset, "image")
+            .next()
+            .ok_or_else(|| anyhow!("Image collection tilesets aren't supported"))?;
+
+        let mut animations = vec![];
+        for tile in children(tileset, "tile") {
+            if let Some(animation) = children(&tile, "animation").next() {
+                let frames = children(&animation, "frame")
+                    .map(|frame| {
+                        Ok(TileAnimationFrame {
+                            tile_id: num(&frame, "tileid")?,
+                            duration: num::<f32>(&frame, "duration")? / 1000.0,
+                        })
+                    })
+                    .collect::<AResult<Vec<_>>>()?;
+
+                animations.push((num(&tile, "id")?, frames));
+            }
+        }
+
+        Ok(RawTileset {
+            first_gid,
+            image: folder.join(attr(&image, "source")?),
+            layout: TilesetLayout {
+                tile_width: num(tileset, "tilewidth")?,
+                tile_height: num(tileset, "tileheight")?,
+                spacing: num_or(tileset, "spacing", 0)?,
+                margin: num_or(tileset, "margin", 0)?,
+            },
+            animations,
+        })
+    })();
+
+    result.with_context(|| format!("Invalid tileset \"{}\"", name))
+}
+
+/// Parses the layers inside `parent` (the map or a group), flattening groups.
+fn parse_layers(
+    parent: &Node,
+    raw_map: &RawMap,
+    first_gids: &[u32],
+    inherited: Inherited,
+    folder: &Path,
+    layers: &mut Vec<RawLayer>,
+) -> AResult<()> {
+    for node in parent.children().filter(Node::is_element) {
+        let name = node.attribute("name").unwrap_or_default().to_string();
+
+        let result: AResult<()> = (|| {
+            let tint = match node.attribute("tintcolor") {
+                Some(tint) => super::parse_color(tint)?,
+                None => [1.0; 4],
+            };
+
+            let inherited = inherited.child(
+                num_or::<u32>(&node, "visible", 1)? != 0,
+                num_or(&node, "opacity", 1.0)?,
+                tint,
+            );
+
+            match node.tag_name().name() {
+                "layer" => {
+                    let data = children(&node, "data")
+                        .next()
+                        .ok_or_else(|| anyhow!("Missing \"data\" element"))?;
+
+                    let gids = match data.attribute("encoding") {
+                        Some(encoding) => super::decode_tile_data(
+                            data.text().unwrap_or_default(),
+                            encoding,
+                            data.attribute("compression"),
+                        )?,
+                        None => children(&data, "tile")
+                            .map(|tile| num_or(&tile, "gid", 0))
+                            .collect::<AResult<Vec<_>>>()?,
+                    };
+
+                    layers.push(RawLayer::Tiles {
+                        name: name.clone(),
+                        inherited,
+                        tiles: super::decode_tiles(
+                            &gids,
+                            first_gids,
+                            raw_map.width,
+                            raw_map.height,
+                        )?,
+                        properties: parse_properties(&node, folder)?,
+                    });
+                }
+                "objectgroup" => {
+                    layers.push(RawLayer::Objects {
+                        name: name.clone(),
+                        inherited,
+                        objects: children(&node, "object")
+                            .map(|object| parse_object(&object, first_gids, folder))
+                            .collect::<AResult<Vec<_>>>()?,
+                        properties: parse_properties(&node, folder)?,
+                    });
+                }
+                "group" => {
+                    parse_layers(&node, raw_map, first_gids, inherited, folder, layers)?;
+                }
+                "imagelayer" => {
+                    log::warn!(
+                        "Skipping image layer \"{}\": image layers aren't supported",
+                        name
+                    );
+                }
+                // Tilesets, properties...
+                _ => {}
+            }
+
+            Ok(())
+        })();
+
+        result.with_context(|| format!("Invalid layer \"{}\"", name))?;
+    }
+
+    Ok(())
+}
+
+fn parse_object(object: &Node, first_gids: &[u32], folder: &Path) -> AResult<TiledObject> {
+    let id = num_or(object, "id", 0)?;
+
+    let result: AResult<TiledObject> = (|| {
+        if object.attribute("template").is_some() {
+            log::warn!(
+                "Object {} uses a template, which isn't supported: only its own attributes are loaded",
+                id
+            );
+        }
+
+        let shape = if let Some(gid) = object.attribute("gid") {
+            let gid = gid
+                .parse::<u32>()
+                .map_err(|_| anyhow!("Invalid gid \"{}\"", gid))?;
+            match super::decode_gid(first_gids, gid)? {
+                Some(tile) => ObjectShape::Tile(tile),
+                None => ObjectShape::Rectangle,
+            }
+        } else if children(object, "ellipse").next().is_some() {
+            ObjectShape::Ellipse
+        } else if children(object, "point").next().is_some() {
+            ObjectShape::Point
+        } else if let Some(polygon) = children(object, "polygon").next() {
+            ObjectShape::Polygon(parse_points(attr(&polygon, "points")?)?)
+        } else if let Some(polyline) = children(object, "polyline").next() {
+            ObjectShape::Polyline(parse_points(attr(&polyline, "points")?)?)
+        } else if let Some(text) = children(object, "text").next() {
+            ObjectShape::Text(text.text().unwrap_or_default().to_string())
+        } else {
+            ObjectShape::Rectangle
+        };
+
+        Ok(TiledObject {
+            id,
+            name: object.attribute("name").unwrap_or_default().to_string(),
+            class: object
+                .attribute("class")
+                .or_else(|| object.attribute("type"))
+                .unwrap_or_default()
+                .to_string(),
+            position: glm::vec2(num_or(object, "x", 0.0)?, num_or(object, "y", 0.0)?),
+            size: glm::vec2(
+                num_or(object, "width", 0.0)?,
+                num_or(object, "height", 0.0)?,
+            ),
+            rotation: num_or(object, "rotation", 0.0)?,
+            visible: num_or::<u32>(object, "visible", 1)? != 0,
+            shape,
+            properties: parse_properties(object, folder)?,
+        })
+    })();
+
+    result.with_context(|| format!("Invalid object {}", id))
+}
+
+/// Parses points such as `0,0 32.5,16 -8,4`.
+fn parse_points(points: &str) -> AResult<Vec<glm::Vec2>> {
+    points
+        .split_whitespace()
+        .map(|point| {
+            let mut coords = point.split(',').map(str::parse::<f32>);
+            match (coords.next(), coords.next(), coords.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => Ok(glm::vec2(x, y)),
+                _ => Err(anyhow!("Invalid point \"{}\"", point)),
+            }
+        })
+        .collect()
+}
+
+/// Parses the `properties` child of `node`, if any.
+fn parse_properties(node: &Node, folder: &Path) -> AResult<Properties> {
+    let mut properties = Properties::new();
+
+    let properties_node = match children(node, "properties").next() {
+        Some(properties_node) => properties_node,
+        None => return Ok(properties),
+    };
+
+    for property in children(&properties_node, "property") {
+        let name = attr(&property, "name")?;
+
+        // Multiline strings are stored as the element's text rather than as an attribute.
+        let value = property
+            .attribute("value")
+            .or_else(|| property.text())
+            .unwrap_or_default();
+
+        let value = match property.attribute("type").unwrap_or("string") {
+            "string" => PropertyValue::String(value.to_string()),
+            "bool" => PropertyValue::Bool(value == "true"),
+            "int" => PropertyValue::Int(parse_value(name, value)?),
+            "float" => PropertyValue::Float(parse_value(name, value)?),
+            "color" if value.is_empty() => PropertyValue::Color([0.0; 4]),
+            "color" => PropertyValue::Color(super::parse_color(value)?),
+            "file" => PropertyValue::File(folder.join(value)),
+            "object" => PropertyValue::Object(parse_value(name, value)?),
+            "class" => PropertyValue::Class(parse_properties(&property, folder)?),
+            other => {
+                return Err(anyhow!(
+                    "Property \"{}\" has unknown type \"{}\"",
+                    name,
+                    other
+                ))
+            }
+        };
+
+        properties.insert(name.to_string(), value);
+    }
+
+    Ok(properties)
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> AResult<T> {
+    value
+        .parse::<T>()
+        .map_err(|_| anyhow!("Property \"{}\" has an invalid value \"{}\"", name, value))
+}
+
+/// Child elements of `node` called `name`.
+fn children<'a, 'input>(
+    node: &Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |n| n.has_tag_name(name))
+}
+
+fn attr<'a>(node: &Node<'a, '_>, name: &str) -> AResult<&'a str> {
+    node.attribute(name).ok_or_else(|| {
+        anyhow!(
+            "Missing attribute \"{}\" in \"{}\" element",
+            name,
+            node.tag_name().name()
+        )
+    })
+}
+
+fn num<T: FromStr>(node: &Node, name: &str) -> AResult<T> {
+    let value = attr(node, name)?;
+    value
+        .parse::<T>()
+        .map_err(|_| anyhow!("Attribute \"{}\" is not a number: \"{}\"", name, value))
+}
+
+fn num_or<T: FromStr>(node: &Node, name: &str, default: T) -> AResult<T> {
+    match node.attribute(name) {
+        Some(_) => num(node, name),
+        None => Ok(default),
+    }
+}
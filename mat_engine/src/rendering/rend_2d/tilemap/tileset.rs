@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use anyhow::Result as AResult;
+use image::GenericImageView;
+use nalgebra_glm as glm;
+
+use crate::{
+    arena::ArenaKey,
+    rendering::{bind_group::BindGroupable, wgpu_texture::WgpuTexture},
+    utils::unwrap_mut,
+    EngineContext,
+};
+
+/// Layout of the tiles inside a tileset image, in pixels.
+#[derive(Copy, Clone, Debug)]
+pub struct TilesetLayout {
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// Space between adjacent tiles.
+    pub spacing: u32,
+    /// Space between the tiles and the edges of the image.
+    pub margin: u32,
+}
+
+impl TilesetLayout {
+    pub fn new(tile_width: u32, tile_height: u32) -> Self {
+        Self {
+            tile_width,
+            tile_height,
+            spacing: 0,
+            margin: 0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TileAnimationFrame {
+    /// Tile (of the same tileset) shown during this frame.
+    pub tile_id: u32,
+    /// In seconds.
+    pub duration: f32,
+}
+
+/// An image split into equally sized tiles, numbered from 0, left to right and then top to
+/// bottom.
+pub struct Tileset {
+    pub(super) bind_group_key: ArenaKey,
+    layout: TilesetLayout,
+    image_width: u32,
+    image_height: u32,
+    columns: u32,
+    tile_count: u32,
+    /// Size tiles are drawn at, in world units, if it differs from the map's tile size.
+    world_size: Option<glm::Vec2>,
+    animations: HashMap<u32, Vec<TileAnimationFrame>>,
+}
+
+impl Tileset {
+    /// Creates a tileset from the bytes of an image file (see `WgpuTexture` for supported
+    /// formats).
+    pub fn new(
+        ctx: &mut EngineContext,
+        image_bytes: &[u8],
+        layout: TilesetLayout,
+    ) -> AResult<Self> {
+        let img = image::load_from_memory(image_bytes)?;
+        let img = image::DynamicImage::ImageRgba8(img.to_rgba8());
+        let (image_width, image_height) = img.dimensions();
+
+        if layout.tile_width == 0 || layout.tile_height == 0 {
+            return Err(anyhow!("Tiles must be at least 1x1 pixels"));
+        }
+
+        let columns = count_tiles(
+            image_width,
+            layout.tile_width,
+            layout.spacing,
+            layout.margin,
+        );
+        let rows = count_tiles(
+            image_height,
+            layout.tile_height,
+            layout.spacing,
+            layout.margin,
+        );
+
+        if columns == 0 || rows == 0 {
+            return Err(anyhow!(
+                "Tileset image ({}x{}) is too small for its tiles ({}x{})",
+                image_width,
+                image_height,
+                layout.tile_width,
+                layout.tile_height
+            ));
+        }
+
+        let wgpu_state = &mut unwrap_mut(&mut ctx.rendering_system).state;
+
+        let texture_bind_group_layout = wgpu_state
+            .device
+            .create_bind_group_layout(&WgpuTexture::get_wgpu_bind_group_layout_descriptor());
+
+        let texture_key = wgpu_state.add_new_texture_from_image(img, Some("tileset"));
+        let bind_group_key = wgpu_state.add_new_texture_bind_group(
+            &texture_bind_group_layout,
+            texture_key,
+            Some("tileset_bind_group"),
+        );
+
+        Ok(Self {
+            bind_group_key,
+            layout,
+            image_width,
+            image_height,
+            columns,
+            tile_count: columns * rows,
+            world_size: None,
+            animations: HashMap::new(),
+        })
+    }
+
+    pub fn tile_count(&self) -> u32 {
+        self.tile_count
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn layout(&self) -> &TilesetLayout {
+        &self.layout
+    }
+
+    pub fn world_size(&self) -> Option<glm::Vec2> {
+        self.world_size
+    }
+
+    /// Sets the size tiles of this tileset are drawn at, in world units. By default (`None`),
+    /// tiles fill their map cell.
+    ///
+    /// Like in Tiled, tiles are aligned with the bottom left corner of their cell (bottom center
+    /// on isometric maps), so bigger tiles, like tall trees or walls, stick out of its top.
+    pub fn set_world_size(&mut self, world_size: Option<glm::Vec2>) {
+        self.world_size = world_size;
+    }
+
+    /// Makes every occurrence of `tile_id` cycle through `frames`. Animations are played by
+    /// `Tilemap::update()`.
+    pub fn set_animation(&mut self, tile_id: u32, frames: Vec<TileAnimationFrame>) {
+        if frames.is_empty() {
+            self.animations.remove(&tile_id);
+        } else {
+            self.animations.insert(tile_id, frames);
+        }
+    }
+
+    pub(super) fn is_animated(&self, tile_id: u32) -> bool {
+        self.animations.contains_key(&tile_id)
+    }
+
+    /// Tile that `tile_id` shows at time `time` (in seconds).
+    pub(super) fn animated_tile_id(&self, tile_id: u32, time: f64) -> u32 {
+        let frames = match self.animations.get(&tile_id) {
+            Some(frames) => frames,
+            None => return tile_id,
+        };
+
+        let total: f64 = frames.iter().map(|f| f.duration.max(0.0) as f64).sum();
+        if total <= 0.0 {
+            return frames[0].tile_id;
+        }
+
+        let mut t = time.rem_euclid(total);
+        for frame in frames {
+            let duration = frame.duration.max(0.0) as f64;
+            if t < duration {
+                return frame.tile_id;
+            }
+            t -= duration;
+        }
+
+        frames[frames.len() - 1].tile_id
+    }
+
+    /// Top left and bottom right texture coordinates of a tile. They are inset by half a texel,
+    /// so that filtering doesn't bleed neighbouring tiles in.
+    pub(super) fn uv_rect(&self, tile_id: u32) -> ([f32; 2], [f32; 2]) {
+        let column = tile_id % self.columns;
+        let row = tile_id / self.columns;
+
+        let l = &self.layout;
+        let x = (l.margin + column * (l.tile_width + l.spacing)) as f32;
+        let y = (l.margin + row * (l.tile_height + l.spacing)) as f32;
+
+        let (w, h) = (self.image_width as f32, self.image_height as f32);
+
+        (
+            [(x + 0.5) / w, (y + 0.5) / h],
+            [
+                (x + l.tile_width as f32 - 0.5) / w,
+                (y + l.tile_height as f32 - 0.5) / h,
+            ],
+        )
+    }
+}
+
+fn count_tiles(image_size: u32, tile_size: u32, spacing: u32, margin: u32) -> u32 {
+    if image_size < 2 * margin + tile_size {
+        return 0;
+    }
+    (image_size - 2 * margin + spacing) / (tile_size + spacing)
+}
@@ -51,3 +51,56 @@ impl VertexBufferable for Vertex2d {
 }
 
 impl Vertex for Vertex2d {}
+
+/// Like `Vertex2d`, but also carrying a color, which is multiplied with the sampled texture
+/// color (see the `colored_textured` shaders). Used for text, shapes, and `Mesh2d`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ColoredVertex2d {
+    pub position: [f32; 2],
+    /// (0, 0) is the top left of the texture.
+    pub tex_coords: [f32; 2],
+    /// rgba
+    pub color: [f32; 4],
+}
+
+// Asserts that there is no padding in ColoredVertex2d
+static_assertions::const_assert_eq!(
+    std::mem::size_of::<ColoredVertex2d>(),
+    std::mem::size_of::<f32>() * 8
+);
+
+impl VertexBufferable for ColoredVertex2d {
+    fn buffer_descriptor(shader_locations: Range<u32>) -> VertexBufferSetting {
+        let start_shader_location = shader_locations.start;
+
+        assert!(shader_locations.len() == 3);
+
+        VertexBufferSetting {
+            stride: std::mem::size_of::<ColoredVertex2d>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: vec![
+                wgpu::VertexAttribute {
+                    // Position
+                    offset: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                    shader_location: start_shader_location,
+                },
+                wgpu::VertexAttribute {
+                    // Tex Coords
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x2,
+                    shader_location: start_shader_location + 1,
+                },
+                wgpu::VertexAttribute {
+                    // Color
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                    shader_location: start_shader_location + 2,
+                },
+            ],
+        }
+    }
+}
+
+impl Vertex for ColoredVertex2d {}
@@ -0,0 +1,496 @@
+//! Lightweight render graph, used to compose a frame out of multiple passes.
+//!
+//! Each pass declares which targets it reads from and which single target it writes to. A target is
+//! either the swapchain (the screen) or a *transient* target, which is an intermediate texture that
+//! only lives for the duration of a frame (the underlying textures are pooled and reused across frames).
+//!
+//! Once all passes have been added, `rendering::execute_render_graph()` will:
+//!
+//! - Order the passes so that every pass runs after all passes that write to the targets it reads;
+//! - Skip passes whose output never (directly or indirectly) reaches the swapchain;
+//! - Allocate the transient targets;
+//! - Record every pass into the frame's single command encoder.
+//!
+//! Passes writing to the same target run in the order they were added, and a pass that reads a target
+//! always sees the result of every pass that writes to it.
+//!
+//! ```ignore
+//! let mut graph = RenderGraph::new();
+//! let scene = graph.create_transient(TransientTargetDesc::screen_sized());
+//!
+//! graph
+//!     .add_pass("scene", scene)
+//!     .clear([0.0, 0.0, 0.0, 1.0])
+//!     .execute(|ctx, target| rend_2d.render_sample_texture(ctx, target, tex_key));
+//!
+//! graph
+//!     .add_pass("imgui", TargetHandle::SWAPCHAIN)
+//!     .execute(|ctx, target| mat_engine::imgui::render(ctx, target));
+//!
+//! mat_engine::rendering::execute_render_graph(ctx, &mut frt, graph)?;
+//! ```
+
+use anyhow::anyhow;
+use anyhow::Result as AResult;
+
+use super::{
+    bind_group::BindGroupable,
+    frame::{FrameRenderTarget, RenderTarget},
+    wgpu_state::WgpuState,
+    wgpu_texture::WgpuTexture,
+};
+use crate::utils::{unwrap_mut, unwrap_ref};
+
+const DEBUG_TRACE_SCHEDULE: bool = false;
+
+/// Pooled transient textures that go unused for this many frames get dropped.
+const MAX_UNUSED_FRAMES: u32 = 120;
+
+/// Identifies a target that passes may read from or write to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TargetHandle(Target);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+enum Target {
+    Swapchain,
+    /// Index into `RenderGraph::transients`.
+    Transient(usize),
+}
+
+impl TargetHandle {
+    /// The screen. Cannot be read from.
+    pub const SWAPCHAIN: TargetHandle = TargetHandle(Target::Swapchain);
+}
+
+/// Size of a transient target.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TransientSize {
+    /// The size of the screen, multiplied by the given factor (e.g. 0.5 for half resolution).
+    ScreenRelative(f32),
+    /// A fixed size, in pixels.
+    Fixed { width: u32, height: u32 },
+}
+
+/// Describes a transient target. The texture format is always the same as the screen's, so
+/// any pipeline that may draw to the screen may also draw to a transient target.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TransientTargetDesc {
+    pub size: TransientSize,
+}
+
+impl TransientTargetDesc {
+    /// A transient target with the same size as the screen.
+    pub fn screen_sized() -> Self {
+        Self {
+            size: TransientSize::ScreenRelative(1.0),
+        }
+    }
+
+    fn resolve_size(&self, screen_width: u32, screen_height: u32) -> (u32, u32) {
+        match self.size {
+            TransientSize::ScreenRelative(factor) => (
+                ((screen_width as f32 * factor) as u32).max(1),
+                ((screen_height as f32 * factor) as u32).max(1),
+            ),
+            TransientSize::Fixed { width, height } => (width.max(1), height.max(1)),
+        }
+    }
+}
+
+type PassFn<'a> = Box<dyn FnOnce(&mut crate::EngineContext, &mut RenderTarget) + 'a>;
+
+struct PassNode<'a> {
+    name: &'static str,
+    inputs: Vec<TargetHandle>,
+    output: TargetHandle,
+    clear: Option<wgpu::Color>,
+    execute: PassFn<'a>,
+}
+
+/// Collects the passes of a single frame. See module docs.
+///
+/// Pass closures may borrow from the surrounding scope (for example, a `Renderer2d`), which is why
+/// the graph is generic over a lifetime. Build a new graph every frame.
+pub struct RenderGraph<'a> {
+    transients: Vec<TransientTargetDesc>,
+    passes: Vec<PassNode<'a>>,
+}
+
+impl<'a> Default for RenderGraph<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> RenderGraph<'a> {
+    pub fn new() -> Self {
+        Self {
+            transients: vec![],
+            passes: vec![],
+        }
+    }
+
+    /// Declares a new transient target, which passes may then read from and write to.
+    pub fn create_transient(&mut self, desc: TransientTargetDesc) -> TargetHandle {
+        self.transients.push(desc);
+        TargetHandle(Target::Transient(self.transients.len() - 1))
+    }
+
+    /// Starts adding a pass that writes to `output`. The pass is only added once
+    /// `PassBuilder::execute()` is called.
+    pub fn add_pass<'g>(
+        &'g mut self,
+        name: &'static str,
+        output: TargetHandle,
+    ) -> PassBuilder<'g, 'a> {
+        PassBuilder {
+            graph: self,
+            name,
+            inputs: vec![],
+            output,
+            clear: None,
+        }
+    }
+
+    /// Returns the order in which passes should be executed, as indices into `self.passes`.
+    /// Passes that don't contribute to the swapchain are left out.
+    fn schedule(&self) -> AResult<Vec<usize>> {
+        let n = self.passes.len();
+
+        for pass in &self.passes {
+            for handle in pass.inputs.iter().chain(std::iter::once(&pass.output)) {
+                if let Target::Transient(t) = handle.0 {
+                    if t >= self.transients.len() {
+                        return Err(anyhow!(
+                            "Pass \"{}\" uses a target that doesn't belong to this graph",
+                            pass.name
+                        ));
+                    }
+                }
+            }
+            if pass.inputs.contains(&TargetHandle::SWAPCHAIN) {
+                return Err(anyhow!(
+                    "Pass \"{}\" reads from the swapchain, which isn't supported",
+                    pass.name
+                ));
+            }
+            if pass.inputs.contains(&pass.output) {
+                return Err(anyhow!(
+                    "Pass \"{}\" reads from the same target it writes to",
+                    pass.name
+                ));
+            }
+        }
+
+        // deps[i] contains every pass that must run before pass i.
+        let mut deps: Vec<Vec<usize>> = vec![vec![]; n];
+        for (i, pass) in self.passes.iter().enumerate() {
+            for (j, other) in self.passes.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let reads_output_of_other = pass.inputs.contains(&other.output);
+                let earlier_writer_of_same_target = j < i && other.output == pass.output;
+                if reads_output_of_other || earlier_writer_of_same_target {
+                    deps[i].push(j);
+                }
+            }
+        }
+
+        // Only passes that (transitively) contribute to the swapchain are needed.
+        let mut needed = vec![false; n];
+        let mut stack: Vec<usize> = (0..n)
+            .filter(|&i| self.passes[i].output == TargetHandle::SWAPCHAIN)
+            .collect();
+        while let Some(i) = stack.pop() {
+            if !needed[i] {
+                needed[i] = true;
+                stack.extend(deps[i].iter().copied());
+            }
+        }
+
+        // Kahn's algorithm, always picking the earliest added pass that is ready, so that
+        // the resulting order is deterministic and as close to insertion order as possible.
+        let mut scheduled = vec![false; n];
+        let mut order = vec![];
+        let needed_count = needed.iter().filter(|x| **x).count();
+        while order.len() < needed_count {
+            let next = (0..n)
+                .find(|&i| needed[i] && !scheduled[i] && deps[i].iter().all(|&d| scheduled[d]));
+
+            match next {
+                Some(i) => {
+                    scheduled[i] = true;
+                    order.push(i);
+                }
+                None => {
+                    return Err(anyhow!(
+                        "Render graph has a cycle involving passes {:?}",
+                        (0..n)
+                            .filter(|&i| needed[i] && !scheduled[i])
+                            .map(|i| self.passes[i].name)
+                            .collect::<Vec<_>>()
+                    ));
+                }
+            }
+        }
+
+        if DEBUG_TRACE_SCHEDULE {
+            log::trace!(
+                "Render graph order: {:?}; culled: {:?}",
+                order
+                    .iter()
+                    .map(|&i| self.passes[i].name)
+                    .collect::<Vec<_>>(),
+                (0..n)
+                    .filter(|&i| !needed[i])
+                    .map(|i| self.passes[i].name)
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        Ok(order)
+    }
+}
+
+/// See `RenderGraph::add_pass()`.
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut RenderGraph<'a>,
+    name: &'static str,
+    inputs: Vec<TargetHandle>,
+    output: TargetHandle,
+    clear: Option<wgpu::Color>,
+}
+
+impl<'g, 'a> PassBuilder<'g, 'a> {
+    /// Declares that this pass samples from `input`. Inside the pass, inputs are available
+    /// (in the order they were declared) from the `RenderTarget`, see
+    /// `RenderTarget::input()`.
+    pub fn read(mut self, input: TargetHandle) -> Self {
+        self.inputs.push(input);
+        self
+    }
+
+    /// Clears the output (to the given rgba color) before running the pass.
+    pub fn clear(mut self, color: [f64; 4]) -> Self {
+        self.clear = Some(wgpu::Color {
+            r: color[0],
+            g: color[1],
+            b: color[2],
+            a: color[3],
+        });
+        self
+    }
+
+    /// Adds the pass to the graph. `func` will be called when the graph is executed, with a
+    /// `RenderTarget` corresponding to this pass's output.
+    pub fn execute<F>(self, func: F)
+    where
+        F: FnOnce(&mut crate::EngineContext, &mut RenderTarget) + 'a,
+    {
+        self.graph.passes.push(PassNode {
+            name: self.name,
+            inputs: self.inputs,
+            output: self.output,
+            clear: self.clear,
+            execute: Box::new(func),
+        });
+    }
+}
+
+/// See `rendering::execute_render_graph()`.
+pub(super) fn execute(
+    ctx: &mut crate::EngineContext,
+    frt: &mut FrameRenderTarget,
+    graph: RenderGraph,
+) -> AResult<()> {
+    let order = graph.schedule()?;
+
+    let RenderGraph { transients, passes } = graph;
+
+    // The pool is taken out of the rendering system while passes run, since pass closures need
+    // the whole `EngineContext`, but we need to hold on to the views of the transient textures.
+    let rendering_system = unwrap_mut(&mut ctx.rendering_system);
+    let mut pool = std::mem::take(&mut rendering_system.transient_targets);
+
+    pool.begin_frame();
+
+    rendering_system.state.reserve_gpu_timer(order.len());
+
+    // Transients only used by culled passes aren't allocated.
+    let mut used = vec![false; transients.len()];
+    for &i in &order {
+        let pass = &passes[i];
+        for handle in pass.inputs.iter().chain(std::iter::once(&pass.output)) {
+            if let Target::Transient(t) = handle.0 {
+                used[t] = true;
+            }
+        }
+    }
+
+    let slots: Vec<Option<usize>> = transients
+        .iter()
+        .zip(used)
+        .map(|(desc, used)| {
+            if !used {
+                return None;
+            }
+            let (width, height) = desc.resolve_size(frt.width, frt.height);
+            Some(pool.acquire(&mut rendering_system.state, width, height))
+        })
+        .collect();
+    let slot = |t: usize| slots[t].expect("Transients of scheduled passes are allocated");
+
+    let mut passes: Vec<Option<PassNode>> = passes.into_iter().map(Some).collect();
+
+    for i in order {
+        let pass = passes[i].take().expect("Pass scheduled twice");
+
+        if let Some(timer) = &mut unwrap_mut(&mut ctx.rendering_system).state.gpu_timer {
+            timer.begin_pass(&mut frt.encoder, pass.name);
+        }
+
+        let inputs = pass
+            .inputs
+            .iter()
+            .map(|handle| match handle.0 {
+                Target::Transient(t) => &pool.entries[slot(t)].bind_group,
+                Target::Swapchain => unreachable!("Checked in schedule()"),
+            })
+            .collect();
+
+        let (view, msaa_view, width, height) = match pass.output.0 {
+            Target::Swapchain => (&frt.view, frt.msaa_view.as_ref(), frt.width, frt.height),
+            Target::Transient(t) => {
+                let entry = &pool.entries[slot(t)];
+                (
+                    &entry.texture.texture_view,
+                    entry.msaa_view.as_ref(),
+                    entry.width,
+                    entry.height,
+                )
+            }
+        };
+
+        let mut target = RenderTarget {
+            encoder: &mut frt.encoder,
+            view,
+            msaa_view,
+            inputs,
+            width,
+            height,
+        };
+
+        if let Some(color) = pass.clear {
+            unwrap_ref(&ctx.rendering_system)
+                .state
+                .make_clear_render_pass(&mut target, color);
+        }
+
+        (pass.execute)(ctx, &mut target);
+
+        if let Some(timer) = &mut unwrap_mut(&mut ctx.rendering_system).state.gpu_timer {
+            timer.end_pass(&mut frt.encoder);
+        }
+    }
+
+    pool.end_frame();
+
+    unwrap_mut(&mut ctx.rendering_system).transient_targets = pool;
+
+    Ok(())
+}
+
+/// Textures backing transient targets, kept around across frames so they needn't be recreated
+/// every frame.
+#[derive(Default)]
+pub(crate) struct TransientTargetPool {
+    entries: Vec<PooledTarget>,
+    /// Created on first use.
+    bind_group_layout: Option<wgpu::BindGroupLayout>,
+}
+
+struct PooledTarget {
+    texture: WgpuTexture,
+    bind_group: wgpu::BindGroup,
+    /// Only when MSAA is enabled.
+    msaa_view: Option<wgpu::TextureView>,
+    width: u32,
+    height: u32,
+    in_use: bool,
+    unused_frames: u32,
+}
+
+impl TransientTargetPool {
+    fn begin_frame(&mut self) {
+        for entry in &mut self.entries {
+            entry.in_use = false;
+        }
+    }
+
+    /// Returns the index of an entry of the given size, creating it if necessary.
+    fn acquire(&mut self, wgpu_state: &mut WgpuState, width: u32, height: u32) -> usize {
+        if let Some(i) = self
+            .entries
+            .iter()
+            .position(|e| !e.in_use && e.width == width && e.height == height)
+        {
+            self.entries[i].in_use = true;
+            self.entries[i].unused_frames = 0;
+            return i;
+        }
+
+        let layout = self.bind_group_layout.get_or_insert_with(|| {
+            wgpu_state
+                .device
+                .create_bind_group_layout(&WgpuTexture::get_wgpu_bind_group_layout_descriptor())
+        });
+
+        let format = wgpu_state.main_surface().cfg.format;
+
+        let texture = WgpuTexture::new_render_target(
+            &mut wgpu_state.device,
+            width,
+            height,
+            format,
+            Some("transient render target"),
+        );
+        let bind_group = texture.make_wgpu_bind_group(layout, &mut wgpu_state.device);
+
+        let msaa_view = if wgpu_state.msaa_samples > 1 {
+            Some(WgpuTexture::new_multisampled_view(
+                &mut wgpu_state.device,
+                width,
+                height,
+                format,
+                wgpu_state.msaa_samples,
+                Some("transient render target (multisampled)"),
+            ))
+        } else {
+            None
+        };
+
+        self.entries.push(PooledTarget {
+            texture,
+            bind_group,
+            msaa_view,
+            width,
+            height,
+            in_use: true,
+            unused_frames: 0,
+        });
+
+        self.entries.len() - 1
+    }
+
+    fn end_frame(&mut self) {
+        for entry in &mut self.entries {
+            if !entry.in_use {
+                entry.unused_frames += 1;
+            }
+        }
+
+        self.entries
+            .retain(|e| e.unused_frames <= MAX_UNUSED_FRAMES);
+    }
+}
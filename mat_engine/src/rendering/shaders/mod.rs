@@ -9,26 +9,58 @@ pub(crate) unsafe fn compile_glsl_to_spirv<S: AsRef<str>>(
     file_name: S,
     shader_type: ShaderType,
 ) -> Shader {
+    try_compile_glsl_to_spirv(source, file_name, shader_type).unwrap()
+}
+
+/// Same as `compile_glsl_to_spirv()`, but returns an error (containing the compiler's messages)
+/// instead of panicking if compilation fails. Useful for user provided shaders.
+///
+/// Safety: See `compile_glsl_to_spirv()`.
+pub(crate) unsafe fn try_compile_glsl_to_spirv<S: AsRef<str>>(
+    source: S,
+    file_name: S,
+    shader_type: ShaderType,
+) -> anyhow::Result<Shader> {
     warn_incorrect_shader_type(&file_name, shader_type);
 
-    let mut compiler = shaderc::Compiler::new().unwrap();
-    let options = shaderc::CompileOptions::new().unwrap();
+    let mut compiler =
+        shaderc::Compiler::new().ok_or_else(|| anyhow::anyhow!("Couldn't create compiler"))?;
+    let options = shaderc::CompileOptions::new()
+        .ok_or_else(|| anyhow::anyhow!("Couldn't create compile options"))?;
 
     let shader_kind = shader_type.get_shaderc_shader_kind();
 
-    let binary_result = compiler
-        .compile_into_spirv(
-            source.as_ref(),
-            shader_kind,
-            file_name.as_ref(),
-            "main",
-            Some(&options),
-        )
-        .unwrap();
+    let binary_result = compiler.compile_into_spirv(
+        source.as_ref(),
+        shader_kind,
+        file_name.as_ref(),
+        "main",
+        Some(&options),
+    )?;
 
-    Shader {
+    Ok(Shader {
         shader_type,
         binary: binary_result.as_binary().to_vec(),
+    })
+}
+
+/// Loads and compiles one of the engine's own shaders, located at
+/// `<engine assets>/shaders/<folder>/<file_name>`.
+///
+/// Panics if the shader can't be loaded or compiled, since engine shaders are expected to be valid.
+pub(crate) fn load_engine_shader(folder: &str, file_name: &str, shader_type: ShaderType) -> Shader {
+    let mut path = crate::assets::get_engine_assets_path();
+    path.push("shaders");
+    path.push(folder);
+    path.push(file_name);
+
+    // Safety: Engine shaders are assumed to be well formed.
+    unsafe {
+        compile_glsl_to_spirv(
+            crate::assets::read_file_at_path_to_string(path).expect("Cannot load shader"),
+            file_name.to_string(),
+            shader_type,
+        )
     }
 }
 
@@ -93,6 +125,7 @@ fn expected_extension(shader_type: ShaderType) -> &'static str {
     match shader_type {
         ShaderType::Vertex => "vert",
         ShaderType::Fragment => "frag",
+        ShaderType::Compute => "comp",
     }
 }
 
@@ -101,6 +134,7 @@ fn expected_extension(shader_type: ShaderType) -> &'static str {
 pub enum ShaderType {
     Vertex,
     Fragment,
+    Compute,
 }
 
 impl ShaderType {
@@ -108,6 +142,7 @@ impl ShaderType {
         match self {
             ShaderType::Vertex => shaderc::ShaderKind::Vertex,
             ShaderType::Fragment => shaderc::ShaderKind::Fragment,
+            ShaderType::Compute => shaderc::ShaderKind::Compute,
         }
     }
 }
@@ -0,0 +1,245 @@
+//! Per frame rendering statistics, and optional GPU timing of render graph passes.
+//!
+//! Statistics are collected by `WgpuState` while a frame is recorded, and are available once the
+//! frame is complete, through `rendering::last_frame_stats()`, and may be shown with
+//! `imgui::add_render_stats_panel()`.
+
+use std::cell::Cell;
+
+/// What was recorded during a frame: since the previous call to `rendering::complete_render()`,
+/// up to and including the one completing the frame.
+///
+/// Only what the engine draws itself is counted: imgui does its own drawing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    pub render_passes: u32,
+    pub draw_calls: u32,
+    /// Sum of the instances of every draw call.
+    pub instances: u32,
+    pub pipeline_switches: u32,
+    pub bind_group_switches: u32,
+    /// Bytes written to GPU buffers, either when creating them or when updating them.
+    pub bytes_uploaded: u64,
+    /// Time the GPU spent on each render graph pass, in execution order. Empty unless GPU timing
+    /// is enabled, see `rendering::set_gpu_timing_enabled()`.
+    pub pass_timings: Vec<PassTiming>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassTiming {
+    /// Name the pass was given in `RenderGraph::add_pass()`.
+    pub name: &'static str,
+    pub milliseconds: f64,
+}
+
+impl RenderStats {
+    /// Total GPU time of the timed passes, in milliseconds.
+    pub fn gpu_milliseconds(&self) -> f64 {
+        self.pass_timings.iter().map(|t| t.milliseconds).sum()
+    }
+}
+
+/// Counters of the frame being recorded.
+///
+/// Most recording only has access to a shared `WgpuState` (render passes borrow it), hence the
+/// `Cell`s.
+#[derive(Default)]
+pub(crate) struct StatsCounters {
+    render_passes: Cell<u32>,
+    draw_calls: Cell<u32>,
+    instances: Cell<u32>,
+    pipeline_switches: Cell<u32>,
+    bind_group_switches: Cell<u32>,
+    bytes_uploaded: Cell<u64>,
+}
+
+impl StatsCounters {
+    pub(super) fn add_render_pass(&self) {
+        self.render_passes.set(self.render_passes.get() + 1);
+    }
+
+    pub(super) fn add_draw_call(&self, instances: u32) {
+        self.draw_calls.set(self.draw_calls.get() + 1);
+        self.instances.set(self.instances.get() + instances);
+    }
+
+    pub(super) fn add_pipeline_switch(&self) {
+        self.pipeline_switches.set(self.pipeline_switches.get() + 1);
+    }
+
+    pub(super) fn add_bind_group_switch(&self) {
+        self.bind_group_switches
+            .set(self.bind_group_switches.get() + 1);
+    }
+
+    pub(super) fn add_upload(&self, bytes: u64) {
+        self.bytes_uploaded.set(self.bytes_uploaded.get() + bytes);
+    }
+
+    /// Returns the counted stats, and resets the counters for the next frame.
+    pub(super) fn take(&self) -> RenderStats {
+        RenderStats {
+            render_passes: self.render_passes.take(),
+            draw_calls: self.draw_calls.take(),
+            instances: self.instances.take(),
+            pipeline_switches: self.pipeline_switches.take(),
+            bind_group_switches: self.bind_group_switches.take(),
+            bytes_uploaded: self.bytes_uploaded.take(),
+            pass_timings: vec![],
+        }
+    }
+}
+
+/// Size of a timestamp, as written by `resolve_query_set()`.
+const TIMESTAMP_SIZE: wgpu::BufferAddress = std::mem::size_of::<u64>() as wgpu::BufferAddress;
+
+/// Writes timestamps before and after every render graph pass, and reads them back once the frame
+/// is submitted.
+///
+/// Reading them back waits for the GPU to finish the frame, which is why timing is opt in.
+pub(crate) struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    /// Written by resolving `query_set`, and mapped to read the timestamps.
+    buffer: wgpu::Buffer,
+    /// Number of passes `query_set` has room for.
+    capacity: u32,
+    /// Passes timed during the current frame.
+    passes: Vec<&'static str>,
+    /// Whether the start of the last pass was written, but not its end yet.
+    open: bool,
+}
+
+impl GpuTimer {
+    pub(super) fn new(device: &wgpu::Device, capacity: u32) -> Self {
+        let capacity = capacity.max(1);
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("pass timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2 * capacity,
+        });
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pass timestamps buffer"),
+            size: 2 * capacity as wgpu::BufferAddress * TIMESTAMP_SIZE,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set,
+            buffer,
+            capacity,
+            passes: vec![],
+            open: false,
+        }
+    }
+
+    pub(super) fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Number of passes timed so far during the current frame.
+    pub(super) fn timed_passes(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Writes the timestamp marking the start of a pass. Passes beyond the capacity are not timed.
+    pub(super) fn begin_pass(&mut self, encoder: &mut wgpu::CommandEncoder, name: &'static str) {
+        if self.passes.len() < self.capacity as usize {
+            encoder.write_timestamp(&self.query_set, 2 * self.passes.len() as u32);
+            self.passes.push(name);
+            self.open = true;
+        }
+    }
+
+    /// Writes the timestamp marking the end of the pass started by the last `begin_pass()`.
+    pub(super) fn end_pass(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if self.open {
+            encoder.write_timestamp(&self.query_set, 2 * self.passes.len() as u32 - 1);
+            self.open = false;
+        }
+    }
+
+    /// Copies the timestamps of the frame to the buffer. Must be recorded after every pass.
+    pub(super) fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        if !self.passes.is_empty() {
+            encoder.resolve_query_set(
+                &self.query_set,
+                0..2 * self.passes.len() as u32,
+                &self.buffer,
+                0,
+            );
+        }
+    }
+
+    /// Reads back the timings of the frame, once it has been submitted. Blocks until the GPU is
+    /// done with it.
+    ///
+    /// `period` is the duration of a timestamp tick, in nanoseconds (see
+    /// `wgpu::Queue::get_timestamp_period()`).
+    pub(super) fn read(&mut self, device: &wgpu::Device, period: f32) -> Vec<PassTiming> {
+        if self.passes.is_empty() {
+            return vec![];
+        }
+
+        let size = 2 * self.passes.len() as wgpu::BufferAddress * TIMESTAMP_SIZE;
+        let slice = self.buffer.slice(..size);
+
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+
+        let timings = match futures::executor::block_on(mapping) {
+            Ok(()) => {
+                let timestamps: Vec<u64> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+                self.buffer.unmap();
+
+                self.passes
+                    .iter()
+                    .zip(timestamps.chunks_exact(2))
+                    .map(|(&name, pair)| PassTiming {
+                        name,
+                        milliseconds: pair[1].saturating_sub(pair[0]) as f64 * period as f64
+                            / 1_000_000.0,
+                    })
+                    .collect()
+            }
+            Err(e) => {
+                log::warn!("Could not read back pass timestamps: {:?}", e);
+                vec![]
+            }
+        };
+
+        self.passes.clear();
+
+        timings
+    }
+}
+
+/// Draws `stats` in an imgui window. See `imgui::add_render_stats_panel()`.
+pub(crate) fn imgui_panel(ui: &::imgui::Ui, stats: &RenderStats) {
+    ::imgui::Window::new("Render stats")
+        .size([260.0, 240.0], ::imgui::Condition::FirstUseEver)
+        .build(ui, || {
+            ui.text(format!("Render passes: {}", stats.render_passes));
+            ui.text(format!("Draw calls: {}", stats.draw_calls));
+            ui.text(format!("Instances: {}", stats.instances));
+            ui.text(format!("Pipeline switches: {}", stats.pipeline_switches));
+            ui.text(format!(
+                "Bind group switches: {}",
+                stats.bind_group_switches
+            ));
+            ui.text(format!(
+                "Uploaded: {:.1} KiB",
+                stats.bytes_uploaded as f64 / 1024.0
+            ));
+
+            if !stats.pass_timings.is_empty() {
+                ui.separator();
+                for timing in &stats.pass_timings {
+                    ui.text(format!("{}: {:.3} ms", timing.name, timing.milliseconds));
+                }
+                ui.text(format!("GPU total: {:.3} ms", stats.gpu_milliseconds()));
+            }
+        });
+}
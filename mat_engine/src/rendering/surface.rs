@@ -0,0 +1,138 @@
+use anyhow::anyhow;
+use anyhow::Result as AResult;
+use winit::dpi::PhysicalSize;
+
+use super::wgpu_texture::WgpuTexture;
+
+/// The surface of a window, which frames are presented to, along with what is needed to render
+/// to it. Managed by `WgpuState`, one per window.
+pub(crate) struct WindowSurface {
+    pub(super) surface: wgpu::Surface,
+    pub(super) cfg: wgpu::SurfaceConfiguration,
+    pub(super) window_inner_size: PhysicalSize<u32>,
+    /// Multisampled counterpart of the surface, resolved into it. Created on demand, and lent to
+    /// the `FrameRenderTarget` while a frame is being rendered.
+    msaa_view: Option<wgpu::TextureView>,
+}
+
+impl WindowSurface {
+    /// `cfg`'s size should be the window's inner size.
+    pub(super) fn new(
+        surface: wgpu::Surface,
+        device: &wgpu::Device,
+        cfg: wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let window_inner_size = PhysicalSize::new(cfg.width, cfg.height);
+
+        let mut window_surface = Self {
+            surface,
+            cfg,
+            window_inner_size,
+            msaa_view: None,
+        };
+        window_surface.configure(device);
+
+        window_surface
+    }
+
+    /// Configures the surface, unless the window is minimized (a surface can't be configured with a
+    /// zero size). In that case, the previous configuration is kept until the window is restored,
+    /// and frames are skipped in the meantime.
+    fn configure(&mut self, device: &wgpu::Device) {
+        if !self.is_minimized() {
+            self.surface.configure(device, &self.cfg);
+        }
+    }
+
+    pub(super) fn resize(&mut self, device: &wgpu::Device, new_inner_size: PhysicalSize<u32>) {
+        self.window_inner_size = new_inner_size;
+
+        if self.is_minimized() {
+            return;
+        }
+        self.cfg.width = new_inner_size.width;
+        self.cfg.height = new_inner_size.height;
+
+        self.configure(device);
+
+        // Recreated at the new size by the next frame.
+        self.msaa_view = None;
+    }
+
+    /// Takes effect immediately (unless the window is minimized, in which case it does once it
+    /// is restored).
+    pub(super) fn set_present_mode(
+        &mut self,
+        device: &wgpu::Device,
+        present_mode: wgpu::PresentMode,
+    ) {
+        self.cfg.present_mode = present_mode;
+        self.configure(device);
+    }
+
+    pub(super) fn is_minimized(&self) -> bool {
+        self.window_inner_size.width == 0 || self.window_inner_size.height == 0
+    }
+
+    /// Gets the surface texture to render the next frame to. If the surface was lost or is
+    /// outdated, it is reconfigured, and if getting it timed out, we try again, once. If that
+    /// fails too, `None` is returned, and the frame should be skipped.
+    ///
+    /// Only running out of memory is an error, as there is no recovering from that.
+    pub(super) fn acquire_frame(
+        &mut self,
+        device: &wgpu::Device,
+    ) -> AResult<Option<wgpu::SurfaceFrame>> {
+        match self.surface.get_current_frame() {
+            Ok(frame) => return Ok(Some(frame)),
+            Err(wgpu::SurfaceError::Lost) | Err(wgpu::SurfaceError::Outdated) => {
+                log::debug!("Surface lost or outdated, reconfiguring it");
+                self.surface.configure(device, &self.cfg);
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                log::debug!("Timed out getting the surface texture, retrying");
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                return Err(anyhow!("Out of memory getting the surface texture"))
+            }
+        }
+
+        match self.surface.get_current_frame() {
+            Ok(frame) => Ok(Some(frame)),
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                Err(anyhow!("Out of memory getting the surface texture"))
+            }
+            Err(e) => {
+                log::warn!("Skipping frame, could not get the surface texture: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Lends the multisampled view for a frame, creating it if needed. See `give_back_msaa_view()`.
+    pub(super) fn take_msaa_view(
+        &mut self,
+        device: &mut wgpu::Device,
+        samples: u32,
+    ) -> wgpu::TextureView {
+        let cfg = &self.cfg;
+
+        self.msaa_view.take().unwrap_or_else(|| {
+            WgpuTexture::new_multisampled_view(
+                device,
+                cfg.width,
+                cfg.height,
+                cfg.format,
+                samples,
+                Some("multisampled framebuffer"),
+            )
+        })
+    }
+
+    /// Kept for the next frame, unless the surface was resized in the meantime.
+    pub(super) fn give_back_msaa_view(&mut self, view: wgpu::TextureView, width: u32, height: u32) {
+        if width == self.cfg.width && height == self.cfg.height {
+            self.msaa_view = Some(view);
+        }
+    }
+}
@@ -7,6 +7,8 @@ pub(super) struct PipelineBuilder<'a> {
     frag_shader_module: Option<&'a wgpu::ShaderModule>,
     texture_format: Option<wgpu::TextureFormat>,
     vertex_buffer_settings: Option<Vec<VertexBufferSetting>>,
+    blend_state: wgpu::BlendState,
+    sample_count: u32,
 }
 
 #[allow(dead_code)]
@@ -18,6 +20,8 @@ impl<'a> PipelineBuilder<'a> {
             frag_shader_module: None,
             texture_format: None,
             vertex_buffer_settings: None,
+            blend_state: wgpu::BlendState::REPLACE,
+            sample_count: 1,
         }
     }
 
@@ -50,6 +54,18 @@ impl<'a> PipelineBuilder<'a> {
         self
     }
 
+    /// Defaults to `wgpu::BlendState::REPLACE`.
+    pub(super) fn set_blend_state(&'a mut self, blend_state: wgpu::BlendState) -> &'a mut Self {
+        self.blend_state = blend_state;
+        self
+    }
+
+    /// Must match the sample count of the targets the pipeline draws to. Defaults to 1.
+    pub(super) fn set_sample_count(&'a mut self, sample_count: u32) -> &'a mut Self {
+        self.sample_count = sample_count;
+        self
+    }
+
     pub(super) fn set_vertex_buffers(
         &'a mut self,
         vertex_buffer_settings: Vec<VertexBufferSetting>,
@@ -95,7 +111,7 @@ impl<'a> PipelineBuilder<'a> {
                         format: self
                             .texture_format
                             .ok_or(anyhow!("You must provide a texture format"))?,
-                        blend: Some(wgpu::BlendState::REPLACE),
+                        blend: Some(self.blend_state),
                         write_mask: wgpu::ColorWrites::ALL,
                     }],
                 }),
@@ -110,7 +126,7 @@ impl<'a> PipelineBuilder<'a> {
                 },
                 depth_stencil: None,
                 multisample: wgpu::MultisampleState {
-                    count: 1,
+                    count: self.sample_count,
                     mask: !0,
                     alpha_to_coverage_enabled: false,
                 }, /*
@@ -1,29 +1,52 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
 use anyhow::anyhow;
 use anyhow::Result as AResult;
+use wgpu::util::DeviceExt;
 use winit::dpi::PhysicalSize;
 
 use super::{
     bind_group::BindGroupable,
-    frame::FrameRenderTarget,
+    frame::{FrameRenderTarget, RenderTarget},
     generic_uniform::Uniform,
+    stats::{GpuTimer, RenderStats, StatsCounters},
+    surface::WindowSurface,
     wgpu_pipeline::{PipelineBuilder, VertexBufferSetting},
     wgpu_texture::WgpuTexture,
+    PresentMode, RenderingConfig,
 };
 use crate::{
     arena::{Arena, ArenaKey},
     typedefs::BoxErr,
 };
 
+/// Color the screen is cleared to at the start of every frame.
+pub(super) const DEFAULT_CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.1,
+    g: 0.2,
+    b: 0.3,
+    a: 1.0,
+};
+
+/// Number of render graph passes the GPU timer initially has room for. It grows as needed.
+const INITIAL_TIMED_PASSES: u32 = 16;
+
 /// Do not use directly from user code. It is managed by `RenderingSystem`.
 #[allow(dead_code, unused_variables)]
 pub(crate) struct WgpuState {
-    pub(super) surface: wgpu::Surface,
+    /// Kept to create the surfaces of windows opened later on.
+    instance: wgpu::Instance,
     pub(super) adapter: wgpu::Adapter,
     pub(super) device: wgpu::Device,
     pub(super) queue: wgpu::Queue,
 
-    pub(super) window_inner_size: PhysicalSize<u32>,
-    pub(super) surface_cfg: wgpu::SurfaceConfiguration,
+    /// Indexed by window, see `windowing::open_window()`.
+    pub(super) surfaces: HashMap<ArenaKey, WindowSurface>,
+    pub(super) main_window: ArenaKey,
+
+    /// Sample count of every render target and pipeline, see `RenderingConfig::msaa_samples`.
+    pub(super) msaa_samples: u32,
 
     // --- ARENAS ---
     // TODO: Maybe move (at least some of) these somewhere else...
@@ -31,6 +54,13 @@ pub(crate) struct WgpuState {
 
     pub(super) bind_groups: Arena<BindGroup>,
     pub(super) render_pipelines: Arena<wgpu::RenderPipeline>,
+    pub(super) compute_pipelines: Arena<wgpu::ComputePipeline>,
+
+    // --- STATS ---
+    pub(super) stats: StatsCounters,
+    pub(super) last_stats: RenderStats,
+    /// Only present while GPU timing is enabled.
+    pub(super) gpu_timer: Option<GpuTimer>,
 }
 
 impl WgpuState {
@@ -38,27 +68,54 @@ impl WgpuState {
         // TODO: Abstract -> Remove direct dependency on winit window (see wgpu trait bounds
         // on window)
         window: &winit::window::Window,
+        window_key: ArenaKey,
+        config: &RenderingConfig,
     ) -> AResult<Self> {
+        if !config.msaa_samples.is_power_of_two() || config.msaa_samples > 16 {
+            return Err(anyhow!(
+                "Invalid MSAA sample count {}, it must be 1, 2, 4, 8 or 16",
+                config.msaa_samples
+            ));
+        }
+
         let window_inner_size = window.inner_size();
 
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let backends = config.backends.to_wgpu();
+        let instance = wgpu::Instance::new(backends);
 
         // Safety: window must outlive surface.
         // TODO: Enforce this!
         let surface = unsafe { instance.create_surface(window) };
 
-        let request_adapter_options = &wgpu::RequestAdapterOptions {
-            compatible_surface: Some(&surface),
-            power_preference: wgpu::PowerPreference::default(),
+        let adapter: wgpu::Adapter = if config.force_fallback_adapter {
+            // A surface only has a preferred format for adapters that can present to it.
+            instance
+                .enumerate_adapters(backends)
+                .find(|adapter| {
+                    adapter.get_info().device_type == wgpu::DeviceType::Cpu
+                        && surface.get_preferred_format(adapter).is_some()
+                })
+                .ok_or(anyhow!("no fallback (software) adapter is available"))?
+        } else {
+            let request_adapter_options = &wgpu::RequestAdapterOptions {
+                compatible_surface: Some(&surface),
+                power_preference: config.power_preference.to_wgpu(),
+            };
+
+            futures::executor::block_on(async {
+                instance.request_adapter(request_adapter_options).await
+            })
+            .ok_or(anyhow!("adapter could not be obtained"))?
         };
 
-        let adapter: wgpu::Adapter = futures::executor::block_on(async {
-            instance.request_adapter(request_adapter_options).await
-        })
-        .ok_or(anyhow!("adapter could not be obtained"))?;
+        log::info!("Using adapter {:?}", adapter.get_info());
+
+        // Timestamp queries are optional, see `set_gpu_timing_enabled()`.
+        let features = wgpu::Features::SPIRV_SHADER_PASSTHROUGH
+            | (adapter.features() & wgpu::Features::TIMESTAMP_QUERY);
 
         let request_device_descriptor = &wgpu::DeviceDescriptor {
-            features: wgpu::Features::SPIRV_SHADER_PASSTHROUGH,
+            features,
             limits: Default::default(),
             label: Some("Default Device"),
         };
@@ -76,45 +133,110 @@ impl WgpuState {
                 .ok_or(anyhow!("Preferred format error"))?,
             width: window_inner_size.width,
             height: window_inner_size.height,
-            present_mode: wgpu::PresentMode::Fifo, // DOes FIFO work now?
+            present_mode: config.present_mode.to_wgpu(),
         };
 
         log::trace!("Preferred format {:#?}", surface_cfg.format);
 
-        surface.configure(&device, &surface_cfg);
+        let mut surfaces = HashMap::new();
+        surfaces.insert(
+            window_key,
+            WindowSurface::new(surface, &device, surface_cfg),
+        );
 
         let render_pipelines = Arena::new();
+        let compute_pipelines = Arena::new();
         let textures = Arena::new();
         let bind_groups = Arena::new();
 
         Ok(Self {
-            surface,
+            instance,
             adapter,
             device,
             queue,
-            window_inner_size,
+            surfaces,
+            main_window: window_key,
             render_pipelines,
+            compute_pipelines,
             textures,
             bind_groups,
-            surface_cfg,
+            msaa_samples: config.msaa_samples,
+            stats: StatsCounters::default(),
+            last_stats: RenderStats::default(),
+            gpu_timer: None,
         })
     }
 
-    pub(super) fn resize(&mut self, new_inner_size: PhysicalSize<u32>) {
+    /// The surface of the main window. Pipelines and render targets use its format.
+    pub(super) fn main_surface(&self) -> &WindowSurface {
+        &self.surfaces[&self.main_window]
+    }
+
+    /// Creates the surface of a window opened after the rendering system was initialized. It
+    /// uses the same format and present mode as the main window's surface.
+    pub(super) fn add_surface(
+        &mut self,
+        window: &winit::window::Window,
+        window_key: ArenaKey,
+    ) -> AResult<()> {
+        let window_inner_size = window.inner_size();
+
+        // Safety: the surface is removed (see `remove_surface()`) before the window is dropped.
+        let surface = unsafe { self.instance.create_surface(window) };
+
+        if surface.get_preferred_format(&self.adapter).is_none() {
+            return Err(anyhow!("The adapter cannot present to the new window"));
+        }
+
+        let cfg = wgpu::SurfaceConfiguration {
+            width: window_inner_size.width,
+            height: window_inner_size.height,
+            ..self.main_surface().cfg.clone()
+        };
+
+        let window_surface = WindowSurface::new(surface, &self.device, cfg);
+        self.surfaces.insert(window_key, window_surface);
+
+        Ok(())
+    }
+
+    pub(super) fn remove_surface(&mut self, window_key: ArenaKey) {
+        self.surfaces.remove(&window_key);
+    }
+
+    pub(super) fn resize(&mut self, window_key: ArenaKey, new_inner_size: PhysicalSize<u32>) {
         log::trace!("Resizing (WgpuState)");
-        assert!(new_inner_size.width > 0 && new_inner_size.height > 0);
 
-        self.window_inner_size = new_inner_size;
-        self.surface_cfg.width = new_inner_size.width;
-        self.surface_cfg.height = new_inner_size.height;
+        if let Some(surface) = self.surfaces.get_mut(&window_key) {
+            surface.resize(&self.device, new_inner_size);
+        }
+    }
 
-        self.surface.configure(&self.device, &self.surface_cfg);
+    /// Applies to the surfaces of every window.
+    pub(super) fn set_present_mode(&mut self, present_mode: PresentMode) {
+        for surface in self.surfaces.values_mut() {
+            surface.set_present_mode(&self.device, present_mode.to_wgpu());
+        }
     }
 
     /// Returns a `FrameRenderTarget`, which will be used for rendering and must be
     /// given back to complete_frame_render().
-    pub(super) fn start_frame_render(&mut self) -> anyhow::Result<FrameRenderTarget> {
-        let frame = self.surface.get_current_frame()?;
+    ///
+    /// Returns `None` if the frame should be skipped, either because the window is minimized, or
+    /// isn't open (yet, or anymore), or because no surface texture could be obtained.
+    pub(super) fn start_frame_render(
+        &mut self,
+        window_key: ArenaKey,
+    ) -> AResult<Option<FrameRenderTarget>> {
+        let surface = match self.surfaces.get_mut(&window_key) {
+            Some(surface) if !surface.is_minimized() => surface,
+            _ => return Ok(None),
+        };
+
+        let frame = match surface.acquire_frame(&self.device)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
         let view = frame
             .output
             .texture
@@ -125,65 +247,149 @@ impl WgpuState {
                 label: Some("wgpu renderer encoder"),
             });
 
+        let msaa_view = if self.msaa_samples > 1 {
+            Some(surface.take_msaa_view(&mut self.device, self.msaa_samples))
+        } else {
+            None
+        };
+
         let mut frt = FrameRenderTarget {
             frame,
             view,
+            msaa_view,
             encoder,
+            window: window_key,
+            width: surface.cfg.width,
+            height: surface.cfg.height,
         };
 
         // By default, clear the screen at the start of a frame.
-        self.make_clear_render_pass(&mut frt);
+        self.make_clear_render_pass(&mut frt.target(), DEFAULT_CLEAR_COLOR);
+
+        Ok(Some(frt))
+    }
+
+    /// Begins a render pass that clears `target` to `color`. The pass is immediately dropped,
+    /// so nothing else is recorded in it.
+    pub(super) fn make_clear_render_pass<'a>(
+        &'a self,
+        target: &'a mut RenderTarget<'_>,
+        color: wgpu::Color,
+    ) -> RenderPass<'a> {
+        self.begin_render_pass(target, wgpu::LoadOp::Clear(color))
+    }
 
-        Ok(frt)
+    /// Begins a render pass that draws on top of whatever `target` already contains.
+    pub(super) fn make_render_pass<'a>(
+        &'a self,
+        target: &'a mut RenderTarget<'_>,
+    ) -> RenderPass<'a> {
+        self.begin_render_pass(target, wgpu::LoadOp::Load)
     }
 
-    fn make_clear_render_pass<'a>(&'a self, frt: &'a mut FrameRenderTarget) -> RenderPass<'a> {
+    fn begin_render_pass<'a>(
+        &'a self,
+        target: &'a mut RenderTarget<'_>,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> RenderPass<'a> {
+        // With MSAA, we draw to the multisampled view, and resolve it into the target's view at
+        // the end of every pass.
+        let (view, resolve_target): (&'a wgpu::TextureView, _) = match target.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(target.view)),
+            None => (target.view, None),
+        };
+
         let render_pass_descriptor = &wgpu::RenderPassDescriptor {
             label: Some("wgpu render pass"),
             color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: &frt.view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
+                view,
+                resolve_target,
+                ops: wgpu::Operations { load, store: true },
             }],
             depth_stencil_attachment: None,
         };
-        let render_pass = frt.encoder.begin_render_pass(render_pass_descriptor);
+        let render_pass = target.encoder.begin_render_pass(render_pass_descriptor);
+
+        self.stats.add_render_pass();
 
         RenderPass {
             wgpu_render_pass: render_pass,
+            stats: &self.stats,
         }
     }
 
-    pub(super) fn make_render_pass<'a>(&'a self, frt: &'a mut FrameRenderTarget) -> RenderPass<'a> {
-        let render_pass_descriptor = &wgpu::RenderPassDescriptor {
-            label: Some("wgpu render pass"),
-            color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: &frt.view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: None,
-        };
-        let render_pass = frt.encoder.begin_render_pass(render_pass_descriptor);
+    pub(super) fn complete_frame_render(&mut self, mut frt: FrameRenderTarget) {
+        if let Some(timer) = &self.gpu_timer {
+            timer.resolve(&mut frt.encoder);
+        }
 
-        RenderPass {
-            wgpu_render_pass: render_pass,
+        if let (Some(surface), Some(msaa_view)) =
+            (self.surfaces.get_mut(&frt.window), frt.msaa_view.take())
+        {
+            surface.give_back_msaa_view(msaa_view, frt.width, frt.height);
+        }
+
+        self.queue.submit(std::iter::once(frt.encoder.finish()));
+
+        self.last_stats = self.stats.take();
+
+        if let Some(timer) = &mut self.gpu_timer {
+            self.last_stats.pass_timings =
+                timer.read(&self.device, self.queue.get_timestamp_period());
         }
     }
 
-    pub(super) fn complete_frame_render(&mut self, frt: FrameRenderTarget) {
-        self.queue.submit(std::iter::once(frt.encoder.finish()))
+    /// Whether the device supports timestamp queries, needed to time passes on the GPU.
+    pub(super) fn gpu_timing_supported(&self) -> bool {
+        self.device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+    }
+
+    /// Does nothing if timing isn't supported.
+    pub(super) fn set_gpu_timing_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.gpu_timer = None;
+        } else if self.gpu_timer.is_none() && self.gpu_timing_supported() {
+            self.gpu_timer = Some(GpuTimer::new(&self.device, INITIAL_TIMED_PASSES));
+        }
+    }
+
+    /// Makes room in the GPU timer, if timing is enabled, for `passes` more passes this frame.
+    ///
+    /// The timer can only grow before any pass of the frame is timed. Passes that don't fit are
+    /// not timed.
+    pub(super) fn reserve_gpu_timer(&mut self, passes: usize) {
+        let new_capacity = match &self.gpu_timer {
+            Some(timer) if timer.timed_passes() == 0 && passes > timer.capacity() as usize => {
+                (passes as u32).max(2 * timer.capacity())
+            }
+            _ => return,
+        };
+
+        self.gpu_timer = Some(GpuTimer::new(&self.device, new_capacity));
+    }
+
+    /// Wrapper around `wgpu::Queue::write_buffer()`, which counts the uploaded bytes in the
+    /// frame's stats.
+    pub(super) fn write_buffer(
+        &self,
+        buffer: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        data: &[u8],
+    ) {
+        self.stats.add_upload(data.len() as u64);
+        self.queue.write_buffer(buffer, offset, data);
+    }
+
+    /// Wrapper around `wgpu::util::DeviceExt::create_buffer_init()`, which counts the uploaded
+    /// bytes in the frame's stats.
+    pub(super) fn create_buffer_init(
+        &self,
+        descriptor: &wgpu::util::BufferInitDescriptor,
+    ) -> wgpu::Buffer {
+        self.stats.add_upload(descriptor.contents.len() as u64);
+        self.device.create_buffer_init(descriptor)
     }
 
     pub(super) fn add_new_render_pipeline(
@@ -192,6 +398,7 @@ impl WgpuState {
         frag_shader: &crate::rendering::shaders::Shader,
         bind_group_layouts: &[&wgpu::BindGroupLayout],
         vertex_buffers: Vec<VertexBufferSetting>,
+        blend_state: wgpu::BlendState,
     ) -> AResult<ArenaKey> {
         let vert_shader_desc = wgpu::ShaderModuleDescriptorSpirV {
             label: Some("Vert Shader"),
@@ -229,13 +436,49 @@ impl WgpuState {
             .set_vertex_shader(&vert_shader_module)
             .set_fragment_shader(&frag_shader_module)
             .set_pipeline_layout(&render_pipeline_layout)
-            .set_texture_format(self.surface_cfg.format)
+            .set_texture_format(self.main_surface().cfg.format)
             .set_vertex_buffers(vertex_buffers)
+            .set_blend_state(blend_state)
+            .set_sample_count(self.msaa_samples)
             .build(&mut self.device);
 
         Ok(self.render_pipelines.insert(render_pipeline?))
     }
 
+    pub(super) fn add_new_compute_pipeline(
+        &mut self,
+        compute_shader: &crate::rendering::shaders::Shader,
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> AResult<ArenaKey> {
+        let compute_shader_desc = wgpu::ShaderModuleDescriptorSpirV {
+            label: Some("Compute Shader"),
+            source: compute_shader.as_ref().into(),
+        };
+
+        // Safety: Bad spirV data may cause UB.
+        let compute_shader_module =
+            unsafe { self.device.create_shader_module_spirv(&compute_shader_desc) };
+
+        let compute_pipeline_layout =
+            self.device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("wgpu compute pipeline layout"),
+                    push_constant_ranges: &[],
+                    bind_group_layouts,
+                });
+
+        let compute_pipeline =
+            self.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("wgpu compute pipeline"),
+                    layout: Some(&compute_pipeline_layout),
+                    module: &compute_shader_module,
+                    entry_point: "main",
+                });
+
+        Ok(self.compute_pipelines.insert(compute_pipeline))
+    }
+
     /// See `WgpuTexture` for info on the format of the bytes.
     pub(crate) fn add_new_texture_from_bytes(
         &mut self,
@@ -250,6 +493,72 @@ impl WgpuState {
         self.textures.insert(texture)
     }
 
+    /// Like `add_new_texture_from_bytes()`, but for an already decoded image. See `WgpuTexture`
+    /// for info on the supported formats.
+    pub(crate) fn add_new_texture_from_image(
+        &mut self,
+        img: image::DynamicImage,
+        label: Option<&'static str>,
+    ) -> ArenaKey {
+        let (texture, cmd_buf) = WgpuTexture::new_from_image(&mut self.device, img, label).unwrap();
+
+        self.queue.submit(std::iter::once(cmd_buf));
+
+        self.textures.insert(texture)
+    }
+
+    /// Adds a new, fully transparent, texture, meant to be filled in later using
+    /// `write_texture_region()`.
+    pub(crate) fn add_new_empty_texture(
+        &mut self,
+        width: u32,
+        height: u32,
+        label: Option<&'static str>,
+    ) -> ArenaKey {
+        let texture = WgpuTexture::new_empty(&mut self.device, width, height, label);
+
+        self.textures.insert(texture)
+    }
+
+    /// Overwrites a region of a texture created by `add_new_empty_texture()` with rgba8 data
+    /// (`4 * width * height` bytes, rows top to bottom).
+    pub(crate) fn write_texture_region(
+        &mut self,
+        texture_key: ArenaKey,
+        origin: (u32, u32),
+        size: (u32, u32),
+        rgba_data: &[u8],
+    ) {
+        let texture = self
+            .textures
+            .get(texture_key)
+            .expect("The texture doesn't exist");
+
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin.0,
+                    y: origin.1,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            rgba_data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(4 * size.0),
+                rows_per_image: std::num::NonZeroU32::new(size.1),
+            },
+            wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
     pub(crate) fn add_new_texture_bind_group(
         &mut self,
         bind_group_layout: &wgpu::BindGroupLayout,
@@ -302,6 +611,8 @@ impl WgpuState {
             });
 
         uniform.update_buffer(&mut enc, &mut self.device);
+        self.stats
+            .add_upload(std::mem::size_of::<T::Content>() as u64);
 
         self.queue.submit(std::iter::once(enc.finish()));
     }
@@ -309,6 +620,7 @@ impl WgpuState {
 
 pub(crate) struct RenderPass<'a> {
     pub(super) wgpu_render_pass: wgpu::RenderPass<'a>,
+    stats: &'a StatsCounters,
 }
 
 impl<'a> RenderPass<'a> {
@@ -326,12 +638,24 @@ impl<'a> RenderPass<'a> {
             .get(bind_group_key)
             .ok_or("Bind group doesn't exist")?;
 
-        self.wgpu_render_pass
-            .set_bind_group(index, &bind_group.wgpu_bind_group, offsets);
+        self.set_wgpu_bind_group(index, &bind_group.wgpu_bind_group, offsets);
 
         Ok(())
     }
 
+    /// Wrapper around `wgpu::RenderPass::set_bind_group()`, for bind groups that aren't stored
+    /// in `WgpuState` (e.g. the inputs of a render graph pass).
+    pub(crate) fn set_wgpu_bind_group(
+        &mut self,
+        index: u32,
+        bind_group: &'a wgpu::BindGroup,
+        offsets: &[wgpu::DynamicOffset],
+    ) {
+        self.stats.add_bind_group_switch();
+        self.wgpu_render_pass
+            .set_bind_group(index, bind_group, offsets);
+    }
+
     /// Wrapper around `wgpu::RenderPass::set_pipeline()` but using an `ArenaKey`
     /// to index into the pipeline arena located in `WgpuState`.
     pub(super) fn set_pipeline(
@@ -344,10 +668,29 @@ impl<'a> RenderPass<'a> {
             .get(pipeline_key)
             .ok_or("Pipeline doesn't exist")?;
 
+        self.stats.add_pipeline_switch();
         self.wgpu_render_pass.set_pipeline(pipeline);
 
         Ok(())
     }
+
+    /// Wrapper around `wgpu::RenderPass::draw()`, counted in the frame's stats.
+    pub(super) fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+        self.stats.add_draw_call(instances.end - instances.start);
+        self.wgpu_render_pass.draw(vertices, instances);
+    }
+
+    /// Wrapper around `wgpu::RenderPass::draw_indexed()`, counted in the frame's stats.
+    pub(super) fn draw_indexed(
+        &mut self,
+        indices: Range<u32>,
+        base_vertex: i32,
+        instances: Range<u32>,
+    ) {
+        self.stats.add_draw_call(instances.end - instances.start);
+        self.wgpu_render_pass
+            .draw_indexed(indices, base_vertex, instances);
+    }
 }
 
 // TODO: Is it even worth it keeping this type around? Also, If I'm gonna have this,
@@ -7,7 +7,6 @@ use wgpu::util::DeviceExt;
 // See https://sotrh.github.io/learn-wgpu/beginner/tutorial5-textures/
 
 pub(crate) struct WgpuTexture {
-    #[allow(dead_code)]
     pub(crate) texture: wgpu::Texture,
     pub(crate) texture_view: wgpu::TextureView,
     pub(crate) sampler: wgpu::Sampler,
@@ -117,6 +116,119 @@ impl WgpuTexture {
             cmd_buffer,
         ))
     }
+
+    /// Makes an empty (fully transparent) rgba8 texture, that may be written to using
+    /// `wgpu::Queue::write_texture()`, such as a glyph atlas.
+    pub(crate) fn new_empty(
+        wgpu_device: &mut wgpu::Device,
+        width: u32,
+        height: u32,
+        label: Option<&'static str>,
+    ) -> Self {
+        let texture = wgpu_device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = wgpu_device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            texture_view,
+            sampler,
+        }
+    }
+
+    /// Makes a multisampled texture that may only be rendered to, and then resolved to a
+    /// single sampled one, see `RenderingConfig::msaa_samples`. Only the view is returned, as
+    /// that's all that is needed to render, and it keeps the texture alive.
+    pub(crate) fn new_multisampled_view(
+        wgpu_device: &mut wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        label: Option<&'static str>,
+    ) -> wgpu::TextureView {
+        wgpu_device
+            .create_texture(&wgpu::TextureDescriptor {
+                label,
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Makes an empty texture that may be both rendered to and sampled from, such as the
+    /// transient targets of a `RenderGraph`.
+    pub(crate) fn new_render_target(
+        wgpu_device: &mut wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        label: Option<&'static str>,
+    ) -> Self {
+        let texture = wgpu_device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        });
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Render targets are usually sampled at (or close to) 1:1, so linear filtering on both
+        // ends is fine, and gives better results when sampling at a different resolution.
+        let sampler = wgpu_device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            texture_view,
+            sampler,
+        }
+    }
 }
 
 impl crate::rendering::bind_group::BindGroupable for WgpuTexture {
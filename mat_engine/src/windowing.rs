@@ -1,4 +1,13 @@
-use crate::utils::unwrap_mut;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use anyhow::Result as AResult;
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition};
+
+use crate::arena::{Arena, ArenaKey};
+use crate::config::WindowConfig;
+use crate::utils::{unwrap_mut, unwrap_ref};
 
 /// Sends a quit request to winit's event loop. This will, (possibly after a delay, as
 /// queued events will still be processed), cause the loop to exit. `Application::close()`
@@ -28,35 +37,242 @@ pub(crate) fn make_winit_event_loop_proxy(
     winit_event_loop.create_proxy()
 }
 
-pub(crate) fn make_default_winit_window(
-    winit_event_loop: &winit::event_loop::EventLoop<Request>,
-) -> winit::window::Window {
-    winit::window::WindowBuilder::new()
-        .with_title("Sample Application")
-        .build(&winit_event_loop)
-        .expect("Could not obtain winit window")
+/// Opens a new window, at the start of the next frame. Returns its key, which may be used with the
+/// functions of this module suffixed with `_of`, `rendering::start_window_render()`, and the
+/// window scoped input functions.
+///
+/// Other functions of this module act on the main window, the one `EngineConfig::window` describes.
+///
+/// Closing the window (from its title bar, for example) closes it right away, unlike closing the
+/// main window, which quits. See `is_window_open()`.
+///
+/// Note: imgui is only drawn to the main window.
+///
+/// This is a wrapper method.
+pub fn open_window(ctx: &mut crate::EngineContext, config: WindowConfig) -> ArenaKey {
+    unwrap_mut(&mut ctx.windowing_system).open_window(config)
+}
+
+/// Closes a window opened with `open_window()`. The main window can't be closed, see
+/// `queue_quit()` instead.
+pub fn close_window(ctx: &mut crate::EngineContext, window: ArenaKey) {
+    if window == main_window(ctx) {
+        log::warn!("The main window can't be closed, quit instead");
+        return;
+    }
+
+    // The surface must not outlive the window.
+    unwrap_mut(&mut ctx.rendering_system).remove_window_surface(window);
+    unwrap_mut(&mut ctx.input_system).forget_window(window);
+    unwrap_mut(&mut ctx.windowing_system).close_window(window);
+}
+
+/// Whether a window was opened (which happens at the start of the frame after `open_window()` is
+/// called), and not closed since.
+///
+/// This is a wrapper method.
+pub fn is_window_open(ctx: &crate::EngineContext, window: ArenaKey) -> bool {
+    unwrap_ref(&ctx.windowing_system)
+        .window_ref(window)
+        .is_some()
+}
+
+/// This is a wrapper method.
+pub fn main_window(ctx: &crate::EngineContext) -> ArenaKey {
+    unwrap_ref(&ctx.windowing_system).main_window()
+}
+
+/// Creates the windows `open_window()` was called for, and their surfaces. Called at the start of
+/// every frame, since windows can only be created by the event loop.
+pub(crate) fn open_pending_windows(
+    ctx: &mut crate::EngineContext,
+    window_target: &winit::event_loop::EventLoopWindowTarget<Request>,
+) {
+    let opened = unwrap_mut(&mut ctx.windowing_system).open_pending_windows(window_target);
+
+    for window in opened {
+        let winit_window = unwrap_ref(&ctx.windowing_system)
+            .window_ref(window)
+            .expect("Just opened");
+
+        if let Err(e) =
+            unwrap_mut(&mut ctx.rendering_system).add_window_surface(winit_window, window)
+        {
+            log::error!("Could not render to new window, closing it: {}", e);
+            close_window(ctx, window);
+        }
+    }
+}
+
+pub(crate) fn make_winit_window(
+    window_target: &winit::event_loop::EventLoopWindowTarget<Request>,
+    config: &WindowConfig,
+) -> AResult<winit::window::Window> {
+    let mut builder = winit::window::WindowBuilder::new()
+        .with_title(&config.title)
+        .with_resizable(config.resizable);
+
+    if let Some((width, height)) = config.size {
+        builder = builder.with_inner_size(winit::dpi::LogicalSize::new(width, height));
+    }
+
+    if config.fullscreen {
+        builder = builder.with_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+    }
+
+    Ok(builder.build(window_target)?)
+}
+
+/// A window in `WindowingSystem::windows`.
+enum WindowSlot {
+    /// Waiting to be created by the event loop, see `open_window()`.
+    Pending(WindowConfig),
+    Open(winit::window::Window),
 }
 
 pub struct WindowingSystem {
-    pub(crate) winit_window: winit::window::Window,
+    windows: Arena<WindowSlot>,
+    main_window: ArenaKey,
+    /// Windows waiting to be created, in the order they were opened.
+    pending_windows: Vec<ArenaKey>,
+    /// Keys of the open windows, by winit id, to route window events.
+    window_keys: HashMap<winit::window::WindowId, ArenaKey>,
     pub(crate) winit_event_loop_proxy: winit::event_loop::EventLoopProxy<Request>,
     pub(crate) force_quit: bool,
+    /// Cursor settings of each window. Windows whose cursor was never changed have no entry.
+    cursor_settings: HashMap<ArenaKey, CursorSettings>,
+}
+
+/// Cursor visibility and grab set by the application for a window, which relative mouse mode
+/// overrides while enabled.
+#[derive(Copy, Clone, Debug)]
+struct CursorSettings {
+    visible: bool,
+    grabbed: bool,
+    relative_mouse_mode: bool,
 }
 
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            grabbed: false,
+            relative_mouse_mode: false,
+        }
+    }
+}
+
+const MAIN_WINDOW_IS_OPEN: &str =
+    "The main window is opened with the windowing system, and never closed";
+
 impl WindowingSystem {
     pub(crate) fn new(
         winit_window: winit::window::Window,
         winit_event_loop_proxy: winit::event_loop::EventLoopProxy<Request>,
     ) -> Self {
+        let winit_id = winit_window.id();
+
+        let mut windows = Arena::new();
+        let main_window = windows.insert(WindowSlot::Open(winit_window));
+
+        let mut window_keys = HashMap::new();
+        window_keys.insert(winit_id, main_window);
+
         Self {
-            winit_window,
+            windows,
+            main_window,
+            pending_windows: vec![],
+            window_keys,
             winit_event_loop_proxy,
             force_quit: false,
+            cursor_settings: HashMap::new(),
         }
     }
 
+    /// The main window.
     pub(crate) fn get_window_ref(&self) -> &winit::window::Window {
-        &self.winit_window
+        self.window_ref(self.main_window)
+            .expect(MAIN_WINDOW_IS_OPEN)
+    }
+
+    /// `None` if the window isn't open.
+    pub(crate) fn window_ref(&self, window: ArenaKey) -> Option<&winit::window::Window> {
+        match self.windows.get(window) {
+            Some(WindowSlot::Open(winit_window)) => Some(winit_window),
+            _ => None,
+        }
+    }
+
+    /// Like `window_ref()`, but warns about windows that aren't open yet, as most settings can
+    /// only be changed once they are.
+    fn window_to_change(&self, window: ArenaKey) -> Option<&winit::window::Window> {
+        if let Some(WindowSlot::Pending(_)) = self.windows.get(window) {
+            log::warn!("Ignoring a setting of a window that isn't open yet");
+        }
+        self.window_ref(window)
+    }
+
+    /// Like `window_to_change()`, for settings that can fail.
+    fn window_to_change_or_err(&self, window: ArenaKey) -> AResult<&winit::window::Window> {
+        self.window_to_change(window)
+            .ok_or_else(|| anyhow!("The window isn't open"))
+    }
+
+    /// See wrapper method `windowing::main_window()`.
+    pub(crate) fn main_window(&self) -> ArenaKey {
+        self.main_window
+    }
+
+    /// Key of the open window with id `winit_id`, if any.
+    pub(crate) fn window_key(&self, winit_id: winit::window::WindowId) -> Option<ArenaKey> {
+        self.window_keys.get(&winit_id).copied()
+    }
+
+    /// See wrapper method.
+    fn open_window(&mut self, config: WindowConfig) -> ArenaKey {
+        let window = self.windows.insert(WindowSlot::Pending(config));
+        self.pending_windows.push(window);
+        window
+    }
+
+    /// See `windowing::open_pending_windows()`. Returns the windows that were opened. Windows that
+    /// couldn't be are forgotten.
+    fn open_pending_windows(
+        &mut self,
+        window_target: &winit::event_loop::EventLoopWindowTarget<Request>,
+    ) -> Vec<ArenaKey> {
+        let mut opened = vec![];
+
+        for window in std::mem::take(&mut self.pending_windows) {
+            let config = match self.windows.get(window) {
+                Some(WindowSlot::Pending(config)) => config,
+                _ => continue,
+            };
+
+            match make_winit_window(window_target, config) {
+                Ok(winit_window) => {
+                    self.window_keys.insert(winit_window.id(), window);
+                    *self.windows.get_mut_unwrap(window) = WindowSlot::Open(winit_window);
+                    opened.push(window);
+                }
+                Err(e) => {
+                    log::error!("Could not open window: {}", e);
+                    self.windows.remove(window);
+                }
+            }
+        }
+
+        opened
+    }
+
+    /// See wrapper method.
+    fn close_window(&mut self, window: ArenaKey) {
+        self.pending_windows.retain(|&pending| pending != window);
+        self.cursor_settings.remove(&window);
+
+        if let Some(WindowSlot::Open(winit_window)) = self.windows.remove(window) {
+            self.window_keys.remove(&winit_window.id());
+        }
     }
 
     /// See wrapper method `windowing::queue_quit()`.
@@ -70,6 +286,424 @@ impl WindowingSystem {
     fn force_quit(&mut self) {
         self.force_quit = true;
     }
+
+    /// See wrapper method.
+    fn set_title_of(&mut self, window: ArenaKey, title: &str) {
+        match self.windows.get_mut(window) {
+            Some(WindowSlot::Open(winit_window)) => winit_window.set_title(title),
+            Some(WindowSlot::Pending(config)) => config.title = String::from(title),
+            None => {}
+        }
+    }
+
+    /// See wrapper method.
+    fn set_size_of(&mut self, window: ArenaKey, width: u32, height: u32) {
+        match self.windows.get_mut(window) {
+            Some(WindowSlot::Open(winit_window)) => {
+                winit_window.set_inner_size(LogicalSize::new(width, height))
+            }
+            Some(WindowSlot::Pending(config)) => config.size = Some((width, height)),
+            None => {}
+        }
+    }
+
+    /// See wrapper method.
+    fn size_of(&self, window: ArenaKey) -> Option<(u32, u32)> {
+        self.window_ref(window).map(|winit_window| {
+            let size: LogicalSize<u32> = winit_window
+                .inner_size()
+                .to_logical(winit_window.scale_factor());
+            (size.width, size.height)
+        })
+    }
+
+    /// See wrapper method.
+    fn physical_size_of(&self, window: ArenaKey) -> Option<(u32, u32)> {
+        self.window_ref(window).map(|winit_window| {
+            let size = winit_window.inner_size();
+            (size.width, size.height)
+        })
+    }
+
+    /// See wrapper method.
+    fn scale_factor_of(&self, window: ArenaKey) -> Option<f64> {
+        self.window_ref(window)
+            .map(|winit_window| winit_window.scale_factor())
+    }
+
+    /// See wrapper method.
+    fn set_position_of(&mut self, window: ArenaKey, x: i32, y: i32) {
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_outer_position(LogicalPosition::new(x, y));
+        }
+    }
+
+    /// See wrapper method.
+    fn set_min_size_of(&mut self, window: ArenaKey, size: Option<(u32, u32)>) {
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_min_inner_size(size.map(|(w, h)| LogicalSize::new(w, h)));
+        }
+    }
+
+    /// See wrapper method.
+    fn set_max_size_of(&mut self, window: ArenaKey, size: Option<(u32, u32)>) {
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_max_inner_size(size.map(|(w, h)| LogicalSize::new(w, h)));
+        }
+    }
+
+    /// See wrapper method.
+    fn set_fullscreen_of(&mut self, window: ArenaKey, fullscreen: Fullscreen) {
+        let winit_window = match self.windows.get_mut(window) {
+            Some(WindowSlot::Open(winit_window)) => winit_window,
+            Some(WindowSlot::Pending(config)) => {
+                // Windows can only be opened borderless fullscreen.
+                config.fullscreen = fullscreen != Fullscreen::Windowed;
+                return;
+            }
+            None => return,
+        };
+
+        let winit_fullscreen = match fullscreen {
+            Fullscreen::Windowed => None,
+            Fullscreen::Borderless => Some(winit::window::Fullscreen::Borderless(None)),
+            Fullscreen::Exclusive => {
+                let video_mode = winit_window.current_monitor().and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|mode| {
+                        (mode.size().width * mode.size().height, mode.refresh_rate())
+                    })
+                });
+
+                match video_mode {
+                    Some(video_mode) => Some(winit::window::Fullscreen::Exclusive(video_mode)),
+                    None => {
+                        log::warn!(
+                            "No video mode available for exclusive fullscreen, \
+                             using borderless fullscreen instead"
+                        );
+                        Some(winit::window::Fullscreen::Borderless(None))
+                    }
+                }
+            }
+        };
+
+        winit_window.set_fullscreen(winit_fullscreen);
+    }
+
+    /// See wrapper method.
+    fn fullscreen_of(&self, window: ArenaKey) -> Option<Fullscreen> {
+        self.window_ref(window)
+            .map(|winit_window| match winit_window.fullscreen() {
+                None => Fullscreen::Windowed,
+                Some(winit::window::Fullscreen::Borderless(_)) => Fullscreen::Borderless,
+                Some(winit::window::Fullscreen::Exclusive(_)) => Fullscreen::Exclusive,
+            })
+    }
+
+    /// See wrapper method.
+    fn set_decorations_of(&mut self, window: ArenaKey, decorations: bool) {
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_decorations(decorations);
+        }
+    }
+
+    /// See wrapper method.
+    fn set_always_on_top_of(&mut self, window: ArenaKey, always_on_top: bool) {
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_always_on_top(always_on_top);
+        }
+    }
+
+    /// See wrapper method.
+    fn set_icon_from_file_of(&mut self, window: ArenaKey, path: PathBuf) -> AResult<()> {
+        let winit_window = self.window_to_change_or_err(window)?;
+
+        let bytes = crate::assets::read_file_at_path_to_bytes(path.clone())
+            .map_err(|e| anyhow!("Cannot read icon {:?}: {}", path, e))?;
+
+        let image = image::load_from_memory(&bytes)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        let icon = winit::window::Icon::from_rgba(image.into_raw(), width, height)?;
+
+        winit_window.set_window_icon(Some(icon));
+
+        Ok(())
+    }
+
+    /// See wrapper method.
+    fn remove_icon_of(&mut self, window: ArenaKey) {
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_window_icon(None);
+        }
+    }
+
+    fn cursor_settings_of(&self, window: ArenaKey) -> CursorSettings {
+        self.cursor_settings
+            .get(&window)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// See wrapper method `input::cursor::set_cursor_visible_of()`.
+    pub(crate) fn set_cursor_visible_of(&mut self, window: ArenaKey, visible: bool) {
+        let mut settings = self.cursor_settings_of(window);
+        settings.visible = visible;
+
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_cursor_visible(visible && !settings.relative_mouse_mode);
+            self.cursor_settings.insert(window, settings);
+        }
+    }
+
+    /// See wrapper method `input::cursor::set_cursor_grabbed_of()`.
+    pub(crate) fn set_cursor_grabbed_of(&mut self, window: ArenaKey, grabbed: bool) -> AResult<()> {
+        let mut settings = self.cursor_settings_of(window);
+        settings.grabbed = grabbed;
+
+        self.window_to_change_or_err(window)?
+            .set_cursor_grab(grabbed || settings.relative_mouse_mode)
+            .map_err(|e| anyhow!("Could not grab the cursor: {}", e))?;
+        self.cursor_settings.insert(window, settings);
+
+        Ok(())
+    }
+
+    /// See wrapper method `input::cursor::set_relative_mouse_mode_of()`.
+    pub(crate) fn set_relative_mouse_mode_of(
+        &mut self,
+        window: ArenaKey,
+        enabled: bool,
+    ) -> AResult<()> {
+        let mut settings = self.cursor_settings_of(window);
+        settings.relative_mouse_mode = enabled;
+
+        let winit_window = self.window_to_change_or_err(window)?;
+        winit_window
+            .set_cursor_grab(enabled || settings.grabbed)
+            .map_err(|e| anyhow!("Could not grab the cursor: {}", e))?;
+        winit_window.set_cursor_visible(settings.visible && !enabled);
+        self.cursor_settings.insert(window, settings);
+
+        Ok(())
+    }
+
+    /// See wrapper method `input::cursor::is_relative_mouse_mode_of()`.
+    pub(crate) fn is_relative_mouse_mode_of(&self, window: ArenaKey) -> bool {
+        self.cursor_settings_of(window).relative_mouse_mode
+    }
+
+    /// See wrapper method `input::cursor::set_cursor_position_of()`.
+    pub(crate) fn set_cursor_position_of(
+        &mut self,
+        window: ArenaKey,
+        x: f64,
+        y: f64,
+    ) -> AResult<()> {
+        self.window_to_change_or_err(window)?
+            .set_cursor_position(PhysicalPosition::new(x, y))
+            .map_err(|e| anyhow!("Could not move the cursor: {}", e))
+    }
+
+    /// See wrapper method `input::cursor::set_cursor_icon_of()`.
+    pub(crate) fn set_cursor_icon_of(
+        &mut self,
+        window: ArenaKey,
+        icon: crate::input::cursor::CursorIcon,
+    ) {
+        if let Some(winit_window) = self.window_to_change(window) {
+            winit_window.set_cursor_icon(icon.to_winit());
+        }
+    }
+
+    /// The OS releases cursor grabs when a window loses focus, restore it once it is regained.
+    pub(crate) fn receive_focus_change(&mut self, window: ArenaKey, focused: bool) {
+        let settings = self.cursor_settings_of(window);
+        if !focused || !(settings.grabbed || settings.relative_mouse_mode) {
+            return;
+        }
+
+        if let Some(winit_window) = self.window_ref(window) {
+            if let Err(e) = winit_window.set_cursor_grab(true) {
+                log::warn!(
+                    "Could not grab the cursor again after regaining focus: {}",
+                    e
+                );
+            }
+        }
+    }
+}
+
+// Unless stated otherwise, sizes and positions given to the functions below are in logical
+// pixels (physical pixels divided by the scale factor), like `EngineConfig`'s window size.
+//
+// Functions ending in `_of` act on any window, see `open_window()`, the others on the main
+// window. Settings of windows that aren't open yet are ignored, except for their title, size and
+// fullscreen mode.
+
+pub fn set_title(ctx: &mut crate::EngineContext, title: &str) {
+    set_title_of(ctx, main_window(ctx), title)
+}
+
+/// This is a wrapper method.
+pub fn set_title_of(ctx: &mut crate::EngineContext, window: ArenaKey, title: &str) {
+    unwrap_mut(&mut ctx.windowing_system).set_title_of(window, title)
+}
+
+/// Sets the inner size of the window, i.e. not counting decorations.
+pub fn set_size(ctx: &mut crate::EngineContext, width: u32, height: u32) {
+    set_size_of(ctx, main_window(ctx), width, height)
+}
+
+/// This is a wrapper method.
+pub fn set_size_of(ctx: &mut crate::EngineContext, window: ArenaKey, width: u32, height: u32) {
+    unwrap_mut(&mut ctx.windowing_system).set_size_of(window, width, height)
+}
+
+/// Inner size of the window, as given to `set_size()`. Zero sized while minimized on some
+/// platforms.
+pub fn size(ctx: &crate::EngineContext) -> (u32, u32) {
+    size_of(ctx, main_window(ctx)).expect(MAIN_WINDOW_IS_OPEN)
+}
+
+/// `None` if the window isn't open.
+///
+/// This is a wrapper method.
+pub fn size_of(ctx: &crate::EngineContext, window: ArenaKey) -> Option<(u32, u32)> {
+    unwrap_ref(&ctx.windowing_system).size_of(window)
+}
+
+/// Inner size of the window in physical pixels, which is also the size of the frames rendered
+/// to it. See `size()`.
+pub fn physical_size(ctx: &crate::EngineContext) -> (u32, u32) {
+    physical_size_of(ctx, main_window(ctx)).expect(MAIN_WINDOW_IS_OPEN)
+}
+
+/// `None` if the window isn't open.
+///
+/// This is a wrapper method.
+pub fn physical_size_of(ctx: &crate::EngineContext, window: ArenaKey) -> Option<(u32, u32)> {
+    unwrap_ref(&ctx.windowing_system).physical_size_of(window)
+}
+
+/// Ratio of physical to logical pixels, e.g. 2.0 on "retina" displays.
+pub fn scale_factor(ctx: &crate::EngineContext) -> f64 {
+    scale_factor_of(ctx, main_window(ctx)).expect(MAIN_WINDOW_IS_OPEN)
+}
+
+/// `None` if the window isn't open.
+///
+/// This is a wrapper method.
+pub fn scale_factor_of(ctx: &crate::EngineContext, window: ArenaKey) -> Option<f64> {
+    unwrap_ref(&ctx.windowing_system).scale_factor_of(window)
+}
+
+/// Sets the position of the top left corner of the window, decorations included, on the
+/// desktop. Has no effect on some platforms (e.g. Wayland).
+pub fn set_position(ctx: &mut crate::EngineContext, x: i32, y: i32) {
+    set_position_of(ctx, main_window(ctx), x, y)
+}
+
+/// This is a wrapper method.
+pub fn set_position_of(ctx: &mut crate::EngineContext, window: ArenaKey, x: i32, y: i32) {
+    unwrap_mut(&mut ctx.windowing_system).set_position_of(window, x, y)
+}
+
+/// `None` removes the limit.
+pub fn set_min_size(ctx: &mut crate::EngineContext, size: Option<(u32, u32)>) {
+    set_min_size_of(ctx, main_window(ctx), size)
+}
+
+/// This is a wrapper method.
+pub fn set_min_size_of(ctx: &mut crate::EngineContext, window: ArenaKey, size: Option<(u32, u32)>) {
+    unwrap_mut(&mut ctx.windowing_system).set_min_size_of(window, size)
+}
+
+/// `None` removes the limit.
+pub fn set_max_size(ctx: &mut crate::EngineContext, size: Option<(u32, u32)>) {
+    set_max_size_of(ctx, main_window(ctx), size)
+}
+
+/// This is a wrapper method.
+pub fn set_max_size_of(ctx: &mut crate::EngineContext, window: ArenaKey, size: Option<(u32, u32)>) {
+    unwrap_mut(&mut ctx.windowing_system).set_max_size_of(window, size)
+}
+
+pub fn set_fullscreen(ctx: &mut crate::EngineContext, fullscreen: Fullscreen) {
+    set_fullscreen_of(ctx, main_window(ctx), fullscreen)
+}
+
+/// Windows that aren't open yet will open in borderless fullscreen for both `Borderless` and
+/// `Exclusive`.
+///
+/// This is a wrapper method.
+pub fn set_fullscreen_of(ctx: &mut crate::EngineContext, window: ArenaKey, fullscreen: Fullscreen) {
+    unwrap_mut(&mut ctx.windowing_system).set_fullscreen_of(window, fullscreen)
+}
+
+pub fn fullscreen(ctx: &crate::EngineContext) -> Fullscreen {
+    fullscreen_of(ctx, main_window(ctx)).expect(MAIN_WINDOW_IS_OPEN)
+}
+
+/// `None` if the window isn't open.
+///
+/// This is a wrapper method.
+pub fn fullscreen_of(ctx: &crate::EngineContext, window: ArenaKey) -> Option<Fullscreen> {
+    unwrap_ref(&ctx.windowing_system).fullscreen_of(window)
+}
+
+/// Shows or hides the title bar and borders.
+pub fn set_decorations(ctx: &mut crate::EngineContext, decorations: bool) {
+    set_decorations_of(ctx, main_window(ctx), decorations)
+}
+
+/// This is a wrapper method.
+pub fn set_decorations_of(ctx: &mut crate::EngineContext, window: ArenaKey, decorations: bool) {
+    unwrap_mut(&mut ctx.windowing_system).set_decorations_of(window, decorations)
+}
+
+pub fn set_always_on_top(ctx: &mut crate::EngineContext, always_on_top: bool) {
+    set_always_on_top_of(ctx, main_window(ctx), always_on_top)
+}
+
+/// This is a wrapper method.
+pub fn set_always_on_top_of(ctx: &mut crate::EngineContext, window: ArenaKey, always_on_top: bool) {
+    unwrap_mut(&mut ctx.windowing_system).set_always_on_top_of(window, always_on_top)
+}
+
+/// Sets the window icon from an image file (see `assets::get_folder_assets_path()`), in any
+/// format the `image` crate supports. Has no effect on some platforms (e.g. macOS).
+pub fn set_icon_from_file(ctx: &mut crate::EngineContext, path: PathBuf) -> AResult<()> {
+    set_icon_from_file_of(ctx, main_window(ctx), path)
+}
+
+/// Fails if the window isn't open.
+///
+/// This is a wrapper method.
+pub fn set_icon_from_file_of(
+    ctx: &mut crate::EngineContext,
+    window: ArenaKey,
+    path: PathBuf,
+) -> AResult<()> {
+    unwrap_mut(&mut ctx.windowing_system).set_icon_from_file_of(window, path)
+}
+
+pub fn remove_icon(ctx: &mut crate::EngineContext) {
+    remove_icon_of(ctx, main_window(ctx))
+}
+
+/// This is a wrapper method.
+pub fn remove_icon_of(ctx: &mut crate::EngineContext, window: ArenaKey) {
+    unwrap_mut(&mut ctx.windowing_system).remove_icon_of(window)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fullscreen {
+    Windowed,
+    /// A borderless window covering the monitor the window is on. Switching to and from it is
+    /// quick.
+    Borderless,
+    /// Takes over the monitor the window is on, using its highest resolution and refresh rate.
+    Exclusive,
 }
 
 /// TODO: Refactor into better, more general event system.
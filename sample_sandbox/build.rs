@@ -0,0 +1,19 @@
+//! Copies `sample_sandbox.toml` next to the executable, where `EngineConfig::from_executable_dir()`
+//! looks for it.
+
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-changed=sample_sandbox.toml");
+
+    // OUT_DIR is `target/<profile>/build/<package>-<hash>/out`, and the executable goes in
+    // `target/<profile>`.
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    let exe_dir = out_dir
+        .ancestors()
+        .nth(3)
+        .expect("OUT_DIR is inside the target directory");
+
+    std::fs::copy("sample_sandbox.toml", exe_dir.join("sample_sandbox.toml"))
+        .expect("Could not copy sample_sandbox.toml next to the executable");
+}
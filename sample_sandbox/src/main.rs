@@ -1,9 +1,29 @@
-use mat_engine::{arena::ArenaKey, input::button::ButtonId, rendering::rend_2d::Renderer2d};
+use mat_engine::{
+    arena::ArenaKey,
+    input::button::ButtonId,
+    rendering::{
+        post_processing::{Effect, PostProcessStack},
+        rend_2d::{
+            debug_draw::{DebugDraw, DebugStyle},
+            instance::Instance,
+            shape::{
+                FillOptions, LineCap, LineJoin, Path, ShapeMesh, ShapeRenderer, StrokeOptions,
+            },
+            ColoredVertex2d, Mesh2d, Renderer2d,
+        },
+        render_graph::{RenderGraph, TargetHandle, TransientTargetDesc},
+    },
+};
 
 use nalgebra_glm as glm;
 
 struct MyApp {
     rend_2d: Renderer2d,
+    debug_draw: DebugDraw,
+    shape_renderer: ShapeRenderer,
+    shapes: ShapeMesh,
+    mesh: Mesh2d,
+    post_stack: PostProcessStack,
     tex_key: ArenaKey,
 }
 
@@ -27,7 +47,7 @@ impl mat_engine::event::ApplicationEventReceiver for MyApp {
 
 impl mat_engine::application::Application for MyApp {
     fn new(ctx: &mut mat_engine::context::EngineContext) -> Self {
-        ctx.imgui_init();
+        log::trace!("Starting sample_sandbox");
 
         let mut rend_2d = Renderer2d::new(ctx);
 
@@ -42,7 +62,75 @@ impl mat_engine::application::Application for MyApp {
             Some("Sample Texture"),
         );
 
-        Self { rend_2d, tex_key }
+        let debug_draw = DebugDraw::new(ctx);
+
+        // A hexagon, textured with the sample texture, with a different tint on each vertex
+        let mut hexagon_vertices = vec![ColoredVertex2d {
+            position: [0.0, 0.0],
+            tex_coords: [0.5, 0.5],
+            color: [1.0, 1.0, 1.0, 1.0],
+        }];
+        for i in 0..6 {
+            let angle = i as f32 / 6.0 * std::f32::consts::TAU;
+            let (sin, cos) = angle.sin_cos();
+            hexagon_vertices.push(ColoredVertex2d {
+                position: [cos * 0.5, sin * 0.5],
+                tex_coords: [0.5 + cos * 0.5, 0.5 - sin * 0.5],
+                color: [(i % 2) as f32, ((i + 1) % 3) as f32 / 2.0, 1.0, 0.8],
+            });
+        }
+        let hexagon_indices: Vec<u32> = (0..6)
+            .flat_map(|i| vec![0, i + 1, (i + 1) % 6 + 1])
+            .collect();
+        let mesh = Mesh2d::new(ctx, &hexagon_vertices, &hexagon_indices);
+
+        let shape_renderer = ShapeRenderer::new(ctx);
+
+        let mut shapes = ShapeMesh::new();
+        shapes
+            .fill(
+                &Path::rounded_rect(glm::vec2(-150.0, -120.0), glm::vec2(80.0, 40.0), 10.0),
+                &FillOptions {
+                    color: [0.9, 0.4, 0.2, 0.8],
+                    ..Default::default()
+                },
+            )
+            .stroke(
+                Path::new().move_to(glm::vec2(-150.0, 60.0)).cubic_to(
+                    glm::vec2(-100.0, 140.0),
+                    glm::vec2(-60.0, -20.0),
+                    glm::vec2(-10.0, 60.0),
+                ),
+                &StrokeOptions {
+                    color: [0.3, 0.8, 1.0, 1.0],
+                    width: 4.0,
+                    join: LineJoin::Round,
+                    cap: LineCap::Round,
+                    ..Default::default()
+                },
+            );
+
+        let mut post_stack = PostProcessStack::new(ctx);
+
+        post_stack.push(
+            ctx,
+            Effect::Vignette {
+                intensity: 0.8,
+                radius: 0.6,
+                softness: 0.5,
+                color: [0.0, 0.0, 0.0],
+            },
+        );
+
+        Self {
+            rend_2d,
+            debug_draw,
+            shape_renderer,
+            shapes,
+            mesh,
+            post_stack,
+            tex_key,
+        }
     }
 
     fn update(&mut self, ctx: &mut mat_engine::context::EngineContext) {
@@ -77,15 +165,39 @@ impl mat_engine::application::Application for MyApp {
 
         self.rend_2d.update(ctx);
 
+        // Outline the sample texture's instances, and point at the first one
+        let outline = DebugStyle::outlined([0.0, 1.0, 0.0, 1.0], 1.0);
+        self.debug_draw
+            .rect(glm::vec2(-5.0, -5.0), glm::vec2(5.0, 5.0), &outline);
+        self.debug_draw
+            .rect(glm::vec2(-10.0, 20.0), glm::vec2(10.0, 40.0), &outline);
+        self.debug_draw
+            .circle(glm::vec2(100.0, 100.0), 4.0, &outline);
+        self.debug_draw.arrow(
+            glm::vec2(-60.0, -40.0),
+            glm::vec2(-6.0, -6.0),
+            &DebugStyle::outlined([1.0, 0.8, 0.0, 1.0], 2.0),
+        );
+
+        if let (_, Some(mouse_pos)) = mat_engine::input::cursor::get_cursor_info(ctx) {
+            let mouse_pos = glm::vec2(mouse_pos.x as f32, mouse_pos.y as f32);
+            self.debug_draw.circle(
+                mouse_pos,
+                6.0,
+                &DebugStyle::filled([1.0, 1.0, 1.0, 0.5]).in_screen_space(),
+            );
+        }
+
         mat_engine::imgui::update(ctx);
     }
 
     fn render(&mut self, ctx: &mut mat_engine::context::EngineContext) {
         // log::warn!("RENDER START");
-        let mut frt = mat_engine::rendering::start_render(ctx).unwrap();
-
-        self.rend_2d
-            .render_sample_texture(ctx, &mut frt, self.tex_key);
+        let mut frt = match mat_engine::rendering::start_render(ctx).unwrap() {
+            Some(frt) => frt,
+            // Minimized, for example.
+            None => return,
+        };
 
         //Render imgui
 
@@ -127,7 +239,52 @@ impl mat_engine::application::Application for MyApp {
             //ui.show_demo_window(&mut false);
         });
 
-        mat_engine::imgui::render(ctx, &mut frt);
+        mat_engine::imgui::add_render_stats_panel(ctx);
+
+        let rend_2d = &mut self.rend_2d;
+        let debug_draw = &mut self.debug_draw;
+        let shape_renderer = &mut self.shape_renderer;
+        let mesh = &self.mesh;
+        shape_renderer.queue_mesh(
+            &self.shapes,
+            Instance {
+                position: glm::vec2(0.0, 0.0),
+                scale: 1.0,
+            },
+        );
+        let tex_key = self.tex_key;
+
+        let mut graph = RenderGraph::new();
+
+        let scene = graph.create_transient(TransientTargetDesc::screen_sized());
+
+        graph
+            .add_pass("scene", scene)
+            .clear([0.1, 0.2, 0.3, 1.0])
+            .execute(|ctx, target| {
+                rend_2d.render_sample_texture(ctx, target, tex_key);
+                rend_2d.render_mesh(
+                    ctx,
+                    target,
+                    mesh,
+                    tex_key,
+                    &[Instance {
+                        position: glm::vec2(120.0, -60.0),
+                        scale: 60.0,
+                    }],
+                );
+                shape_renderer.render(ctx, target, &rend_2d.camera);
+                debug_draw.render(ctx, target, &rend_2d.camera);
+            });
+
+        self.post_stack
+            .add_passes(&mut graph, scene, TargetHandle::SWAPCHAIN);
+
+        graph
+            .add_pass("imgui", TargetHandle::SWAPCHAIN)
+            .execute(|ctx, target| mat_engine::imgui::render(ctx, target));
+
+        mat_engine::rendering::execute_render_graph(ctx, &mut frt, graph).unwrap();
 
         //log::warn!("COMPLETE RENDER START");
 
@@ -138,12 +295,9 @@ impl mat_engine::application::Application for MyApp {
 }
 
 fn main() {
-    flexi_logger::Logger::with_str("mat_engine=trace,sample_sandbox=trace,warn")
-        .format(flexi_logger::colored_opt_format)
-        .start()
-        .unwrap();
-
-    log::trace!("Starting sample_sandbox");
+    // Every setting of the sample comes from the sample_sandbox.toml next to the executable (copied
+    // there by build.rs), so it may be tweaked without recompiling.
+    let config = mat_engine::EngineConfig::from_executable_dir("sample_sandbox.toml").unwrap();
 
-    mat_engine::run::<MyApp>();
+    mat_engine::run::<MyApp>(config);
 }